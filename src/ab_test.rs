@@ -0,0 +1,94 @@
+//! Instant front/back comparisons.
+//!
+//! Front/back confusion is the most common HRTF failure, and it's hard
+//! to judge while dragging an emitter around. With a
+//! [`SpatialAudioABTest`], pressing its toggle key swaps two emitters'
+//! positions in a single frame, and their directions are snapped rather
+//! than rate-limited, so the comparison is immediate.
+//!
+//! Run with `--scene=ab` for one emitter in front of the listener and
+//! one behind it. Press `A` to swap them.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::latency::LatencyCompensated;
+use crate::spatial::{SpatialAudioCommand, SpatialAudioCommandKind};
+use crate::{DemoAssets, DemoChain, ORBIT_VOLUME, spawn_default_chain};
+
+pub struct ABTestPlugin;
+
+impl Plugin for ABTestPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            swap_ab_emitters.run_if(resource_exists::<SpatialAudioABTest>),
+        );
+    }
+}
+
+/// Two emitters whose positions swap when `toggle_key` is pressed.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct SpatialAudioABTest {
+    pub emitter_a: Entity,
+    pub emitter_b: Entity,
+    pub toggle_key: KeyCode,
+}
+
+fn swap_ab_emitters(
+    test: Res<SpatialAudioABTest>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut transforms: Query<&mut Transform>,
+    mut spatial_commands: EventWriter<SpatialAudioCommand>,
+) {
+    if !input.just_pressed(test.toggle_key) {
+        return;
+    }
+
+    let Ok([mut a, mut b]) = transforms.get_many_mut([test.emitter_a, test.emitter_b]) else {
+        warn!("the A/B test emitters no longer exist");
+        return;
+    };
+    core::mem::swap(&mut a.translation, &mut b.translation);
+
+    for target in [test.emitter_a, test.emitter_b] {
+        spatial_commands.write(SpatialAudioCommand {
+            target,
+            kind: SpatialAudioCommandKind::SnapDirection,
+        });
+    }
+}
+
+/// Spawn one emitter in front of the listener and one behind it.
+pub fn spawn_ab_scene(
+    assets: Res<DemoAssets>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let mut spawn = |sample: &'static str, position: Vec3| {
+        let mut emitter = commands.spawn((
+            Mesh2d(assets.emitter_circle.clone()),
+            MeshMaterial2d(assets.emitter_material.clone()),
+            SamplePlayer::new(server.load(sample))
+                .looping()
+                .with_volume(ORBIT_VOLUME),
+            Transform::from_translation(position),
+            LatencyCompensated,
+        ));
+        spawn_default_chain(&mut emitter, &buses, *chain);
+        emitter.id()
+    };
+
+    // 2D listeners face +Y.
+    let emitter_a = spawn("divine_comedy.ogg", Vec3::new(0.0, 150.0, 0.0));
+    let emitter_b = spawn("caw.ogg", Vec3::new(0.0, -150.0, 0.0));
+
+    commands.insert_resource(SpatialAudioABTest {
+        emitter_a,
+        emitter_b,
+        toggle_key: KeyCode::KeyA,
+    });
+}
@@ -0,0 +1,210 @@
+//! Parameter automation for hands-free demonstrations.
+//!
+//! An [`Automate`] component modulates one field of a sibling
+//! component with a low-frequency oscillator or a one-shot ramp.
+
+use bevy::{
+    ecs::component::Mutable,
+    prelude::*,
+    reflect::{GetTypeRegistration, Typed},
+};
+use bevy_seedling::prelude::*;
+
+pub struct AutomationPlugin;
+
+impl Plugin for AutomationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AutomationToggleKey>()
+            .register_type::<LfoShape>()
+            .add_automation::<ReverbParam>()
+            .add_automation::<VolumeParam>();
+    }
+}
+
+/// Registers automation for an [`AutomationTarget`].
+pub trait AddAutomation {
+    fn add_automation<T: AutomationTarget>(&mut self) -> &mut Self;
+}
+
+impl AddAutomation for App {
+    fn add_automation<T: AutomationTarget>(&mut self) -> &mut Self {
+        self.register_type::<Automate<T>>()
+            .add_systems(Update, (toggle_automation::<T>, automate::<T>).chain())
+    }
+}
+
+/// A field of a component that can be automated.
+pub trait AutomationTarget:
+    Reflect + FromReflect + TypePath + Typed + GetTypeRegistration + Copy + Send + Sync + 'static
+{
+    /// The component holding the field.
+    type Component: Component<Mutability = Mutable>;
+
+    /// Write `value` to this field of `component`.
+    fn apply(self, component: &mut Self::Component, value: f32);
+}
+
+/// The key that starts and stops all automation.
+///
+/// Defaults to [`KeyCode::KeyL`].
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct AutomationToggleKey(pub KeyCode);
+
+impl Default for AutomationToggleKey {
+    fn default() -> Self {
+        Self(KeyCode::KeyL)
+    }
+}
+
+/// The waveform used by [`Automate`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum LfoShape {
+    #[default]
+    Sine,
+    Triangle,
+    /// A single rising ramp that holds its final value.
+    Ramp,
+}
+
+impl LfoShape {
+    /// Evaluate the shape at `phase` in cycles.
+    ///
+    /// Periodic shapes produce values in `[-1, 1]`, while
+    /// [`LfoShape::Ramp`] produces values in `[0, 1]`.
+    pub fn evaluate(self, phase: f32) -> f32 {
+        match self {
+            Self::Sine => (phase * core::f32::consts::TAU).sin(),
+            Self::Triangle => {
+                let phase = phase.rem_euclid(1.0);
+                if phase < 0.25 {
+                    4.0 * phase
+                } else if phase < 0.75 {
+                    2.0 - 4.0 * phase
+                } else {
+                    4.0 * phase - 4.0
+                }
+            }
+            Self::Ramp => phase.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Modulates a field of a sibling component over time.
+///
+/// The written value is `center + depth * shape(phase)`.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component)]
+pub struct Automate<T: AutomationTarget> {
+    pub target: T,
+    pub shape: LfoShape,
+    /// The modulation rate in Hz.
+    ///
+    /// For [`LfoShape::Ramp`], this is the inverse of the ramp duration.
+    pub rate: f32,
+    pub depth: f32,
+    pub center: f32,
+    /// Whether the automation is currently advancing.
+    pub running: bool,
+    phase: f32,
+}
+
+impl<T: AutomationTarget + Default> Default for Automate<T> {
+    fn default() -> Self {
+        Self::new(T::default(), LfoShape::default(), 0.1, 0.0, 0.0)
+    }
+}
+
+impl<T: AutomationTarget> Automate<T> {
+    pub fn new(target: T, shape: LfoShape, rate: f32, depth: f32, center: f32) -> Self {
+        Self {
+            target,
+            shape,
+            rate,
+            depth,
+            center,
+            running: false,
+            phase: 0.0,
+        }
+    }
+
+    /// The current automated value.
+    pub fn value(&self) -> f32 {
+        self.center + self.depth * self.shape.evaluate(self.phase)
+    }
+}
+
+fn toggle_automation<T: AutomationTarget>(
+    mut automations: Query<&mut Automate<T>>,
+    key: Res<AutomationToggleKey>,
+    input: Res<ButtonInput<KeyCode>>,
+) {
+    if !input.just_pressed(key.0) {
+        return;
+    }
+
+    for mut automation in automations.iter_mut() {
+        automation.running = !automation.running;
+    }
+}
+
+fn automate<T: AutomationTarget>(
+    mut automations: Query<(&mut Automate<T>, &mut T::Component)>,
+    time: Res<Time>,
+) {
+    for (mut automation, mut component) in automations.iter_mut() {
+        if !automation.running {
+            continue;
+        }
+
+        automation.phase += automation.rate * time.delta_secs();
+        if automation.shape != LfoShape::Ramp {
+            automation.phase = automation.phase.rem_euclid(1.0);
+        }
+
+        automation.target.apply(&mut component, automation.value());
+    }
+}
+
+/// Automatable [`FreeverbNode`] parameters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum ReverbParam {
+    #[default]
+    RoomSize,
+    Damping,
+    Width,
+}
+
+impl AutomationTarget for ReverbParam {
+    type Component = FreeverbNode;
+
+    fn apply(self, component: &mut Self::Component, value: f32) {
+        let value = value.clamp(0.0, 1.0);
+
+        match self {
+            Self::RoomSize => component.room_size = value,
+            Self::Damping => component.damping = value,
+            Self::Width => component.width = value,
+        }
+    }
+}
+
+/// Automatable [`VolumeNode`] parameters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum VolumeParam {
+    /// The volume in linear amplitude.
+    #[default]
+    Linear,
+    /// The volume in decibels.
+    Decibels,
+}
+
+impl AutomationTarget for VolumeParam {
+    type Component = VolumeNode;
+
+    fn apply(self, component: &mut Self::Component, value: f32) {
+        component.volume = match self {
+            Self::Linear => Volume::Linear(value.max(0.0)),
+            Self::Decibels => Volume::Decibels(value),
+        };
+    }
+}
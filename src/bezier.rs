@@ -0,0 +1,122 @@
+//! Emitters that follow a Bézier curve.
+//!
+//! [`Spinner`](crate::Spinner) only orbits in circles. A
+//! [`BezierEmitter`] instead traces a curve of any degree through its
+//! control points once per period, which is closer to how game entities
+//! actually move past a listener.
+//!
+//! Run with `--scene=bezier` to replace the orbiting emitter with one
+//! that swoops past the listener along a cubic curve.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::latency::LatencyCompensated;
+use crate::spatial::{SpatialAudioCommand, SpatialAudioCommandKind};
+use crate::state::AppState;
+use crate::{DemoAssets, DemoChain, spawn_default_chain};
+
+pub struct BezierEmitterPlugin;
+
+impl Plugin for BezierEmitterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, follow_bezier.run_if(in_state(AppState::Playing)));
+    }
+}
+
+/// Moves an emitter along a Bézier curve.
+///
+/// Three control points make a quadratic curve, four a cubic, and so
+/// on. A single point holds the emitter still.
+#[derive(Debug, Clone, Component)]
+pub struct BezierEmitter {
+    pub control_points: Vec<Vec2>,
+    /// Seconds to travel the curve from start to end.
+    pub period_seconds: f32,
+    /// How far along the curve the emitter is, in `[0, 1)`.
+    t: f32,
+    /// Scratch space for evaluating the curve.
+    scratch: Vec<Vec2>,
+}
+
+impl BezierEmitter {
+    pub fn new(control_points: Vec<Vec2>, period_seconds: f32) -> Self {
+        Self {
+            scratch: Vec::with_capacity(control_points.len()),
+            control_points,
+            period_seconds,
+            t: 0.0,
+        }
+    }
+
+    /// The point on the curve at `t`, using De Casteljau's algorithm.
+    ///
+    /// Returns `None` without control points.
+    pub fn evaluate(&mut self, t: f32) -> Option<Vec2> {
+        self.scratch.clear();
+        self.scratch.extend_from_slice(&self.control_points);
+
+        for len in (1..self.scratch.len()).rev() {
+            for i in 0..len {
+                self.scratch[i] = self.scratch[i].lerp(self.scratch[i + 1], t);
+            }
+        }
+
+        self.scratch.first().copied()
+    }
+}
+
+/// Spawn an emitter that swoops past the listener.
+pub fn spawn_bezier_scene(
+    assets: Res<DemoAssets>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let mut emitter = commands.spawn((
+        Mesh2d(assets.emitter_circle.clone()),
+        MeshMaterial2d(assets.emitter_material.clone()),
+        SamplePlayer::new(server.load("divine_comedy.ogg"))
+            .looping()
+            .with_volume(Volume::Decibels(-12.0)),
+        Transform::default(),
+        BezierEmitter::new(
+            vec![
+                Vec2::new(-400.0, -250.0),
+                Vec2::new(-150.0, 350.0),
+                Vec2::new(150.0, -350.0),
+                Vec2::new(400.0, 250.0),
+            ],
+            10.0,
+        ),
+        LatencyCompensated,
+    ));
+    spawn_default_chain(&mut emitter, &buses, *chain);
+}
+
+fn follow_bezier(
+    mut emitters: Query<(Entity, &mut BezierEmitter, &mut Transform)>,
+    mut spatial_commands: EventWriter<SpatialAudioCommand>,
+    time: Res<Time>,
+) {
+    for (entity, mut emitter, mut transform) in emitters.iter_mut() {
+        let period = emitter.period_seconds.max(f32::EPSILON);
+        let t = emitter.t + time.delta_secs() / period;
+        emitter.t = t.fract();
+
+        // Jumping back to the start is a teleport, not a swoop.
+        if t >= 1.0 {
+            spatial_commands.write(SpatialAudioCommand {
+                target: entity,
+                kind: SpatialAudioCommandKind::SnapDirection,
+            });
+        }
+
+        let t = emitter.t;
+        if let Some(position) = emitter.evaluate(t) {
+            transform.translation = position.extend(transform.translation.z);
+        }
+    }
+}
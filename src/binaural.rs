@@ -0,0 +1,331 @@
+//! A shared bus for the binaural signal.
+//!
+//! Every HRTF chain produces a left and a right ear, and stages like
+//! headphone EQ, crossfeed or hearing-loss compensation want both ears
+//! of the whole mix rather than one emitter at a time. The demo routes
+//! its chains into a single [`BinauralBus`] on their way to the main
+//! bus, which gives those stages one place to go.
+//!
+//! By convention, channel 0 is the left ear and channel 1 the right.
+//! The bus applies a gain and a low and high shelf to each ear, so an
+//! uneven pair of ears or headphones can be evened out. Shelves at
+//! 0 dB are skipped.
+
+use bevy::prelude::*;
+use bevy_seedling::{SeedlingSystems, prelude::*};
+use firewheel::{
+    channel_config::ChannelConfig,
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+
+use crate::biquad::{Biquad, BiquadCoefficients};
+use crate::bus::ReverbEntity;
+
+#[derive(Default)]
+pub struct BinauralBusPlugin {
+    /// The bus's starting settings.
+    pub bus: BinauralBus,
+}
+
+impl Plugin for BinauralBusPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_node::<BinauralBus>()
+            .register_type::<Decibels>()
+            .register_type::<BinauralBus>()
+            .add_systems(Startup, route_reverb_through_binaural_bus)
+            .add_systems(
+                Last,
+                route_pools_through_binaural_bus.before(SeedlingSystems::Acquire),
+            );
+
+        app.world_mut().spawn(self.bus.clone());
+    }
+}
+
+/// A gain in decibels.
+///
+/// Firewheel's [`Volume`] can't be reflected, so the bus's gains use
+/// this instead, which the inspector shows as a decibel slider.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Diff, Patch, Reflect)]
+#[reflect(Default)]
+pub struct Decibels(pub f32);
+
+impl Decibels {
+    pub fn linear(self) -> f32 {
+        Volume::Decibels(self.0).linear()
+    }
+}
+
+/// The left and right ears of every HRTF chain, before the main bus.
+#[derive(Debug, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct BinauralBus {
+    pub left_gain: Decibels,
+    pub right_gain: Decibels,
+    /// The left ear's low shelf, in decibels.
+    pub left_low_db: f32,
+    /// The left ear's high shelf, in decibels.
+    pub left_high_db: f32,
+    /// The right ear's low shelf, in decibels.
+    pub right_low_db: f32,
+    /// The right ear's high shelf, in decibels.
+    pub right_high_db: f32,
+}
+
+impl Default for BinauralBus {
+    fn default() -> Self {
+        Self {
+            left_gain: Decibels(0.0),
+            right_gain: Decibels(0.0),
+            left_low_db: 0.0,
+            left_high_db: 0.0,
+            right_low_db: 0.0,
+            right_high_db: 0.0,
+        }
+    }
+}
+
+impl BinauralBus {
+    const LOW_SHELF_FREQUENCY: f32 = 250.0;
+    const HIGH_SHELF_FREQUENCY: f32 = 4000.0;
+
+    /// Each ear's gain, left then right.
+    fn gains(&self) -> [f32; 2] {
+        [self.left_gain.linear(), self.right_gain.linear()]
+    }
+
+    /// Each ear's shelves in decibels, left then right.
+    pub fn shelves_db(&self) -> [[f32; 2]; 2] {
+        [
+            [self.left_low_db, self.left_high_db],
+            [self.right_low_db, self.right_high_db],
+        ]
+    }
+}
+
+/// Configuration for [`BinauralBus`].
+#[derive(Debug, Default, Clone, Component)]
+pub struct BinauralBusConfig;
+
+struct BinauralBusProcessor {
+    params: BinauralBus,
+    sample_rate: f32,
+    gains: [f32; 2],
+    /// Low shelf and high shelf for each ear.
+    filters: [[Biquad; 2]; 2],
+    /// Whether each ear has a shelf that isn't flat.
+    eq_active: [bool; 2],
+}
+
+impl BinauralBusProcessor {
+    fn apply_params(&mut self) {
+        self.gains = self.params.gains();
+
+        for ((ear, active), [low_db, high_db]) in self
+            .filters
+            .iter_mut()
+            .zip(&mut self.eq_active)
+            .zip(self.params.shelves_db())
+        {
+            ear[0].coefficients = BiquadCoefficients::low_shelf(
+                BinauralBus::LOW_SHELF_FREQUENCY,
+                low_db,
+                self.sample_rate,
+            );
+            ear[1].coefficients = BiquadCoefficients::high_shelf(
+                BinauralBus::HIGH_SHELF_FREQUENCY,
+                high_db,
+                self.sample_rate,
+            );
+
+            let was_active = *active;
+            *active = low_db != 0.0 || high_db != 0.0;
+
+            // Stale state would click in when the EQ returns.
+            if was_active && !*active {
+                ear.iter_mut().for_each(Biquad::reset);
+            }
+        }
+    }
+}
+
+impl AudioNode for BinauralBus {
+    type Configuration = BinauralBusConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("binaural bus")
+            .channel_config(ChannelConfig::new(2, 2))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let mut processor = BinauralBusProcessor {
+            params: self.clone(),
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            gains: [1.0; 2],
+            filters: Default::default(),
+            eq_active: [false; 2],
+        };
+        processor.apply_params();
+
+        processor
+    }
+}
+
+impl AudioNodeProcessor for BinauralBusProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        let mut changed = false;
+        events.for_each_patch::<BinauralBus>(|patch| {
+            Patch::apply(&mut self.params, patch);
+            changed = true;
+        });
+        if changed {
+            self.apply_params();
+        }
+
+        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
+            for ear in &mut self.filters {
+                ear.iter_mut().for_each(Biquad::reset);
+            }
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        for (((input, output), ear), (gain, active)) in inputs
+            .iter()
+            .zip(outputs.iter_mut())
+            .zip(&mut self.filters)
+            .zip(self.gains.into_iter().zip(self.eq_active))
+        {
+            for frame in 0..proc_info.frames {
+                let mut sample = input[frame];
+                if active {
+                    for stage in ear.iter_mut() {
+                        sample = stage.process(sample);
+                    }
+                }
+
+                output[frame] = sample * gain;
+            }
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
+
+/// Send the shared reverb through the [`BinauralBus`].
+///
+/// The bus itself is left unconnected, so it feeds the main bus.
+fn route_reverb_through_binaural_bus(
+    binaural: Single<Entity, With<BinauralBus>>,
+    reverb: Option<Res<ReverbEntity>>,
+    mut commands: Commands,
+) {
+    if let Some(reverb) = reverb {
+        commands
+            .entity(reverb.0)
+            .disconnect(MainBus)
+            .connect(*binaural);
+    }
+}
+
+/// Send every pool that carries an effect chain through the
+/// [`BinauralBus`] as it's spawned.
+///
+/// Chains end in their pool's volume node, which is the pool entity
+/// itself, so rerouting that catches every chain in the pool. The
+/// demo's chains all play through the dynamic pools bevy_seedling
+/// spawns for each new set of effects.
+fn route_pools_through_binaural_bus(
+    binaural: Single<Entity, With<BinauralBus>>,
+    pools: Query<Entity, (Added<SampleEffects>, With<PoolSize>, Without<SamplePlayer>)>,
+    mut commands: Commands,
+) {
+    for pool in pools.iter() {
+        commands.entity(pool).disconnect(MainBus).connect(*binaural);
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use bevy_seedling::{context::AudioContext, node::FirewheelNode};
+
+    use super::*;
+    use crate::null_backend::NullAudioBackend;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            SeedlingPlugin::<NullAudioBackend>::new(),
+        ));
+
+        let reverb = app.world_mut().spawn(FreeverbNode::default()).id();
+        app.insert_resource(ReverbEntity(reverb))
+            .add_plugins(BinauralBusPlugin::default());
+        app.finish();
+        app.cleanup();
+
+        app
+    }
+
+    /// Whether `from` feeds `to` in the audio graph.
+    fn connected(app: &mut App, from: Entity, to: Entity) -> bool {
+        let world = app.world_mut();
+        let from = world.get::<FirewheelNode>(from).unwrap().0;
+        let to = world.get::<FirewheelNode>(to).unwrap().0;
+
+        world.resource_mut::<AudioContext>().with(move |context| {
+            context
+                .edges()
+                .iter()
+                .any(|edge| edge.src_node == from && edge.dst_node == to)
+        })
+    }
+
+    #[test]
+    fn reverb_and_pools_feed_the_bus_only() {
+        let mut app = app();
+        app.world_mut().spawn((
+            SamplePlayer::default(),
+            sample_effects![VolumePanNode::default()],
+        ));
+        for _ in 0..4 {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        let binaural = world
+            .query_filtered::<Entity, With<BinauralBus>>()
+            .single(world)
+            .unwrap();
+        let main_bus = world
+            .query_filtered::<Entity, With<MainBus>>()
+            .single(world)
+            .unwrap();
+        let reverb = world.resource::<ReverbEntity>().0;
+        let pool = world
+            .query_filtered::<Entity, (With<SampleEffects>, With<PoolSize>)>()
+            .single(world)
+            .unwrap();
+
+        assert!(connected(&mut app, reverb, binaural));
+        assert!(!connected(&mut app, reverb, main_bus));
+        assert!(connected(&mut app, pool, binaural));
+        assert!(!connected(&mut app, pool, main_bus));
+        assert!(connected(&mut app, binaural, main_bus));
+    }
+}
@@ -0,0 +1,233 @@
+//! Feeding a renderer fixed-size blocks whatever the stream delivers.
+//!
+//! Firewheel doesn't promise the same `proc_info.frames` from one call
+//! to the next, and the web backend in particular delivers irregular
+//! blocks. A partitioned renderer expects its input in whole
+//! partitions. [`FixedBlocks`] queues input until a whole partition is
+//! ready, and plays output from a queue primed with silence.
+//!
+//! The queue is primed once, when the stream starts, with the most a
+//! stream can get ahead of the partitions: one frame short of a whole
+//! partition. So the added latency is a constant
+//! [`latency`](FixedBlocks::latency) frames whatever blocks arrive,
+//! and irregular blocks never cause a dropout or a jump in timing.
+
+use std::collections::VecDeque;
+
+/// Splits blocks of any size into blocks of `len` frames.
+#[derive(Debug, Clone)]
+pub struct FixedBlocks {
+    len: usize,
+    /// Input waiting for a whole block.
+    pending: Vec<f32>,
+    /// Scratch for the block being rendered, left then right.
+    rendered: [Vec<f32>; 2],
+    /// Rendered output waiting to be played, left then right.
+    queued: [VecDeque<f32>; 2],
+}
+
+impl FixedBlocks {
+    /// Split into blocks of `len` frames, for streams delivering
+    /// at most `max_frames` per call.
+    pub fn new(len: usize, max_frames: usize) -> Self {
+        let len = len.max(1);
+
+        let mut blocks = Self {
+            len,
+            pending: Vec::with_capacity(len),
+            rendered: [vec![0.0; len], vec![0.0; len]],
+            // At most the primed frames, a rendered block
+            // and a call's worth of frames.
+            queued: [
+                VecDeque::with_capacity(2 * len + max_frames),
+                VecDeque::with_capacity(2 * len + max_frames),
+            ],
+        };
+        blocks.reset();
+
+        blocks
+    }
+
+    /// The frames of latency added on top of the renderer's own,
+    /// for blocks of `len` frames.
+    pub fn latency(len: usize) -> usize {
+        len - 1
+    }
+
+    /// Drop anything queued and start over from silence.
+    ///
+    /// Only call this when the output is silent, since any
+    /// queued output is lost.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        for queue in &mut self.queued {
+            queue.clear();
+            queue.extend(core::iter::repeat_n(0.0, self.len - 1));
+        }
+    }
+
+    /// Render `input` into `left` and `right` a block at a time.
+    ///
+    /// `render` is called with a block of input, which it may
+    /// overwrite, and the left and right output for that block.
+    pub fn process(
+        &mut self,
+        input: &mut [f32],
+        left: &mut [f32],
+        right: &mut [f32],
+        mut render: impl FnMut(&mut [f32], &mut [f32], &mut [f32]),
+    ) {
+        let len = self.len;
+
+        for &sample in input.iter() {
+            self.pending.push(sample);
+            if self.pending.len() < len {
+                continue;
+            }
+
+            let [rendered_left, rendered_right] = &mut self.rendered;
+            render(&mut self.pending, rendered_left, rendered_right);
+            self.pending.clear();
+
+            for (queue, rendered) in self.queued.iter_mut().zip(&self.rendered) {
+                queue.extend(rendered.iter().copied());
+            }
+        }
+
+        // The primed frames mean the queues always hold a call's worth.
+        let [queued_left, queued_right] = &mut self.queued;
+        for (output, queue) in [(left, queued_left), (right, queued_right)] {
+            let n = output.len().min(queue.len());
+            for (out, sample) in output.iter_mut().zip(queue.drain(..n)) {
+                *out = sample;
+            }
+            output[n..].fill(0.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Block sizes from 1 to `max`, from a fixed seed.
+    fn block_sizes(seed: u32, max: usize) -> impl Iterator<Item = usize> {
+        let mut state = seed.max(1);
+        core::iter::from_fn(move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            Some(state as usize % max + 1)
+        })
+    }
+
+    /// Run `signal` through `blocks` in blocks of `sizes`, with a render
+    /// function that scales left and right, returning both outputs.
+    fn run(
+        blocks: &mut FixedBlocks,
+        signal: &[f32],
+        sizes: impl Iterator<Item = usize>,
+    ) -> (Vec<f32>, Vec<f32>) {
+        let (mut left, mut right) = (vec![0.0; signal.len()], vec![0.0; signal.len()]);
+        let mut start = 0;
+
+        for size in sizes {
+            if start == signal.len() {
+                break;
+            }
+            let end = (start + size).min(signal.len());
+            let mut input = signal[start..end].to_vec();
+
+            blocks.process(
+                &mut input,
+                &mut left[start..end],
+                &mut right[start..end],
+                |input, left, right| {
+                    for ((input, left), right) in input.iter().zip(left).zip(right) {
+                        *left = *input;
+                        *right = -0.5 * *input;
+                    }
+                },
+            );
+
+            start = end;
+        }
+
+        (left, right)
+    }
+
+    fn signal(len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (i as f32 * 0.37).sin() + 0.01 * i as f32)
+            .collect()
+    }
+
+    #[test]
+    fn irregular_blocks_only_add_constant_latency() {
+        let signal = signal(4096);
+
+        for (seed, len, max_frames) in [(1, 128, 512), (7, 64, 100), (99, 256, 31), (3, 1, 17)] {
+            let mut blocks = FixedBlocks::new(len, max_frames);
+            let latency = FixedBlocks::latency(len);
+            let (left, right) = run(&mut blocks, &signal, block_sizes(seed, max_frames));
+
+            assert!(left[..latency].iter().all(|sample| *sample == 0.0));
+            for frame in latency..signal.len() {
+                assert_eq!(left[frame], signal[frame - latency], "frame {frame}");
+                assert_eq!(
+                    right[frame],
+                    -0.5 * signal[frame - latency],
+                    "frame {frame}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn regular_and_irregular_blocks_agree() {
+        let signal = signal(2048);
+
+        let regular = run(
+            &mut FixedBlocks::new(128, 128),
+            &signal,
+            core::iter::repeat(128),
+        );
+        let irregular = run(
+            &mut FixedBlocks::new(128, 300),
+            &signal,
+            block_sizes(42, 300),
+        );
+
+        assert_eq!(regular, irregular);
+    }
+
+    #[test]
+    fn output_matches_block_length() {
+        let mut blocks = FixedBlocks::new(64, 200);
+
+        for size in block_sizes(5, 200).take(200) {
+            let mut input = vec![1.0; size];
+            let (mut left, mut right) = (vec![f32::NAN; size], vec![f32::NAN; size]);
+
+            blocks.process(&mut input, &mut left, &mut right, |_, left, right| {
+                left.fill(1.0);
+                right.fill(1.0);
+            });
+
+            // Every output frame is written, with no gaps left behind.
+            assert!(left.iter().chain(&right).all(|sample| sample.is_finite()));
+        }
+    }
+
+    #[test]
+    fn reset_starts_over_from_silence() {
+        let signal = signal(512);
+        let mut blocks = FixedBlocks::new(32, 50);
+        let first = run(&mut blocks, &signal, block_sizes(11, 50));
+
+        blocks.reset();
+        let second = run(&mut blocks, &signal, block_sizes(11, 50));
+
+        assert_eq!(first, second);
+    }
+}
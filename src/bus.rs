@@ -0,0 +1,143 @@
+//! Named reverb buses that effect chains can send to.
+//!
+//! Spawning an entity with [`ReverbBus`] registers it in [`ReverbBuses`]
+//! under its name, so spawn helpers can look up a send target instead of
+//! being handed an [`Entity`]. Sends tagged with [`ReverbSend`] follow
+//! their bus: they fade out when it's despawned and are pointed at its
+//! replacement, at their old volume, when a bus with the same name is
+//! spawned again.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+/// Registers an entity as the reverb bus with this name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ReverbBus(pub &'static str);
+
+impl ReverbBus {
+    /// The bus emitters send to by default.
+    pub const MAIN: Self = Self("main");
+}
+
+/// Marks a [`SendNode`] as feeding the named bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub struct ReverbSend(pub &'static str);
+
+/// Every live [`ReverbBus`], keyed by name.
+#[derive(Debug, Default, Clone, Resource)]
+pub struct ReverbBuses(HashMap<&'static str, Entity>);
+
+impl ReverbBuses {
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.0.get(name).copied()
+    }
+
+    /// A send to the named bus, or `None` if there isn't one.
+    pub fn send(&self, name: &'static str, volume: Volume) -> Option<(SendNode, ReverbSend)> {
+        let bus = self.get(name)?;
+        Some((SendNode::new(volume, bus), ReverbSend(name)))
+    }
+}
+
+/// Settings for the shared reverb spawned by
+/// [`SpatialAudioPlugin::with_reverb`](crate::spatial::SpatialAudioPlugin::with_reverb).
+///
+/// Mirrors [`FreeverbNode`]'s fields and defaults.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FreeverbConfig {
+    pub room_size: f32,
+    pub damping: f32,
+    pub width: f32,
+}
+
+impl Default for FreeverbConfig {
+    fn default() -> Self {
+        let node = FreeverbNode::default();
+        Self {
+            room_size: node.room_size,
+            damping: node.damping,
+            width: node.width,
+        }
+    }
+}
+
+impl From<FreeverbConfig> for FreeverbNode {
+    fn from(config: FreeverbConfig) -> Self {
+        Self {
+            room_size: config.room_size,
+            damping: config.damping,
+            width: config.width,
+        }
+    }
+}
+
+/// The shared reverb, registered as [`ReverbBus::MAIN`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct ReverbEntity(pub Entity);
+
+/// The send volume a [`ReverbSend`] had before its bus was despawned.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct SilencedSend(pub Volume);
+
+/// Register a new bus and point existing sends for its name at it.
+pub fn register_bus(
+    trigger: Trigger<OnInsert, ReverbBus>,
+    bus_query: Query<&ReverbBus>,
+    sends: Query<(Entity, &ReverbSend, &SendNode, Option<&SilencedSend>)>,
+    mut buses: ResMut<ReverbBuses>,
+    mut commands: Commands,
+) {
+    let entity = trigger.target();
+    let Ok(bus) = bus_query.get(entity) else {
+        return;
+    };
+
+    let replaced = buses
+        .0
+        .insert(bus.0, entity)
+        .is_some_and(|previous| previous != entity);
+
+    for (send_entity, send, node, silenced) in sends.iter() {
+        if send.0 != bus.0 || (!replaced && silenced.is_none()) {
+            continue;
+        }
+
+        let volume = silenced.map_or(node.send_volume, |silenced| silenced.0);
+        commands
+            .entity(send_entity)
+            .remove::<SilencedSend>()
+            .insert(SendNode::new(volume, entity));
+    }
+}
+
+/// Forget a despawned bus and silence the sends that fed it.
+pub fn unregister_bus(
+    trigger: Trigger<OnReplace, ReverbBus>,
+    bus_query: Query<&ReverbBus>,
+    mut sends: Query<(Entity, &ReverbSend, &mut SendNode)>,
+    mut buses: ResMut<ReverbBuses>,
+    mut commands: Commands,
+) {
+    let entity = trigger.target();
+    let Ok(bus) = bus_query.get(entity) else {
+        return;
+    };
+
+    // A bus that was already replaced has nothing left feeding it.
+    if buses.get(bus.0) != Some(entity) {
+        return;
+    }
+    buses.0.remove(bus.0);
+
+    // The send volume is smoothed, so this fades rather than clicks.
+    for (send_entity, send, mut node) in sends.iter_mut() {
+        if send.0 == bus.0 {
+            commands
+                .entity(send_entity)
+                .insert(SilencedSend(node.send_volume));
+            node.send_volume = Volume::Linear(0.0);
+        }
+    }
+}
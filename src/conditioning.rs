@@ -0,0 +1,352 @@
+//! Cleaning up the sofar dataset's impulse responses.
+//!
+//! Some community SOFA files carry a DC offset, which convolution turns
+//! into rumble, or peaks near full scale, which distort once a loud
+//! source goes through them. When the dataset loads, its filters are
+//! sampled on a grid of directions and measured with
+//! [`HrirAnalysis`], and problems are logged. [`HrirConditioning`] can
+//! then clean the filters up as nodes look them up:
+//!
+//! - DC blocking subtracts each filter's [`tail_mean`] from every tap.
+//! - Peak normalization scales the whole dataset by one gain, so its
+//!   peak lands on a target without changing level differences
+//!   between ears or directions.
+//! - Truncation drops the taps past the dataset's effective length,
+//!   which also shortens every node's convolution.
+//!
+//! Conditioning is off by default. Turn it on with the
+//! [`SofarPlugin`](crate::sofar_hrtf::SofarPlugin)'s `conditioning`,
+//! or with `--hrir-dc-block`, `--hrir-peak=DB` and `--hrir-truncate=DB`.
+//! It applies to [`SofarHrtfNode`](crate::sofar_hrtf::SofarHrtfNode)s.
+//! The ambisonic and realfft renderers use the dataset as it is.
+//!
+//! The grid is measured rather than the dataset's own measurements,
+//! which sofar doesn't expose, so a filter between grid points that
+//! rings for longer than its neighbours can lose some of its tail.
+
+use std::sync::OnceLock;
+
+use bevy::prelude::*;
+use sofar::reader::{Filter, Sofar};
+
+use crate::dataset::{HrirAnalysis, tail_mean};
+
+/// The energy threshold effective lengths are reported at
+/// when the dataset isn't truncated, in decibels.
+pub const REPORT_THRESHOLD_DB: f32 = -60.0;
+
+/// The spacing of the directions sampled for analysis, in degrees.
+const GRID_STEP_DEGREES: f32 = 15.0;
+
+/// How the sofar dataset's filters are cleaned up.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HrirConditioning {
+    /// Remove each filter's DC offset.
+    pub dc_block: bool,
+    /// Scale the dataset so its peak is this level, in dBFS.
+    pub peak_db: Option<f32>,
+    /// Drop the taps after which less than this much of a filter's
+    /// energy is left, in decibels, such as `-60.0`.
+    pub truncate_below_db: Option<f32>,
+}
+
+static CONDITIONING: OnceLock<HrirConditioning> = OnceLock::new();
+
+impl HrirConditioning {
+    /// Read `--hrir-dc-block`, `--hrir-peak=DB` and
+    /// `--hrir-truncate=DB` from the arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut conditioning = Self::default();
+
+        for arg in args {
+            if arg == "--hrir-dc-block" {
+                conditioning.dc_block = true;
+            } else if let Some(value) = arg.strip_prefix("--hrir-peak=") {
+                match value.parse() {
+                    Ok(db) => conditioning.peak_db = Some(db),
+                    Err(e) => warn!("invalid HRIR peak `{value}`: {e}"),
+                }
+            } else if let Some(value) = arg.strip_prefix("--hrir-truncate=") {
+                match value.parse::<f32>() {
+                    Ok(db) if db < 0.0 => conditioning.truncate_below_db = Some(db),
+                    Ok(_) => warn!("HRIR truncation `{value}` must be below 0 dB"),
+                    Err(e) => warn!("invalid HRIR truncation `{value}`: {e}"),
+                }
+            }
+        }
+
+        conditioning
+    }
+
+    /// Whether any conditioning is turned on.
+    pub fn is_active(&self) -> bool {
+        self.dc_block || self.peak_db.is_some() || self.truncate_below_db.is_some()
+    }
+
+    /// Use these settings when the dataset loads.
+    ///
+    /// The dataset is only loaded once, so settings
+    /// made after that, or made twice, are ignored.
+    pub fn install(self) {
+        if CONDITIONING.set(self).is_err() {
+            warn!("HRIR conditioning was already set, ignoring {self:?}");
+        }
+    }
+
+    /// The settings the dataset loads with.
+    pub fn installed() -> Self {
+        CONDITIONING.get().copied().unwrap_or_default()
+    }
+}
+
+/// The conditioning worked out for one dataset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrirConditioner {
+    dc_block: bool,
+    gain: f32,
+    len: usize,
+}
+
+impl HrirConditioner {
+    /// Leave `filter_len`-tap filters as they are.
+    pub fn identity(filter_len: usize) -> Self {
+        Self {
+            dc_block: false,
+            gain: 1.0,
+            len: filter_len,
+        }
+    }
+
+    /// The number of taps in a conditioned filter.
+    pub fn filter_len(&self) -> usize {
+        self.len
+    }
+
+    /// Condition `filter` into `out`, which should have
+    /// [`filter_len`](Self::filter_len) taps.
+    pub fn apply(&self, filter: &Filter, out: &mut Filter) {
+        self.apply_ir(&filter.left, &mut out.left);
+        self.apply_ir(&filter.right, &mut out.right);
+    }
+
+    fn apply_all(&self, filters: &[Filter]) -> Vec<Filter> {
+        filters
+            .iter()
+            .map(|filter| {
+                let mut out = Filter::new(self.len);
+                self.apply(filter, &mut out);
+                out
+            })
+            .collect()
+    }
+
+    fn apply_ir(&self, ir: &[f32], out: &mut [f32]) {
+        let dc = if self.dc_block { tail_mean(ir) } else { 0.0 };
+
+        for (out, tap) in out.iter_mut().zip(ir) {
+            *out = (tap - dc) * self.gain;
+        }
+    }
+}
+
+/// What was found in a dataset, and how it's conditioned.
+#[derive(Debug, Clone, Copy)]
+pub struct DatasetConditioning {
+    pub analysis: HrirAnalysis,
+    /// The conditioned filters, if any conditioning is on.
+    pub conditioned: Option<HrirAnalysis>,
+    pub conditioner: HrirConditioner,
+}
+
+/// Measure `sofa` and work out its conditioning.
+pub fn condition_dataset(
+    sofa: &Sofar,
+    dataset: &str,
+    conditioning: HrirConditioning,
+) -> DatasetConditioning {
+    let filter_len = sofa.filter_len();
+    let filters: Vec<_> = grid_directions()
+        .map(|direction| {
+            let mut filter = Filter::new(filter_len);
+            sofa.filter(direction.x, direction.y, direction.z, &mut filter);
+            filter
+        })
+        .collect();
+
+    condition_filters(&filters, filter_len, dataset, conditioning)
+}
+
+/// Measure `filter_len`-tap `filters` sampled from
+/// `dataset`, and work out their conditioning.
+fn condition_filters(
+    filters: &[Filter],
+    filter_len: usize,
+    dataset: &str,
+    conditioning: HrirConditioning,
+) -> DatasetConditioning {
+    let threshold_db = conditioning
+        .truncate_below_db
+        .unwrap_or(REPORT_THRESHOLD_DB);
+
+    let analysis = HrirAnalysis::measure(ears(filters), threshold_db);
+    analysis.warn(dataset);
+
+    if !conditioning.is_active() {
+        return DatasetConditioning {
+            analysis,
+            conditioned: None,
+            conditioner: HrirConditioner::identity(filter_len),
+        };
+    }
+
+    let mut conditioner = HrirConditioner {
+        dc_block: conditioning.dc_block,
+        gain: 1.0,
+        len: filter_len,
+    };
+
+    // The peak and length are measured once any DC is gone, since the
+    // offset adds to one and smears energy across the other.
+    let blocked = HrirAnalysis::measure(ears(&conditioner.apply_all(filters)), threshold_db);
+    // Silent filters have no peak to move.
+    if let Some(peak_db) = conditioning.peak_db.filter(|_| blocked.peak_db.is_finite()) {
+        conditioner.gain = 10f32.powf((peak_db - blocked.peak_db) / 20.0);
+    }
+    if conditioning.truncate_below_db.is_some() {
+        conditioner.len = blocked.effective_len.max(1);
+    }
+
+    let conditioned = HrirAnalysis::measure(ears(&conditioner.apply_all(filters)), threshold_db);
+    info!("conditioned {dataset}: {conditioned}");
+
+    DatasetConditioning {
+        analysis,
+        conditioned: Some(conditioned),
+        conditioner,
+    }
+}
+
+/// Both ears of every filter.
+fn ears(filters: &[Filter]) -> impl Iterator<Item = &[f32]> {
+    filters
+        .iter()
+        .flat_map(|filter| [&filter.left[..], &filter.right[..]])
+}
+
+/// Directions every [`GRID_STEP_DEGREES`] in azimuth and elevation.
+fn grid_directions() -> impl Iterator<Item = Vec3> {
+    let steps = (360.0 / GRID_STEP_DEGREES) as usize;
+    let elevations = (180.0 / GRID_STEP_DEGREES) as usize;
+
+    (0..=elevations).flat_map(move |elevation| {
+        let elevation = (elevation as f32 * GRID_STEP_DEGREES - 90.0).to_radians();
+        (0..steps).map(move |azimuth| {
+            let azimuth = (azimuth as f32 * GRID_STEP_DEGREES).to_radians();
+            Vec3::new(
+                elevation.cos() * azimuth.cos(),
+                elevation.cos() * azimuth.sin(),
+                elevation.sin(),
+            )
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::energy_len;
+
+    const LEN: usize = 256;
+
+    /// A decaying resonance peaking at `peak`, with the right
+    /// ear at half the left's level, both offset by `dc`.
+    fn filter(peak: f32, dc: f32) -> Filter {
+        let mut filter = Filter::new(LEN);
+        for (n, (left, right)) in filter.left.iter_mut().zip(&mut filter.right).enumerate() {
+            let tap = peak * 0.95f32.powi(n as i32) * (0.3 * n as f32).cos();
+            *left = tap + dc;
+            *right = 0.5 * tap + dc;
+        }
+
+        filter
+    }
+
+    fn condition(filters: &[Filter], conditioning: HrirConditioning) -> DatasetConditioning {
+        condition_filters(filters, LEN, "synthetic", conditioning)
+    }
+
+    #[test]
+    fn detects_dc_offset_and_near_clipping() {
+        let clean = condition(&[filter(0.5, 0.0)], HrirConditioning::default()).analysis;
+        assert!(!clean.has_dc_offset());
+        assert!(!clean.near_clipping());
+
+        let offset = condition(&[filter(0.5, 0.05)], HrirConditioning::default()).analysis;
+        assert!(offset.has_dc_offset());
+        assert!((offset.dc_offset - 0.05).abs() < 1e-3);
+
+        let hot = condition(&[filter(0.99, 0.0)], HrirConditioning::default()).analysis;
+        assert!(hot.near_clipping());
+    }
+
+    #[test]
+    fn inactive_conditioning_is_the_identity() {
+        let result = condition(&[filter(0.99, 0.05)], HrirConditioning::default());
+
+        assert_eq!(result.conditioned, None);
+        assert_eq!(result.conditioner, HrirConditioner::identity(LEN));
+    }
+
+    #[test]
+    fn dc_block_removes_the_offset() {
+        let conditioning = HrirConditioning {
+            dc_block: true,
+            ..default()
+        };
+        let result = condition(&[filter(0.5, 0.05), filter(0.3, -0.02)], conditioning);
+
+        let conditioned = result.conditioned.unwrap();
+        assert!(!conditioned.has_dc_offset());
+        assert!(conditioned.dc_offset < 1e-6);
+    }
+
+    #[test]
+    fn peak_normalization_keeps_level_differences() {
+        let filters = [filter(0.99, 0.0), filter(0.4, 0.0)];
+        let conditioning = HrirConditioning {
+            peak_db: Some(-6.0),
+            ..default()
+        };
+        let result = condition(&filters, conditioning);
+
+        assert!((result.conditioned.unwrap().peak_db + 6.0).abs() < 1e-3);
+
+        // One gain for the dataset, so ears and directions keep their ratios.
+        let mut loud = Filter::new(LEN);
+        let mut quiet = Filter::new(LEN);
+        result.conditioner.apply(&filters[0], &mut loud);
+        result.conditioner.apply(&filters[1], &mut quiet);
+        assert!((loud.right[0] / loud.left[0] - 0.5).abs() < 1e-5);
+        assert!((quiet.left[0] / loud.left[0] - 0.4 / 0.99).abs() < 1e-5);
+    }
+
+    #[test]
+    fn truncation_keeps_the_energy_above_the_threshold() {
+        let filters = [filter(0.5, 0.0)];
+        let conditioning = HrirConditioning {
+            truncate_below_db: Some(-60.0),
+            ..default()
+        };
+        let result = condition(&filters, conditioning);
+
+        let len = result.conditioner.filter_len();
+        assert_eq!(len, energy_len(&filters[0].left, -60.0));
+        assert!(len < LEN);
+
+        let mut truncated = Filter::new(len);
+        result.conditioner.apply(&filters[0], &mut truncated);
+        let energy = |ir: &[f32]| ir.iter().map(|tap| tap * tap).sum::<f32>();
+        let kept = energy(&truncated.left) / energy(&filters[0].left);
+        assert!(kept > 1.0 - 1e-6);
+    }
+}
@@ -0,0 +1,338 @@
+//! Loading [`SpatialAudioPlugin`] settings from a TOML file.
+//!
+//! Project-wide audio settings can live in a file rather than in
+//! code. [`SpatialAudioPlugin::from_toml`] reads one, starting from
+//! [`SpatialAudioPlugin::default`] and overriding whatever the file
+//! sets. Every key is optional:
+//!
+//! ```toml
+//! # The default sampler pool, as on the command line.
+//! pool_size = "4..=32"
+//! spawn_default_pool = true
+//! # "bevy-default", "z-up-right-handed" or "web-audio".
+//! axis_convention = "bevy-default"
+//! # "identity", "exaggerate-rear", "widen-front", or
+//! # `[heard, rendered]` azimuth points in degrees.
+//! azimuth_remap = [[30.0, 50.0], [60.0, 85.0]]
+//!
+//! # What to ask the audio device for. It may pick something
+//! # else, and the browser always picks for itself.
+//! [stream]
+//! sample_rate = 48000
+//! block_len = 512
+//!
+//! # How often moving emitters send direction updates.
+//! [direction_update]
+//! min_interval = 0.0   # seconds
+//! max_interval = 0.1   # seconds
+//! step_degrees = 1.0
+//!
+//! # A shared reverb. Without this table, there's none.
+//! [reverb]
+//! room_size = 0.85
+//! damping = 0.9
+//! width = 0.9
+//! ```
+//!
+//! The HRTF datasets are embedded in the binary, so there's no key
+//! for a SOFA path. Unknown keys are an error rather than ignored, so
+//! a typo doesn't quietly leave a setting at its default.
+//!
+//! The repository's `audio_config.toml` is a complete example.
+
+use std::{fmt, io, num::NonZeroU32};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::bus::FreeverbConfig;
+use crate::remap::AzimuthRemap;
+use crate::spatial::{AxisConvention, DirectionUpdateRate, SpatialAudioPlugin, parse_pool_size};
+
+/// What to ask the audio device for.
+///
+/// Either setting left unset is up to the device.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StreamSettings {
+    pub sample_rate: Option<NonZeroU32>,
+    /// The number of frames processed per block.
+    pub block_len: Option<NonZeroU32>,
+}
+
+/// Why a config file couldn't be loaded.
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The file exists but couldn't be read.
+    Io { path: String, source: io::Error },
+    /// The file isn't valid TOML, has an unknown key,
+    /// or has a value of the wrong type.
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+    /// A value has the right type but isn't one the plugin accepts.
+    Invalid {
+        key: &'static str,
+        value: String,
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "can't read {path}: {source}"),
+            Self::Parse { path, source } => write!(f, "can't parse {path}: {source}"),
+            Self::Invalid {
+                key,
+                value,
+                expected,
+            } => write!(f, "invalid `{key}` `{value}`: expected {expected}"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Parse { source, .. } => Some(source),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+/// The file's schema. Unset keys leave the plugin's defaults alone.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    pool_size: Option<String>,
+    spawn_default_pool: Option<bool>,
+    axis_convention: Option<String>,
+    azimuth_remap: Option<RemapSetting>,
+    stream: StreamTable,
+    direction_update: Option<DirectionUpdateTable>,
+    reverb: Option<ReverbTable>,
+}
+
+/// A preset's name, or the points of a custom curve.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RemapSetting {
+    Preset(String),
+    Points(Vec<[f32; 2]>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct StreamTable {
+    sample_rate: Option<NonZeroU32>,
+    block_len: Option<NonZeroU32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DirectionUpdateTable {
+    min_interval: Option<f32>,
+    max_interval: Option<f32>,
+    step_degrees: Option<f32>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ReverbTable {
+    room_size: Option<f32>,
+    damping: Option<f32>,
+    width: Option<f32>,
+}
+
+impl SpatialAudioPlugin {
+    /// Build the plugin from the TOML file at `path`.
+    ///
+    /// A missing file gives [`SpatialAudioPlugin::default`]. See
+    /// [`config`](crate::config) for the keys.
+    pub fn from_toml(path: &str) -> Result<Self, ConfigError> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                info!("no audio config at {path}, using the defaults");
+                return Ok(Self::default());
+            }
+            Err(source) => {
+                return Err(ConfigError::Io {
+                    path: path.into(),
+                    source,
+                });
+            }
+        };
+
+        let file: ConfigFile = toml::from_str(&text).map_err(|source| ConfigError::Parse {
+            path: path.into(),
+            source,
+        })?;
+
+        let plugin = file.apply(Self::default())?;
+        info!("loaded audio config from {path}");
+
+        Ok(plugin)
+    }
+}
+
+impl ConfigFile {
+    fn apply(self, mut plugin: SpatialAudioPlugin) -> Result<SpatialAudioPlugin, ConfigError> {
+        if let Some(value) = self.pool_size {
+            let pool_size = parse_pool_size(&value).ok_or(ConfigError::Invalid {
+                key: "pool_size",
+                value,
+                expected: "`MIN..=MAX` with MIN no larger than MAX",
+            })?;
+            plugin = plugin.with_pool_size(pool_size);
+        }
+
+        if let Some(spawn_default_pool) = self.spawn_default_pool {
+            plugin = plugin.with_spawn_default_pool(spawn_default_pool);
+        }
+
+        if let Some(value) = self.axis_convention {
+            let axis_convention = match value.as_str() {
+                "bevy-default" => AxisConvention::BevyDefault,
+                "z-up-right-handed" => AxisConvention::ZUpRightHanded,
+                "web-audio" => AxisConvention::WebAudioStyle,
+                _ => {
+                    return Err(ConfigError::Invalid {
+                        key: "axis_convention",
+                        value,
+                        expected: "`bevy-default`, `z-up-right-handed` or `web-audio`",
+                    });
+                }
+            };
+            plugin = plugin.with_axis_convention(axis_convention);
+        }
+
+        if let Some(setting) = self.azimuth_remap {
+            plugin.azimuth_remap = match setting {
+                RemapSetting::Preset(value) => AzimuthRemap::PRESETS
+                    .iter()
+                    .find(|(name, _)| name.replace(' ', "-") == value)
+                    .map(|(_, preset)| preset())
+                    .ok_or(ConfigError::Invalid {
+                        key: "azimuth_remap",
+                        value,
+                        expected: "`identity`, `exaggerate-rear` or `widen-front`",
+                    })?,
+                RemapSetting::Points(points) => {
+                    AzimuthRemap::new(points.iter().copied().map(Vec2::from)).map_err(|e| {
+                        ConfigError::Invalid {
+                            key: "azimuth_remap",
+                            value: e.to_string(),
+                            expected: "points between 0 and 180 that increase in both azimuths",
+                        }
+                    })?
+                }
+            };
+        }
+
+        plugin.stream = StreamSettings {
+            sample_rate: self.stream.sample_rate,
+            block_len: self.stream.block_len,
+        };
+
+        if let Some(table) = self.direction_update {
+            let default = DirectionUpdateRate::default();
+            let rate = DirectionUpdateRate {
+                min_interval: table.min_interval.unwrap_or(default.min_interval),
+                max_interval: table.max_interval.unwrap_or(default.max_interval),
+                step_radians: table
+                    .step_degrees
+                    .map_or(default.step_radians, f32::to_radians),
+            };
+
+            // Written so that NaN fails each check.
+            let ordered = rate.min_interval >= 0.0 && rate.max_interval >= rate.min_interval;
+            if !ordered {
+                return Err(ConfigError::Invalid {
+                    key: "direction_update",
+                    value: format!("{}..={}", rate.min_interval, rate.max_interval),
+                    expected: "`min_interval` of at least 0 and no larger than `max_interval`",
+                });
+            }
+            let stepped = rate.step_radians > 0.0;
+            if !stepped {
+                return Err(ConfigError::Invalid {
+                    key: "direction_update",
+                    value: format!("step_degrees = {}", rate.step_radians.to_degrees()),
+                    expected: "`step_degrees` greater than 0",
+                });
+            }
+
+            plugin = plugin.with_direction_update_rate(rate);
+        }
+
+        if let Some(table) = self.reverb {
+            let default = FreeverbConfig::default();
+            plugin.reverb = Some(FreeverbConfig {
+                room_size: table.room_size.unwrap_or(default.room_size),
+                damping: table.damping.unwrap_or(default.damping),
+                width: table.width.unwrap_or(default.width),
+            });
+        }
+
+        Ok(plugin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(text: &str) -> Result<SpatialAudioPlugin, ConfigError> {
+        let file: ConfigFile = toml::from_str(text).unwrap();
+        file.apply(SpatialAudioPlugin::default())
+    }
+
+    fn rejects_direction_update(text: &str) -> bool {
+        matches!(
+            apply(text),
+            Err(ConfigError::Invalid {
+                key: "direction_update",
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn accepts_a_valid_direction_update() {
+        let plugin = apply(
+            "[direction_update]\nmin_interval = 0.01\nmax_interval = 0.2\nstep_degrees = 2.0",
+        )
+        .unwrap();
+
+        assert_eq!(plugin.direction_update_rate.min_interval, 0.01);
+        assert_eq!(plugin.direction_update_rate.max_interval, 0.2);
+        assert_eq!(plugin.direction_update_rate.step_radians, 2f32.to_radians());
+    }
+
+    #[test]
+    fn rejects_nan_intervals() {
+        assert!(rejects_direction_update(
+            "[direction_update]\nmin_interval = nan"
+        ));
+        assert!(rejects_direction_update(
+            "[direction_update]\nmax_interval = nan"
+        ));
+        assert!(rejects_direction_update(
+            "[direction_update]\nmin_interval = -0.1"
+        ));
+        assert!(rejects_direction_update(
+            "[direction_update]\nmin_interval = 0.5\nmax_interval = 0.1"
+        ));
+    }
+
+    #[test]
+    fn rejects_steps_that_are_not_positive() {
+        for step in ["0.0", "-1.0", "nan"] {
+            let text = format!("[direction_update]\nstep_degrees = {step}");
+            assert!(rejects_direction_update(&text), "{step}");
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Two-band processing for the HRTF nodes.
+//!
+//! Low frequencies carry little spectral HRTF information, so the
+//! nodes can optionally split their input with a Linkwitz-Riley
+//! crossover, convolve only the high band, and render the low band
+//! with a simple delay-and-gain panner. Linkwitz-Riley bands sum to
+//! an allpass response, so the split itself is phase-coherent.
+
+use bevy::prelude::*;
+
+use crate::biquad::{Biquad, BiquadCoefficients};
+
+/// How an HRTF node renders low frequencies.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+pub enum LowFrequencyMode {
+    /// The full band is convolved with the HRIR.
+    #[default]
+    Full,
+    /// Frequencies below the crossover only receive interaural
+    /// time and level differences.
+    SimplePan,
+}
+
+/// The supported crossover range in Hz.
+pub const CROSSOVER_RANGE: core::ops::RangeInclusive<f32> = 200.0..=700.0;
+
+/// A fourth-order Linkwitz-Riley crossover.
+#[derive(Debug, Clone)]
+pub struct Crossover {
+    low: [Biquad; 2],
+    high: [Biquad; 2],
+}
+
+impl Crossover {
+    pub fn new(frequency: f32, sample_rate: f32) -> Self {
+        let frequency = frequency.clamp(*CROSSOVER_RANGE.start(), *CROSSOVER_RANGE.end());
+        let q = core::f32::consts::FRAC_1_SQRT_2;
+
+        let low = Biquad::new(BiquadCoefficients::low_pass(frequency, q, sample_rate));
+        let high = Biquad::new(BiquadCoefficients::high_pass(frequency, q, sample_rate));
+
+        Self {
+            low: [low; 2],
+            high: [high; 2],
+        }
+    }
+
+    /// Split a sample into its low and high bands.
+    pub fn split(&mut self, sample: f32) -> (f32, f32) {
+        let low = self.low[0].process(sample);
+        let low = self.low[1].process(low);
+        let high = self.high[0].process(sample);
+        let high = self.high[1].process(high);
+
+        (low, high)
+    }
+}
+
+/// A per-ear delay and gain panner.
+#[derive(Debug, Clone)]
+pub struct SimplePan {
+    delay_line: Vec<f32>,
+    write: usize,
+    delays: [usize; 2],
+    gains: [f32; 2],
+}
+
+impl SimplePan {
+    /// Create a panner that can delay up to `max_delay` samples.
+    pub fn new(max_delay: usize) -> Self {
+        Self {
+            delay_line: vec![0.0; max_delay + 1],
+            write: 0,
+            delays: [0; 2],
+            gains: [1.0; 2],
+        }
+    }
+
+    /// Derive delays and gains from a pair of impulse responses.
+    ///
+    /// Each ear's delay is the position of its response peak, and its
+    /// gain is the response's DC gain, so the low band lines up with
+    /// the convolved high band.
+    #[cfg(feature = "sofar")]
+    pub fn set_from_hrir(&mut self, left: &[f32], right: &[f32]) {
+        let max_delay = self.delay_line.len() - 1;
+
+        for (ear, hrir) in [left, right].into_iter().enumerate() {
+            let peak = hrir
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                .map(|(i, _)| i)
+                .unwrap_or_default();
+
+            self.delays[ear] = peak.min(max_delay);
+            self.gains[ear] = hrir.iter().sum();
+        }
+    }
+
+    /// Derive delays and gains from a listener-relative direction,
+    /// where +X points to the listener's right.
+    ///
+    /// The interaural time difference follows Woodworth's spherical
+    /// head model. `base_delay` is added to both ears to match the
+    /// latency of the convolved band.
+    #[cfg(any(feature = "fyrox", feature = "lite"))]
+    pub fn set_from_direction(&mut self, direction: Vec3, sample_rate: f32, base_delay: usize) {
+        const HEAD_RADIUS: f32 = 0.0875;
+        const SPEED_OF_SOUND: f32 = 343.0;
+        // A gentle level difference; low frequencies diffract around the head.
+        const MAX_ILD_DB: f32 = 3.0;
+
+        let lateral = direction.normalize_or_zero().x.clamp(-1.0, 1.0);
+        let angle = lateral.abs().asin();
+        let itd = HEAD_RADIUS / SPEED_OF_SOUND * (angle + angle.sin());
+        let itd_samples = (itd * sample_rate).round() as usize;
+
+        let max_delay = self.delay_line.len() - 1;
+        let near = 10f32.powf(MAX_ILD_DB * lateral.abs() / 40.0);
+        let far = near.recip();
+
+        // The ear facing the source hears it first and louder.
+        let (near_ear, far_ear) = if lateral >= 0.0 { (1, 0) } else { (0, 1) };
+        self.delays[near_ear] = base_delay.min(max_delay);
+        self.delays[far_ear] = (base_delay + itd_samples).min(max_delay);
+        self.gains[near_ear] = near;
+        self.gains[far_ear] = far;
+    }
+
+    /// Pan a single sample, returning the left and right outputs.
+    pub fn process(&mut self, sample: f32) -> (f32, f32) {
+        let len = self.delay_line.len();
+        self.delay_line[self.write] = sample;
+
+        let read = |ear: usize| {
+            let index = (self.write + len - self.delays[ear]) % len;
+            self.delay_line[index] * self.gains[ear]
+        };
+        let output = (read(0), read(1));
+
+        self.write = (self.write + 1) % len;
+
+        output
+    }
+}
+
+/// The low-band state held by a node in [`LowFrequencyMode::SimplePan`].
+#[derive(Debug, Clone)]
+pub struct LowBandSplit {
+    pub crossover: Crossover,
+    pub pan: SimplePan,
+    /// Staging for the low band of the current block.
+    pub low: Vec<f32>,
+}
+
+impl LowBandSplit {
+    /// Create the split if `mode` calls for one.
+    pub fn new(
+        mode: LowFrequencyMode,
+        frequency: f32,
+        sample_rate: f32,
+        max_delay: usize,
+        buffer_size: usize,
+    ) -> Option<Self> {
+        match mode {
+            LowFrequencyMode::Full => None,
+            LowFrequencyMode::SimplePan => Some(Self {
+                crossover: Crossover::new(frequency, sample_rate),
+                pan: SimplePan::new(max_delay),
+                low: Vec::with_capacity(buffer_size),
+            }),
+        }
+    }
+}
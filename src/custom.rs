@@ -0,0 +1,98 @@
+//! User-defined HRTF processors.
+//!
+//! [`SpatialAudioPlugin::with_custom_hrtf_processor`] has every
+//! [`SofarHrtfNode`] build its processor with a user factory instead of
+//! the built-in renderer, for example to render an HRTF measured from
+//! the user's own head. Everything on the ECS side stays the same: the
+//! node's direction is still computed and patched each frame, and the
+//! custom processor receives those patches as the built-in one would.
+//!
+//! A factory can also be set on a single node's
+//! [`HrtfConfig::custom_processor`]. The plugin's factory only fills in
+//! configurations that don't have one.
+//!
+//! [`SpatialAudioPlugin::with_custom_hrtf_processor`]: crate::spatial::SpatialAudioPlugin::with_custom_hrtf_processor
+//! [`SofarHrtfNode`]: crate::sofar_hrtf::SofarHrtfNode
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use firewheel::{
+    StreamInfo,
+    event::NodeEventList,
+    node::{AudioNodeProcessor, ProcBuffers, ProcInfo, ProcessStatus},
+};
+
+use crate::sofar_hrtf::HrtfConfig;
+
+type Factory = dyn Fn(&HrtfConfig, &StreamInfo) -> Box<dyn AudioNodeProcessor> + Send + Sync;
+
+/// Builds the processor for a sofar node in place of the built-in one.
+#[derive(Clone)]
+pub struct HrtfProcessorFactory(Arc<Factory>);
+
+impl core::fmt::Debug for HrtfProcessorFactory {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("HrtfProcessorFactory")
+            .finish_non_exhaustive()
+    }
+}
+
+impl HrtfProcessorFactory {
+    pub fn new<T: AudioNodeProcessor + 'static>(
+        factory: impl Fn(&HrtfConfig, &StreamInfo) -> T + Send + Sync + 'static,
+    ) -> Self {
+        Self(Arc::new(
+            move |config: &HrtfConfig, stream_info: &StreamInfo| -> Box<dyn AudioNodeProcessor> {
+                Box::new(factory(config, stream_info))
+            },
+        ))
+    }
+
+    /// Build a processor for a node with `config` on the stream `stream_info`.
+    pub fn construct(
+        &self,
+        config: &HrtfConfig,
+        stream_info: &StreamInfo,
+    ) -> Box<dyn AudioNodeProcessor> {
+        (self.0)(config, stream_info)
+    }
+}
+
+/// Either a backend's processor or one built by an [`HrtfProcessorFactory`].
+pub enum MaybeCustom<P> {
+    Builtin(P),
+    Custom(Box<dyn AudioNodeProcessor>),
+}
+
+impl<P: AudioNodeProcessor> AudioNodeProcessor for MaybeCustom<P> {
+    fn process(
+        &mut self,
+        buffers: ProcBuffers,
+        proc_info: &ProcInfo,
+        events: NodeEventList,
+    ) -> ProcessStatus {
+        match self {
+            Self::Builtin(processor) => processor.process(buffers, proc_info, events),
+            Self::Custom(processor) => processor.process(buffers, proc_info, events),
+        }
+    }
+}
+
+/// Give every [`HrtfConfig`] without a factory of its own `factory`.
+///
+/// Configurations are filled in as they're inserted, before
+/// the node's processor is built.
+pub fn use_custom_processor(app: &mut App, factory: HrtfProcessorFactory) {
+    app.add_observer(
+        move |trigger: Trigger<OnInsert, HrtfConfig>, mut configs: Query<&mut HrtfConfig>| {
+            let Ok(mut config) = configs.get_mut(trigger.target()) else {
+                return;
+            };
+
+            if config.custom_processor.is_none() {
+                config.custom_processor = Some(factory.clone());
+            }
+        },
+    );
+}
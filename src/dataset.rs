@@ -0,0 +1,223 @@
+//! Reporting what each HRTF dataset costs.
+//!
+//! A dataset's filter length sets how much work and memory every node
+//! using it needs, so a long-filter SOFA file can be many times more
+//! expensive per emitter than a short one. Once a backend has loaded
+//! its dataset, the numbers are logged and kept in [`DatasetInfo`],
+//! along with a projection for the current node count.
+//!
+//! Byte counts are estimates of the buffers each node allocates, not
+//! measurements, but they scale the way the real cost does.
+//!
+//! Backends that can sample their filters also report an
+//! [`HrirAnalysis`], which catches datasets with a DC offset or peaks
+//! near full scale before they're blamed on the renderer.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::spatial::HrtfNode;
+
+/// The cost of one loaded dataset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetStats {
+    pub dataset: &'static str,
+    /// Taps per ear in each filter.
+    pub filter_len: usize,
+    /// Measured directions, if the backend exposes them.
+    pub measurements: Option<usize>,
+    /// Memory every node needs for its own filter, renderer and
+    /// staging buffers, in bytes.
+    pub bytes_per_node: usize,
+    /// Memory shared by every node using the dataset, in bytes.
+    pub shared_bytes: usize,
+    /// How many nodes currently use the dataset.
+    pub nodes: usize,
+    /// The filters as loaded, if the backend can sample them.
+    pub hrir: Option<HrirAnalysis>,
+    /// The filters after conditioning, if any was applied.
+    pub conditioned_hrir: Option<HrirAnalysis>,
+}
+
+impl DatasetStats {
+    /// The memory the dataset costs with the current node count, in bytes.
+    pub fn projected_bytes(&self) -> usize {
+        self.shared_bytes + self.bytes_per_node * self.nodes
+    }
+}
+
+impl core::fmt::Display for DatasetStats {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        const KIB: f32 = 1024.0;
+
+        write!(f, "{}: {} taps", self.dataset, self.filter_len)?;
+        if let Some(measurements) = self.measurements {
+            write!(f, ", {measurements} measurements")?;
+        }
+        write!(
+            f,
+            ", {:.0} KiB per node, {:.0} KiB for {} nodes",
+            self.bytes_per_node as f32 / KIB,
+            self.projected_bytes() as f32 / KIB,
+            self.nodes,
+        )?;
+
+        if let Some(hrir) = &self.hrir {
+            write!(f, "; {hrir}")?;
+        }
+        if let Some(conditioned) = &self.conditioned_hrir {
+            write!(f, "; conditioned to {conditioned}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Peaks at or above this level, in dBFS, are reported as near clipping.
+#[cfg(feature = "sofar")]
+pub const NEAR_CLIPPING_DB: f32 = -0.5;
+
+/// DC offsets at or above this level are reported.
+#[cfg(feature = "sofar")]
+pub const DC_OFFSET_WARNING: f32 = 1e-3;
+
+/// The level and length of a dataset's filters, measured over both
+/// ears of a set of sampled directions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrirAnalysis {
+    /// The largest tap, in dBFS.
+    pub peak_db: f32,
+    /// The largest DC offset, measured as the mean of a filter's last
+    /// quarter, where a clean response has decayed to nothing.
+    pub dc_offset: f32,
+    /// The most taps any filter needs to keep all but
+    /// `threshold_db` of its energy.
+    pub effective_len: usize,
+    /// The energy threshold `effective_len` was measured at, in decibels.
+    pub threshold_db: f32,
+}
+
+#[cfg(feature = "sofar")]
+impl HrirAnalysis {
+    /// Measure every impulse response in `irs`.
+    pub fn measure<'a>(irs: impl IntoIterator<Item = &'a [f32]>, threshold_db: f32) -> Self {
+        let mut peak = 0f32;
+        let mut dc_offset = 0f32;
+        let mut effective_len = 0;
+
+        for ir in irs {
+            peak = ir.iter().fold(peak, |peak, tap| peak.max(tap.abs()));
+            dc_offset = dc_offset.max(tail_mean(ir).abs());
+            effective_len = effective_len.max(energy_len(ir, threshold_db));
+        }
+
+        Self {
+            peak_db: 20.0 * peak.log10(),
+            dc_offset,
+            effective_len,
+            threshold_db,
+        }
+    }
+
+    /// Whether the peak is at or near full scale.
+    pub fn near_clipping(&self) -> bool {
+        self.peak_db >= NEAR_CLIPPING_DB
+    }
+
+    /// Whether the filters carry a noticeable DC offset.
+    pub fn has_dc_offset(&self) -> bool {
+        self.dc_offset >= DC_OFFSET_WARNING
+    }
+
+    /// Log a warning for each problem found in `dataset`.
+    pub fn warn(&self, dataset: &str) {
+        if self.near_clipping() {
+            warn!(
+                "{dataset} peaks at {:.1} dBFS and may distort after convolution",
+                self.peak_db
+            );
+        }
+        if self.has_dc_offset() {
+            warn!(
+                "{dataset} has a DC offset of {:.4}, which convolution turns into rumble",
+                self.dc_offset
+            );
+        }
+    }
+}
+
+impl core::fmt::Display for HrirAnalysis {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "peak {:.1} dBFS, DC {:.4}, {} taps above {:.0} dB",
+            self.peak_db, self.dc_offset, self.effective_len, self.threshold_db
+        )
+    }
+}
+
+/// The mean of the last quarter of `ir`.
+#[cfg(feature = "sofar")]
+pub fn tail_mean(ir: &[f32]) -> f32 {
+    let tail = &ir[ir.len() - ir.len() / 4..];
+    if tail.is_empty() {
+        return 0.0;
+    }
+
+    tail.iter().sum::<f32>() / tail.len() as f32
+}
+
+/// The number of taps from the start of `ir` that hold all
+/// but `threshold_db` of its energy.
+#[cfg(feature = "sofar")]
+pub fn energy_len(ir: &[f32], threshold_db: f32) -> usize {
+    let total: f32 = ir.iter().map(|tap| tap * tap).sum();
+    let limit = total * 10f32.powf(threshold_db / 10.0);
+
+    let mut tail = 0.0;
+    for (index, tap) in ir.iter().enumerate().rev() {
+        tail += tap * tap;
+        if tail > limit {
+            return index + 1;
+        }
+    }
+
+    0
+}
+
+/// The cost of every loaded dataset, keyed by dataset name.
+#[derive(Debug, Default, Resource)]
+pub struct DatasetInfo(pub HashMap<&'static str, DatasetStats>);
+
+/// An HRTF node that can report its dataset's cost.
+pub trait HrtfDataset: HrtfNode {
+    /// The dataset's cost with no nodes, once a node has loaded it.
+    fn dataset_stats() -> Option<DatasetStats>;
+}
+
+/// Record `N`'s dataset once it's loaded, and keep its node count current.
+pub fn update_dataset_info<N: HrtfDataset>(
+    mut info: ResMut<DatasetInfo>,
+    nodes: Query<(), With<N>>,
+) {
+    let Some(mut stats) = N::dataset_stats() else {
+        return;
+    };
+    let count = nodes.iter().count();
+
+    match info.0.get(stats.dataset) {
+        Some(existing) if existing.nodes == count => {}
+        Some(_) => {
+            if let Some(existing) = info.0.get_mut(stats.dataset) {
+                existing.nodes = count;
+                debug!("{existing}");
+            }
+        }
+        None => {
+            stats.nodes = count;
+            info!("{stats}");
+            info.0.insert(stats.dataset, stats);
+        }
+    }
+}
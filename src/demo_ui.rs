@@ -0,0 +1,216 @@
+//! An on-screen control panel for the demo.
+//!
+//! Every control writes to the same components and resources the
+//! keyboard shortcuts and systems use, so the panel is just another
+//! way of driving the ECS.
+
+use bevy::prelude::*;
+use bevy_egui::{EguiContextPass, EguiContexts, EguiPlugin, egui};
+use bevy_seedling::prelude::*;
+
+use crate::binaural::BinauralBus;
+use crate::remap::AzimuthRemap;
+use crate::spatial_audio::SpatialAudioNodes;
+use crate::strip::ChannelStrip;
+use crate::throttle::PendingEmitters;
+use crate::transition::TransitionSceneExt;
+use crate::{DemoChain, SpinPeriod, Spinner, direction_to_az_el, spawn_n};
+
+pub struct SpatialAudioDemoUI;
+
+impl Plugin for SpatialAudioDemoUI {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(EguiPlugin {
+            enable_multipass_for_primary_context: true,
+        })
+        .add_systems(EguiContextPass, control_panel);
+    }
+}
+
+/// The backend compiled into this build.
+///
+/// Backends are chosen with cargo features, so the panel can
+/// only report the active one.
+const BACKEND: &str = if cfg!(feature = "lite") {
+    "simple pan (lite)"
+} else if cfg!(feature = "sofar") {
+    "sofar"
+} else if cfg!(feature = "fyrox") {
+    "fyrox"
+} else if cfg!(feature = "realfft-backend") {
+    "realfft"
+} else {
+    "none"
+};
+
+fn control_panel(
+    mut contexts: EguiContexts,
+    mut period: ResMut<SpinPeriod>,
+    chain: Res<DemoChain>,
+    emitters: Query<(Entity, &SampleEffects, Option<&ChannelStrip>), With<Spinner>>,
+    mut reverbs: Query<&mut FreeverbNode>,
+    mut ears: Query<&mut BinauralBus>,
+    mut remap: ResMut<AzimuthRemap>,
+    mut nodes: SpatialAudioNodes,
+    mut bypass: Local<bool>,
+    #[cfg(any(feature = "sofar", feature = "fyrox"))] datasets: Option<
+        Res<crate::dataset::DatasetInfo>,
+    >,
+    mut commands: Commands,
+) {
+    let Some(ctx) = contexts.try_ctx_mut() else {
+        return;
+    };
+
+    egui::Window::new("Spatial audio").show(ctx, |ui| {
+        ui.label(format!("backend: {BACKEND}"));
+        #[cfg(any(feature = "sofar", feature = "fyrox"))]
+        for stats in datasets.iter().flat_map(|datasets| datasets.0.values()) {
+            ui.label(stats.to_string());
+        }
+        ui.label(format!("chain: {:?} (Tab to cycle)", *chain));
+
+        ui.add(egui::Slider::new(&mut period.0, 2.0..=60.0).text("seconds per orbit"));
+
+        let current = emitters.iter().count();
+        let mut count = current;
+        ui.add(egui::Slider::new(&mut count, 0..=128).text("emitters"));
+
+        if count != current {
+            let chain = *chain;
+            commands.transition_scene(move |commands| {
+                commands.queue(move |world: &mut World| {
+                    let emitters: Vec<_> = world
+                        .query_filtered::<Entity, With<Spinner>>()
+                        .iter(world)
+                        .collect();
+                    for entity in emitters {
+                        world.despawn(entity);
+                    }
+
+                    spawn_n(&mut world.resource_mut::<PendingEmitters>(), chain, count);
+                });
+            });
+        }
+
+        for mut reverb in reverbs.iter_mut() {
+            let mut room_size = reverb.room_size;
+            ui.add(egui::Slider::new(&mut room_size, 0.0..=1.0).text("room size"));
+
+            if room_size != reverb.room_size {
+                reverb.room_size = room_size;
+            }
+        }
+
+        for mut bus in ears.iter_mut() {
+            ui.collapsing("ears", |ui| {
+                let mut edited = bus.clone();
+                for (name, gain, low, high) in [
+                    (
+                        "left",
+                        &mut edited.left_gain,
+                        &mut edited.left_low_db,
+                        &mut edited.left_high_db,
+                    ),
+                    (
+                        "right",
+                        &mut edited.right_gain,
+                        &mut edited.right_low_db,
+                        &mut edited.right_high_db,
+                    ),
+                ] {
+                    ui.add(
+                        egui::Slider::new(&mut gain.0, -24.0..=6.0).text(format!("{name} gain dB")),
+                    );
+
+                    ui.add(
+                        egui::Slider::new(low, -12.0..=12.0).text(format!("{name} low shelf dB")),
+                    );
+                    ui.add(
+                        egui::Slider::new(high, -12.0..=12.0).text(format!("{name} high shelf dB")),
+                    );
+                }
+
+                // Only patch the bus when a slider moved.
+                if edited.left_gain != bus.left_gain
+                    || edited.right_gain != bus.right_gain
+                    || edited.shelves_db() != bus.shelves_db()
+                {
+                    *bus = edited;
+                }
+            });
+        }
+
+        ui.collapsing("azimuth remap", |ui| {
+            ui.horizontal(|ui| {
+                for (name, preset) in AzimuthRemap::PRESETS {
+                    let preset = preset();
+                    let selected = preset == *remap;
+                    if ui.selectable_label(selected, name).clicked() && !selected {
+                        *remap = preset;
+                    }
+                }
+            });
+
+            azimuth_remap_plot(ui, &remap);
+        });
+
+        if ui.checkbox(&mut bypass, "bypass HRTF").changed() {
+            for effect in emitters.iter().flat_map(|(_, effects, _)| effects.iter()) {
+                if let Some(mut node) = nodes.get_mut(effect) {
+                    node.set_bypass(*bypass);
+                }
+            }
+        }
+
+        ui.separator();
+
+        for (entity, effects, strip) in emitters.iter() {
+            // Emitters spawned with a channel strip name their HRTF node.
+            let node = match strip.and_then(|strip| strip.get("hrtf")) {
+                Some(hrtf) => nodes.get(hrtf),
+                None => effects.iter().find_map(|effect| nodes.get(effect)),
+            };
+            let Some(node) = node else {
+                continue;
+            };
+
+            let (azimuth, _) = direction_to_az_el(node.direction());
+
+            ui.label(format!("{entity}: {azimuth:.0}°"));
+        }
+    });
+}
+
+/// Plot heard azimuth against rendered azimuth, from front to back.
+fn azimuth_remap_plot(ui: &mut egui::Ui, remap: &AzimuthRemap) {
+    const SIZE: f32 = 120.0;
+    const STEPS: usize = 90;
+
+    let (response, painter) = ui.allocate_painter(egui::vec2(SIZE, SIZE), egui::Sense::hover());
+    let rect = response.rect;
+    let to_screen = |heard: f32, rendered: f32| {
+        egui::pos2(
+            rect.left() + heard / 180.0 * rect.width(),
+            rect.bottom() - rendered / 180.0 * rect.height(),
+        )
+    };
+
+    painter.line_segment(
+        [to_screen(0.0, 0.0), to_screen(180.0, 180.0)],
+        egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+    );
+
+    let curve = (0..=STEPS)
+        .map(|step| {
+            let heard = 180.0 * step as f32 / STEPS as f32;
+            to_screen(heard, remap.remap_azimuth(heard))
+        })
+        .collect();
+    painter.add(egui::Shape::line(
+        curve,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE),
+    ));
+
+    ui.label("heard azimuth → rendered, front to back");
+}
@@ -0,0 +1,128 @@
+//! Frame-exact runs.
+//!
+//! Bevy normally advances time by however long each frame took, so two
+//! runs with the same `--seed` still move emitters, automation and
+//! direction updates by slightly different amounts each frame. With
+//! `--fixed-timestep=HZ`, every frame advances real and virtual time by
+//! exactly `1 / HZ` seconds instead, so the parameter changes sent to
+//! the audio graph depend only on the frame count and the seed.
+//!
+//! Systems that affect audio should read [`Time`] rather than the wall
+//! clock, draw randomness from an [`RngStream`](crate::rng::RngStream),
+//! and not depend on the order entities or hash maps are iterated in.
+
+use std::time::Duration;
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+/// Read `--fixed-timestep=HZ` from the arguments.
+///
+/// Without it, time follows the wall clock.
+pub fn time_strategy_from_args(args: impl IntoIterator<Item = String>) -> TimeUpdateStrategy {
+    let mut strategy = TimeUpdateStrategy::Automatic;
+
+    for arg in args {
+        if let Some(value) = arg.strip_prefix("--fixed-timestep=") {
+            match value.parse::<f64>() {
+                Ok(hz) if hz > 0.0 => {
+                    info!("advancing time by a fixed 1/{hz} s per frame");
+                    strategy =
+                        TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / hz));
+                }
+                _ => warn!("invalid fixed timestep `{value}`: expected a positive rate in Hz"),
+            }
+        }
+    }
+
+    strategy
+}
+
+#[cfg(all(test, feature = "sofar"))]
+mod tests {
+    use core::f32::consts::TAU;
+
+    use bevy_seedling::prelude::*;
+
+    use super::*;
+    use crate::remap::AzimuthRemap;
+    use crate::rng::DemoRng;
+    use crate::sofar_hrtf::SofarHrtfNode;
+    use crate::spatial::{
+        AxisConvention, DirectionPatches, DirectionUpdateRate, update_hrtf_effects,
+    };
+    use crate::{SpeedCurve, SpinPeriod, Spinner, spinner};
+
+    const FRAMES: u32 = 120;
+
+    /// The direction every emitter's node holds after each of
+    /// [`FRAMES`] frames, with `--seed=seed --fixed-timestep=60`.
+    ///
+    /// The wall-clock length of each frame follows `frame_sleep`.
+    fn directions(seed: u64, frame_sleep: impl Fn(u32) -> Duration) -> Vec<[u32; 3]> {
+        let args = [format!("--seed={seed}"), "--fixed-timestep=60".into()];
+
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, TransformPlugin))
+            .insert_resource(DemoRng::from_args(args.clone()))
+            .insert_resource(time_strategy_from_args(args))
+            .insert_resource(SpinPeriod(4.0))
+            .init_resource::<DirectionUpdateRate>()
+            .init_resource::<AxisConvention>()
+            .init_resource::<AzimuthRemap>()
+            .init_resource::<DirectionPatches>()
+            .add_systems(Update, spinner)
+            .add_systems(Last, update_hrtf_effects::<SofarHrtfNode>);
+        #[cfg(feature = "direction-events")]
+        app.add_event::<crate::spatial::HrtfDirectionChanged>()
+            .init_resource::<crate::spatial::HrtfEventThreshold>();
+
+        // Two listeners the same distance from the origin, so
+        // emitters crossing it have to break a tie.
+        for x in [-1.0, 1.0] {
+            app.world_mut()
+                .spawn((SpatialListener3D, Transform::from_xyz(x, 0.0, 0.0)));
+        }
+
+        let mut rng = app.world().resource::<DemoRng>().stream("spinners");
+        let mut nodes = Vec::new();
+        for _ in 0..4 {
+            let mut spinner = Spinner::new(rng.range(0.0, TAU));
+            spinner.radius = rng.range(0.5, 10.0);
+            let curve = SpeedCurve::new(vec![(0.0, 1.0), (rng.range(0.5, 2.0), 3.0), (2.0, 1.0)]);
+
+            let emitter = app
+                .world_mut()
+                .spawn((spinner, curve, Transform::default()))
+                .id();
+            nodes.push(
+                app.world_mut()
+                    .spawn((SofarHrtfNode::default(), EffectOf(emitter)))
+                    .id(),
+            );
+        }
+
+        let mut directions = Vec::new();
+        for frame in 0..FRAMES {
+            std::thread::sleep(frame_sleep(frame));
+            app.update();
+
+            for node in &nodes {
+                let direction = app.world().get::<SofarHrtfNode>(*node).unwrap().direction;
+                directions.push(direction.to_array().map(f32::to_bits));
+            }
+        }
+
+        directions
+    }
+
+    #[test]
+    fn fixed_timestep_runs_repeat_exactly() {
+        let first = directions(7, |_| Duration::ZERO);
+        // Uneven frames on the wall clock don't matter.
+        let second = directions(7, |frame| Duration::from_millis(u64::from(frame % 3)));
+        assert_eq!(first, second);
+
+        // The directions really do follow the seed.
+        assert_ne!(first, directions(8, |_| Duration::ZERO));
+    }
+}
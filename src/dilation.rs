@@ -0,0 +1,132 @@
+//! Slow motion.
+//!
+//! [`TimeDilation`] scales [`Time<Virtual>`], which drives every
+//! moving emitter, path and automation, and the direction update
+//! schedule. Directions are worked out from those dilated positions,
+//! so what's heard slows down with what's seen.
+//!
+//! With [`DilationPitch::Drops`], sample players also play back at the
+//! dilated speed, so pitched content drops with time like a slowed
+//! tape. With [`DilationPitch::Preserved`], the default, only motion
+//! slows. Choose with `--dilation-pitch=drops` or
+//! `--dilation-pitch=preserved`.
+//!
+//! Press `H` to ramp between normal speed and [`SLOW_MOTION`] over
+//! [`RAMP_SECONDS`]. The ramp runs on real time, and playback speed
+//! follows it a frame at a time rather than jumping.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+pub struct TimeDilationPlugin {
+    pub pitch: DilationPitch,
+}
+
+impl Plugin for TimeDilationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.pitch)
+            .init_resource::<TimeDilation>()
+            .init_resource::<DilationTarget>()
+            .add_systems(
+                Update,
+                (
+                    toggle_slow_motion,
+                    ramp_dilation,
+                    apply_playback_speed.run_if(resource_equals(DilationPitch::Drops)),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// How fast game time runs relative to real time.
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+pub struct TimeDilation(pub f32);
+
+impl Default for TimeDilation {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Whether dilation changes the pitch of sample playback.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum DilationPitch {
+    /// Samples play at the dilated speed, so their pitch follows it.
+    Drops,
+    /// Samples play at their normal speed, and only motion slows.
+    #[default]
+    Preserved,
+}
+
+impl DilationPitch {
+    /// Read `--dilation-pitch=drops` or `--dilation-pitch=preserved` from the arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut pitch = Self::default();
+
+        for arg in args {
+            match arg.strip_prefix("--dilation-pitch=") {
+                Some("drops") => pitch = Self::Drops,
+                Some("preserved") => pitch = Self::Preserved,
+                Some(other) => {
+                    warn!("unknown dilation pitch `{other}`, expected `drops` or `preserved`")
+                }
+                None => {}
+            }
+        }
+
+        pitch
+    }
+}
+
+/// The dilation `H` slows time to.
+pub const SLOW_MOTION: f32 = 0.25;
+
+/// How long a ramp between normal speed and [`SLOW_MOTION`] takes, in seconds.
+pub const RAMP_SECONDS: f32 = 0.5;
+
+/// The dilation [`TimeDilation`] is ramping toward.
+#[derive(Debug, Clone, Copy, Resource)]
+struct DilationTarget(f32);
+
+impl Default for DilationTarget {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Press `H` to ramp into or out of slow motion.
+fn toggle_slow_motion(input: Res<ButtonInput<KeyCode>>, mut target: ResMut<DilationTarget>) {
+    if input.just_pressed(KeyCode::KeyH) {
+        target.0 = if target.0 < 1.0 { 1.0 } else { SLOW_MOTION };
+        info!("time dilation: {}", target.0);
+    }
+}
+
+/// Move the dilation toward its target and apply it to virtual time.
+fn ramp_dilation(
+    target: Res<DilationTarget>,
+    mut dilation: ResMut<TimeDilation>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+    real_time: Res<Time<Real>>,
+) {
+    if dilation.0 != target.0 {
+        let step = (1.0 - SLOW_MOTION) / RAMP_SECONDS * real_time.delta_secs();
+        dilation.0 += (target.0 - dilation.0).clamp(-step, step);
+    }
+
+    if virtual_time.relative_speed() != dilation.0 {
+        virtual_time.set_relative_speed(dilation.0);
+    }
+}
+
+/// Play samples back at the dilated speed.
+fn apply_playback_speed(dilation: Res<TimeDilation>, mut players: Query<&mut PlaybackSettings>) {
+    let speed = dilation.0 as f64;
+
+    for mut settings in players.iter_mut() {
+        if settings.speed != speed {
+            settings.speed = speed;
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Downmixing multichannel input to the mono signal HRTF nodes spatialize.
+//!
+//! Averaging every channel equally is fine for stereo, but wrong for
+//! surround stems: the center carries dialogue and should dominate, the
+//! surrounds should sit lower, and the LFE channel shouldn't be
+//! spatialized at all. [`ChannelLayout`] picks a weight per channel.
+//!
+//! The surround weights follow the ITU-R BS.775 stereo downmix
+//! (center and surrounds at -3 dB, LFE dropped), folded to mono.
+//! Channels are expected in WAV/SMPTE order.
+
+use std::{fmt, sync::Arc};
+
+use firewheel::channel_config::NonZeroChannelCount;
+
+const MINUS_3_DB: f32 = core::f32::consts::FRAC_1_SQRT_2;
+
+/// How an HRTF node's input channels are weighted into mono.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ChannelLayout {
+    /// Pick the standard layout for the configured channel count.
+    #[default]
+    Auto,
+    Mono,
+    /// Left, right.
+    Stereo,
+    /// Front left, front right, back left, back right.
+    Quad,
+    /// Left, right, center, LFE, surround left, surround right.
+    Surround5_1,
+    /// Left, right, center, LFE, back left, back right,
+    /// side left, side right.
+    Surround7_1,
+    /// One weight per input channel.
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "the demo's stems all have standard layouts")
+    )]
+    Custom(Arc<[f32]>),
+}
+
+impl ChannelLayout {
+    /// The standard layout with `channels` channels, if there is one.
+    pub fn for_channels(channels: u32) -> Option<Self> {
+        match channels {
+            1 => Some(Self::Mono),
+            2 => Some(Self::Stereo),
+            4 => Some(Self::Quad),
+            6 => Some(Self::Surround5_1),
+            8 => Some(Self::Surround7_1),
+            _ => None,
+        }
+    }
+
+    /// The per-channel downmix weights.
+    ///
+    /// Returns `None` for [`ChannelLayout::Auto`].
+    pub fn weights(&self) -> Option<Arc<[f32]>> {
+        let side = 0.5 * MINUS_3_DB;
+
+        let weights: &[f32] = match self {
+            Self::Auto => return None,
+            Self::Custom(weights) => return Some(weights.clone()),
+            Self::Mono => &[1.0],
+            Self::Stereo => &[0.5, 0.5],
+            Self::Quad => &[0.25, 0.25, 0.25, 0.25],
+            Self::Surround5_1 => &[0.5, 0.5, MINUS_3_DB, 0.0, side, side],
+            Self::Surround7_1 => &[0.5, 0.5, MINUS_3_DB, 0.0, side, side, side, side],
+        };
+
+        Some(weights.into())
+    }
+}
+
+/// Why a [`ChannelLayout`] can't downmix a node's input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownmixError {
+    /// [`ChannelLayout::Auto`] has no standard layout for this count.
+    NoLayout { channels: u32 },
+    /// The layout has a different number of channels than the input.
+    ChannelMismatch { layout: usize, channels: u32 },
+}
+
+impl fmt::Display for DownmixError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoLayout { channels } => write!(
+                f,
+                "no standard layout has {channels} channels; set `channel_layout` to \
+                 `ChannelLayout::Custom` with one weight per channel"
+            ),
+            Self::ChannelMismatch { layout, channels } => write!(
+                f,
+                "the channel layout has {layout} channels, but the node takes {channels}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DownmixError {}
+
+/// Weighted downmix to mono.
+#[derive(Debug, Clone)]
+pub struct Downmix {
+    weights: Arc<[f32]>,
+}
+
+impl Downmix {
+    /// The downmix for `layout` with `channels` inputs.
+    pub fn new(
+        layout: &ChannelLayout,
+        channels: NonZeroChannelCount,
+    ) -> Result<Self, DownmixError> {
+        let channels = channels.get().get();
+        let weights = layout
+            .weights()
+            .or_else(|| ChannelLayout::for_channels(channels)?.weights())
+            .ok_or(DownmixError::NoLayout { channels })?;
+
+        if weights.len() != channels as usize {
+            return Err(DownmixError::ChannelMismatch {
+                layout: weights.len(),
+                channels,
+            });
+        }
+
+        Ok(Self { weights })
+    }
+
+    /// An equal-weight average of `channels` inputs.
+    pub fn average(channels: NonZeroChannelCount) -> Self {
+        let channels = channels.get().get();
+
+        Self {
+            weights: vec![1.0 / channels as f32; channels as usize].into(),
+        }
+    }
+
+    /// [`Downmix::new`], logging the error and averaging instead on failure.
+    pub fn new_or_average(layout: &ChannelLayout, channels: NonZeroChannelCount) -> Self {
+        Self::new(layout, channels).unwrap_or_else(|e| {
+            bevy::log::error!("invalid downmix, averaging the inputs instead: {e}");
+            Self::average(channels)
+        })
+    }
+
+    /// The downmixed sample at `frame`.
+    pub fn sample(&self, inputs: &[&[f32]], frame: usize) -> f32 {
+        inputs
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(channel, weight)| channel[frame] * weight)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(count: u32) -> NonZeroChannelCount {
+        NonZeroChannelCount::new(count).unwrap()
+    }
+
+    /// How much of each input channel reaches the mono output, found by
+    /// feeding a ramp through one channel at a time.
+    fn measured_gains(downmix: &Downmix, count: usize) -> Vec<f32> {
+        let ramp: Vec<f32> = (1..=8).map(|i| i as f32 / 8.0).collect();
+        let silence = vec![0.0; ramp.len()];
+
+        (0..count)
+            .map(|channel| {
+                let inputs: Vec<&[f32]> = (0..count)
+                    .map(|i| {
+                        if i == channel {
+                            &ramp[..]
+                        } else {
+                            &silence[..]
+                        }
+                    })
+                    .collect();
+                let output: Vec<f32> = (0..ramp.len())
+                    .map(|frame| downmix.sample(&inputs, frame))
+                    .collect();
+
+                // Every frame is scaled alike, so the gain is the same throughout.
+                let gain = output[0] / ramp[0];
+                for (out, input) in output.iter().zip(&ramp) {
+                    assert!((out - input * gain).abs() < 1e-6);
+                }
+                gain
+            })
+            .collect()
+    }
+
+    fn assert_gains(layout: ChannelLayout, expected: &[f32]) {
+        let count = expected.len();
+        let downmix = Downmix::new(&layout, channels(count as u32)).unwrap();
+        let gains = measured_gains(&downmix, count);
+
+        for (channel, (gain, expected)) in gains.iter().zip(expected).enumerate() {
+            assert!(
+                (gain - expected).abs() < 1e-6,
+                "{layout:?} channel {channel}: {gain}, expected {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn surround_layouts_weight_each_channel() {
+        let side = 0.5 * MINUS_3_DB;
+
+        assert_gains(ChannelLayout::Quad, &[0.25; 4]);
+        assert_gains(
+            ChannelLayout::Surround5_1,
+            &[0.5, 0.5, MINUS_3_DB, 0.0, side, side],
+        );
+        assert_gains(
+            ChannelLayout::Surround7_1,
+            &[0.5, 0.5, MINUS_3_DB, 0.0, side, side, side, side],
+        );
+    }
+
+    #[test]
+    fn auto_picks_the_layout_by_channel_count() {
+        for (count, layout) in [
+            (4, ChannelLayout::Quad),
+            (6, ChannelLayout::Surround5_1),
+            (8, ChannelLayout::Surround7_1),
+        ] {
+            let auto = Downmix::new(&ChannelLayout::Auto, channels(count)).unwrap();
+            let explicit = Downmix::new(&layout, channels(count)).unwrap();
+            assert_eq!(auto.weights, explicit.weights);
+        }
+    }
+
+    #[test]
+    fn lfe_is_never_spatialized() {
+        for count in [6, 8] {
+            let downmix = Downmix::new(&ChannelLayout::Auto, channels(count)).unwrap();
+            let rumble = [1.0; 4];
+            let silence = [0.0; 4];
+            let inputs: Vec<&[f32]> = (0..count)
+                .map(|i| if i == 3 { &rumble[..] } else { &silence[..] })
+                .collect();
+
+            assert!((0..4).all(|frame| downmix.sample(&inputs, frame) == 0.0));
+        }
+    }
+
+    #[test]
+    fn unusable_layouts_fall_back_to_averaging() {
+        assert_eq!(
+            Downmix::new(&ChannelLayout::Auto, channels(3)).unwrap_err(),
+            DownmixError::NoLayout { channels: 3 }
+        );
+        assert_eq!(
+            Downmix::new(&ChannelLayout::Surround5_1, channels(4)).unwrap_err(),
+            DownmixError::ChannelMismatch {
+                layout: 6,
+                channels: 4
+            }
+        );
+
+        for (layout, count) in [(ChannelLayout::Auto, 3), (ChannelLayout::Surround5_1, 4)] {
+            let downmix = Downmix::new_or_average(&layout, channels(count));
+            let gains = measured_gains(&downmix, count as usize);
+            let average = 1.0 / count as f32;
+            assert!(gains.iter().all(|gain| (gain - average).abs() < 1e-6));
+        }
+    }
+
+    #[test]
+    fn custom_weights_cover_unusual_counts() {
+        let layout = ChannelLayout::Custom([0.5, 0.25, 0.25].into());
+        assert_gains(layout, &[0.5, 0.25, 0.25]);
+    }
+}
@@ -0,0 +1,206 @@
+//! Earphone insertion compensation.
+//!
+//! In-ear monitors seal the ear canal, which boosts low frequencies
+//! (the occlusion effect) and shifts the canal resonance upward,
+//! trading some high-frequency energy for it. Placing this node after
+//! an HRTF node roughly models that change in the effective response.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::EffectOf;
+use firewheel::{
+    channel_config::ChannelConfig,
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+
+use crate::biquad::{Biquad, BiquadCoefficients};
+use crate::validate::Spatializer;
+
+/// Models the response change caused by inserting an earphone.
+///
+/// Applies a low-shelf boost below 1 kHz and a high-frequency
+/// roll-off, both derived from the insertion depth.
+#[derive(Debug, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct EarphoneInsertionFilter {
+    /// The insertion depth into the ear canal, in millimeters.
+    pub insertion_depth_mm: f32,
+}
+
+impl Default for EarphoneInsertionFilter {
+    fn default() -> Self {
+        EarphoneInsertion::Normal.into()
+    }
+}
+
+/// Typical earphone insertion depths.
+///
+/// Values are approximate, drawn from occlusion-effect
+/// measurements of foam and silicone tips.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EarphoneInsertion {
+    /// The tip sits at the entrance of the canal, around 4 mm deep.
+    Shallow,
+    /// A typical fit, around 10 mm deep.
+    Normal,
+    /// A deep fit reaching the bony part of the canal, around 16 mm.
+    Deep,
+}
+
+impl EarphoneInsertion {
+    /// Parse `shallow`, `normal` or `deep`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "shallow" => Some(Self::Shallow),
+            "normal" => Some(Self::Normal),
+            "deep" => Some(Self::Deep),
+            _ => None,
+        }
+    }
+}
+
+impl From<EarphoneInsertion> for EarphoneInsertionFilter {
+    fn from(value: EarphoneInsertion) -> Self {
+        let insertion_depth_mm = match value {
+            EarphoneInsertion::Shallow => 4.0,
+            EarphoneInsertion::Normal => 10.0,
+            EarphoneInsertion::Deep => 16.0,
+        };
+
+        Self { insertion_depth_mm }
+    }
+}
+
+/// The fit spatial chains are heard through.
+///
+/// While this resource exists, every new effect chain with a
+/// [`Spatializer`] gets an [`EarphoneInsertionFilter`] at its end.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct EarphoneFit(pub EarphoneInsertion);
+
+/// Append an [`EarphoneInsertionFilter`] to new spatial chains.
+pub fn fit_earphones(
+    spatializers: Query<&EffectOf, Added<Spatializer>>,
+    fit: Res<EarphoneFit>,
+    mut commands: Commands,
+) {
+    for EffectOf(emitter) in spatializers.iter() {
+        commands.spawn((EarphoneInsertionFilter::from(fit.0), EffectOf(*emitter)));
+    }
+}
+
+impl EarphoneInsertionFilter {
+    const SHELF_FREQUENCY: f32 = 1000.0;
+    const ROLL_OFF_FREQUENCY: f32 = 8000.0;
+
+    /// The low-shelf boost in decibels.
+    ///
+    /// The occlusion effect is strongest for shallow fits, where the
+    /// cartilaginous canal wall radiates into the sealed volume, and
+    /// weakens as the tip reaches the bony canal.
+    pub fn low_shelf_db(&self) -> f32 {
+        let depth = self.insertion_depth_mm.clamp(0.0, 20.0);
+        (8.0 - depth * 0.3).max(0.0)
+    }
+
+    /// The high-frequency roll-off in decibels.
+    pub fn high_roll_off_db(&self) -> f32 {
+        let depth = self.insertion_depth_mm.clamp(0.0, 20.0);
+        -depth * 0.25
+    }
+
+    fn coefficients(&self, sample_rate: f32) -> [BiquadCoefficients; 2] {
+        [
+            BiquadCoefficients::low_shelf(Self::SHELF_FREQUENCY, self.low_shelf_db(), sample_rate),
+            BiquadCoefficients::high_shelf(
+                Self::ROLL_OFF_FREQUENCY,
+                self.high_roll_off_db(),
+                sample_rate,
+            ),
+        ]
+    }
+}
+
+/// Configuration for [`EarphoneInsertionFilter`].
+#[derive(Debug, Default, Clone, Component)]
+pub struct EarphoneConfig;
+
+struct EarphoneProcessor {
+    sample_rate: f32,
+    /// Low shelf and high shelf for each ear.
+    filters: [[Biquad; 2]; 2],
+}
+
+impl EarphoneProcessor {
+    fn set_filter(&mut self, filter: &EarphoneInsertionFilter) {
+        let coefficients = filter.coefficients(self.sample_rate);
+
+        for ear in &mut self.filters {
+            for (stage, coefficients) in ear.iter_mut().zip(coefficients) {
+                stage.coefficients = coefficients;
+            }
+        }
+    }
+}
+
+impl AudioNode for EarphoneInsertionFilter {
+    type Configuration = EarphoneConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("earphone insertion filter")
+            .channel_config(ChannelConfig::new(2, 2))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let mut processor = EarphoneProcessor {
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            filters: Default::default(),
+        };
+        processor.set_filter(self);
+
+        processor
+    }
+}
+
+impl AudioNodeProcessor for EarphoneProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        events.for_each_patch::<EarphoneInsertionFilter>(
+            |EarphoneInsertionFilterPatch::InsertionDepthMm(insertion_depth_mm)| {
+                self.set_filter(&EarphoneInsertionFilter { insertion_depth_mm });
+            },
+        );
+
+        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
+            for ear in &mut self.filters {
+                ear.iter_mut().for_each(Biquad::reset);
+            }
+
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        for ((input, output), ear) in inputs.iter().zip(outputs.iter_mut()).zip(&mut self.filters) {
+            for frame in 0..proc_info.frames {
+                let mut sample = input[frame];
+                for stage in ear.iter_mut() {
+                    sample = stage.process(sample);
+                }
+
+                output[frame] = sample;
+            }
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
@@ -0,0 +1,125 @@
+//! A scene that isolates elevation.
+//!
+//! Run with `--scene=elevation` to replace the orbiting emitter with one
+//! held directly in front of the listener that sweeps from
+//! [`MIN_ELEVATION`] up to [`MAX_ELEVATION`] and back. The current
+//! elevation is shown in the HUD, and a short tick plays each time the
+//! emitter passes its highest point.
+
+use std::f32::consts::TAU;
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::latency::LatencyCompensated;
+use crate::{DemoAssets, DemoChain, az_el_to_direction, spawn_default_chain};
+
+/// The lowest elevation of the sweep, in degrees.
+pub const MIN_ELEVATION: f32 = -60.0;
+
+/// The highest elevation of the sweep, in degrees.
+pub const MAX_ELEVATION: f32 = 90.0;
+
+pub struct ElevationScenePlugin;
+
+impl Plugin for ElevationScenePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (oscillate_elevation, update_readout).chain());
+    }
+}
+
+/// Sweeps an emitter in elevation in front of a listener at the origin.
+#[derive(Debug, Clone, Component)]
+pub struct ElevationOscillator {
+    /// Seconds for one sweep up and back down.
+    pub period: f32,
+    /// The distance from the listener.
+    pub distance: f32,
+    phase: f32,
+    elevation: f32,
+}
+
+impl ElevationOscillator {
+    pub fn new(period: f32, distance: f32) -> Self {
+        Self {
+            period,
+            distance,
+            phase: 0.0,
+            elevation: MIN_ELEVATION,
+        }
+    }
+}
+
+#[derive(Component)]
+struct ElevationReadout;
+
+/// Spawn the elevation emitter and its readout.
+pub fn spawn_elevation_scene(
+    assets: Res<DemoAssets>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let mut emitter = commands.spawn((
+        Mesh2d(assets.emitter_circle.clone()),
+        MeshMaterial2d(assets.emitter_material.clone()),
+        SamplePlayer::new(server.load("divine_comedy.ogg"))
+            .looping()
+            .with_volume(Volume::Decibels(-12.0)),
+        Transform::default(),
+        ElevationOscillator::new(8.0, 200.0),
+        LatencyCompensated,
+    ));
+    spawn_default_chain(&mut emitter, &buses, *chain);
+
+    commands.spawn((
+        ElevationReadout,
+        Text::default(),
+        TextFont::from_font_size(48.0),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(16.0),
+            right: Val::Px(16.0),
+            ..default()
+        },
+    ));
+}
+
+fn oscillate_elevation(
+    mut emitters: Query<(&mut ElevationOscillator, &mut Transform)>,
+    server: Res<AssetServer>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (mut oscillator, mut transform) in emitters.iter_mut() {
+        let previous = oscillator.phase;
+        oscillator.phase =
+            (oscillator.phase + time.delta_secs() / oscillator.period.max(f32::EPSILON)).fract();
+
+        // The top of the sweep is halfway through the cycle.
+        if previous < 0.5 && oscillator.phase >= 0.5 {
+            commands.spawn(SamplePlayer::new(server.load("caw.ogg")));
+        }
+
+        let blend = (1.0 - (oscillator.phase * TAU).cos()) / 2.0;
+        oscillator.elevation = MIN_ELEVATION + (MAX_ELEVATION - MIN_ELEVATION) * blend;
+
+        // The listener's frame matches the node frame in 2D.
+        transform.translation = az_el_to_direction(0.0, oscillator.elevation) * oscillator.distance;
+    }
+}
+
+fn update_readout(
+    emitters: Query<&ElevationOscillator>,
+    mut readout: Query<&mut Text, With<ElevationReadout>>,
+) {
+    let Some(oscillator) = emitters.iter().next() else {
+        return;
+    };
+
+    for mut text in readout.iter_mut() {
+        text.0 = format!("elevation {:+.0}°", oscillator.elevation);
+    }
+}
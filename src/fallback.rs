@@ -0,0 +1,106 @@
+//! Degraded-mode fallback for HRTF nodes that fail to initialize.
+//!
+//! Building a renderer can fail, for example when the dataset's filter
+//! length doesn't fit the partition length. Rather than panicking while
+//! the graph is being built, a backend logs the error and substitutes a
+//! [`PassthroughProcessor`], which downmixes the input to both ears
+//! unprocessed. The failure is flagged on the node's [`InitFailure`]
+//! cell and surfaced on the main thread as [`HrtfInitFailed`].
+
+use bevy::prelude::*;
+use firewheel::node::{AudioNodeProcessor, ProcBuffers, ProcessStatus};
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig, InitFailure};
+
+/// Sent when an HRTF node fell back to passthrough because
+/// its processor couldn't be built.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct HrtfInitFailed(pub Entity);
+
+/// Either a backend's processor or the passthrough it fell back to.
+pub enum FallibleProcessor<P> {
+    Ready(P),
+    Passthrough(PassthroughProcessor),
+}
+
+impl<P> FallibleProcessor<P> {
+    /// Use `result`, or log the error, flag `failure`, and pass through.
+    pub fn new<E: core::fmt::Debug>(result: Result<P, E>, failure: &InitFailure) -> Self {
+        match result {
+            Ok(processor) => Self::Ready(processor),
+            Err(e) => {
+                error!("failed to build HRTF processor, passing audio through: {e:?}");
+                failure.set();
+
+                Self::Passthrough(PassthroughProcessor)
+            }
+        }
+    }
+}
+
+impl<P: AudioNodeProcessor> AudioNodeProcessor for FallibleProcessor<P> {
+    fn process(
+        &mut self,
+        buffers: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        match self {
+            Self::Ready(processor) => processor.process(buffers, proc_info, events),
+            Self::Passthrough(processor) => processor.process(buffers, proc_info, events),
+        }
+    }
+}
+
+/// Downmixes the input to both outputs without spatialization.
+pub struct PassthroughProcessor;
+
+impl AudioNodeProcessor for PassthroughProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        _: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        for frame in 0..proc_info.frames {
+            let mut downmixed = 0.0;
+            for channel in inputs {
+                downmixed += channel[frame];
+            }
+            downmixed /= inputs.len() as f32;
+
+            outputs[0][frame] = downmixed;
+            outputs[1][frame] = downmixed;
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
+
+/// Send [`HrtfInitFailed`] for every `N` node that fell back to passthrough.
+pub fn report_init_failures<N: HrtfNode>(
+    configs: Query<(Entity, &N::Configuration), With<N>>,
+    mut failed: EventWriter<HrtfInitFailed>,
+) {
+    for (entity, config) in configs.iter() {
+        if config.init_failure().is_some_and(|failure| failure.take()) {
+            failed.write(HrtfInitFailed(entity));
+        }
+    }
+}
+
+/// Name the nodes behind each [`HrtfInitFailed`].
+///
+/// The audio thread logs why a processor couldn't be built,
+/// but it doesn't know which entity the node belongs to.
+pub fn log_init_failures(mut failed: EventReader<HrtfInitFailed>) {
+    for HrtfInitFailed(entity) in failed.read() {
+        warn!("HRTF node {entity} is passing audio through unspatialized");
+    }
+}
@@ -0,0 +1,106 @@
+//! Custom distance falloff.
+//!
+//! The HRTF nodes only spatialize direction, so an emitter's level
+//! doesn't change with distance unless something attenuates it. An
+//! emitter with [`SpatialAudioFalloff`] has its [`FalloffVolume`] set
+//! from any [`Curve`] over normalized distance, so games can use step
+//! functions, sigmoids, or whatever shape suits them.
+//!
+//! bevy_seedling's [`SpatialBasicNode`] applies its own distance model,
+//! so a falloff on a panner chain stacks on top of it.
+
+use std::sync::Arc;
+
+use bevy::math::curve::{Curve, FunctionCurve, Interval};
+use bevy::prelude::*;
+use bevy_seedling::{SeedlingSystems, prelude::*};
+
+use crate::spatial::{Listeners, closest_listener};
+
+pub struct FalloffPlugin;
+
+impl Plugin for FalloffPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Last,
+            apply_distance_attenuation.before(SeedlingSystems::Acquire),
+        );
+    }
+}
+
+/// Maps an emitter's distance from its closest listener to a gain.
+///
+/// The curve is sampled at the distance divided by `max_distance`, so
+/// 0 is at the listener and 1 is at `max_distance` or beyond. Samples
+/// outside the curve's domain are clamped to it, and the gain to
+/// `[0, 1]`.
+#[derive(Clone, Component)]
+pub struct SpatialAudioFalloff {
+    pub curve: Arc<dyn Curve<f32> + Send + Sync>,
+    pub max_distance: f32,
+}
+
+impl SpatialAudioFalloff {
+    pub fn new(curve: impl Curve<f32> + Send + Sync + 'static, max_distance: f32) -> Self {
+        Self {
+            curve: Arc::new(curve),
+            max_distance,
+        }
+    }
+
+    /// A smooth falloff that drops through half gain at `midpoint`,
+    /// a normalized distance, more sharply the higher `steepness` is.
+    pub fn sigmoid(max_distance: f32, midpoint: f32, steepness: f32) -> Self {
+        let curve = FunctionCurve::new(Interval::UNIT, move |t: f32| {
+            1.0 / (1.0 + (steepness * (t - midpoint)).exp())
+        });
+
+        Self::new(curve, max_distance)
+    }
+
+    /// The gain at `distance` from the listener.
+    pub fn gain(&self, distance: f32) -> f32 {
+        let t = (distance / self.max_distance.max(f32::EPSILON)).clamp(0.0, 1.0);
+        self.curve.sample_clamped(t).clamp(0.0, 1.0)
+    }
+}
+
+impl core::fmt::Debug for SpatialAudioFalloff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("SpatialAudioFalloff")
+            .field("domain", &self.curve.domain())
+            .field("max_distance", &self.max_distance)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Marks the [`VolumeNode`] in an effect chain that
+/// [`SpatialAudioFalloff`] drives.
+///
+/// Groups and muting leave this node alone.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct FalloffVolume;
+
+/// Set each falloff volume from its emitter's distance to the closest listener.
+pub fn apply_distance_attenuation(
+    emitters: Query<(&GlobalTransform, &SpatialAudioFalloff, &SampleEffects)>,
+    mut volumes: Query<&mut VolumeNode, With<FalloffVolume>>,
+    listeners: Listeners,
+) {
+    for (transform, falloff, effects) in emitters.iter() {
+        let position = transform.translation();
+        let Some(listener) = closest_listener(&listeners, position) else {
+            continue;
+        };
+
+        let gain = falloff.gain(listener.translation().distance(position));
+
+        let mut volumes = volumes.iter_many_mut(effects.iter());
+        while let Some(mut volume) = volumes.fetch_next() {
+            // Skip imperceptible changes rather than patching every frame.
+            if (volume.volume.linear() - gain).abs() > 1e-4 {
+                volume.volume = Volume::Linear(gain);
+            }
+        }
+    }
+}
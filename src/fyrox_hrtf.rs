@@ -1,5 +1,7 @@
 //! Head-related transfer function (HRTF) node.
 
+use std::sync::{Arc, Once, OnceLock};
+
 use bevy::prelude::*;
 use bevy_seedling::{SeedlingSystems, prelude::*};
 use firewheel::{
@@ -9,25 +11,139 @@ use firewheel::{
 };
 use hrtf::{HrirSphere, HrtfContext, HrtfProcessor};
 
-pub struct FyroxPlugin;
+use crate::crossover::{LowBandSplit, LowFrequencyMode};
+use crate::dataset::{DatasetInfo, DatasetStats, HrtfDataset, update_dataset_info};
+use crate::downmix::{ChannelLayout, Downmix};
+use crate::fallback::{FallibleProcessor, HrtfInitFailed, report_init_failures};
+use crate::latency::{HrtfLatencyInfo, tag_node_latency};
+use crate::limiter::{ClipCounter, OutputSafety, collect_clipped_samples, report_clipped_samples};
+use crate::rate::DatasetRate;
+use crate::spatial::{
+    AppliedDirection, CustomDirectionSystem, FRONT, HrtfNode, HrtfNodeConfig, InitFailure,
+    SpatialAudioDebugSystems, apply_spatial_audio_commands, draw_direction_gizmos,
+    update_hrtf_effects, usable_direction, warn_unhandled_commands,
+};
+use crate::stale::{report_stale_directions, track_direction_age};
+use crate::state::AppState;
+use crate::tail::SilenceTail;
+use crate::telemetry::{Telemetry, TelemetryMessage, drain_telemetry, silence_non_finite};
+use crate::template::{ChainTemplate, register_chain_template};
+use crate::trail::{draw_direction_trails, record_direction_trail};
+use crate::validate::Spatializer;
+use crate::vr::update_vr_controller_hrtf;
+
+/// The embedded HRIR sphere.
+pub const SPHERE: &[u8] = include_bytes!("../assets/irc_1002_c.bin");
+
+/// The sample rate [`SPHERE`] was measured at.
+pub const SPHERE_SAMPLE_RATE: u32 = 44_100;
+/// The frames rendered per interpolation step.
+///
+/// `hrtf` pads each block to `BLOCK_LEN + 511` samples for the sphere's
+/// 512-tap filters, but only gives `rustfft` that many samples of scratch.
+/// At 256 frames the padded length is 767, which `rustfft` 6.4 can only
+/// plan with Bluestein's algorithm, and that needs four times the scratch.
+/// At 257 frames it's 768, which plans in place.
+const BLOCK_LEN: usize = 257;
+const INTERPOLATION_STEPS: usize = 4;
+const FFT_BUFFER_LEN: usize = BLOCK_LEN * INTERPOLATION_STEPS;
+
+pub struct FyroxPlugin {
+    /// The chain built for [`Spatial`](crate::template::Spatial) emitters.
+    pub template: ChainTemplate,
+}
+
+impl Default for FyroxPlugin {
+    fn default() -> Self {
+        Self {
+            // The fyrox renderer comes out much quieter than the others.
+            template: ChainTemplate {
+                volume: Some(Volume::Decibels(18.0)),
+                ..default()
+            },
+        }
+    }
+}
 
 impl Plugin for FyroxPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Last, update_hrtf_effects.before(SeedlingSystems::Acquire))
-            .register_node::<FyroxHrtfNode>();
+        app.add_systems(
+            Last,
+            (
+                apply_spatial_audio_commands::<FyroxHrtfNode>.before(warn_unhandled_commands),
+                (
+                    update_hrtf_effects::<FyroxHrtfNode>,
+                    update_vr_controller_hrtf::<FyroxHrtfNode>,
+                )
+                    .run_if(
+                        not(in_state(AppState::Menu))
+                            .and(not(resource_exists::<CustomDirectionSystem>)),
+                    ),
+            )
+                .chain()
+                .before(SeedlingSystems::Acquire),
+        )
+        .add_systems(Last, apply_standby.before(SeedlingSystems::Acquire))
+        .add_systems(
+            Update,
+            (
+                (
+                    draw_direction_gizmos::<FyroxHrtfNode>,
+                    record_direction_trail::<FyroxHrtfNode>.before(draw_direction_trails),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                tag_node_latency::<FyroxHrtfNode>,
+                update_dataset_info::<FyroxHrtfNode>,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                (
+                    collect_clipped_samples::<FyroxHrtfNode>.before(report_clipped_samples),
+                    track_direction_age::<FyroxHrtfNode>.before(report_stale_directions),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                report_init_failures::<FyroxHrtfNode>,
+                drain_telemetry::<FyroxHrtfNode>,
+            ),
+        )
+        .add_event::<HrtfInitFailed>()
+        .init_resource::<DatasetInfo>()
+        .register_node::<FyroxHrtfNode>()
+        .register_required_components::<FyroxHrtfNode, Spatializer>()
+        .register_type::<FyroxHrtfNode>()
+        .register_type::<HrtfConfig>();
+
+        // Input is staged for a full FFT buffer.
+        app.world_mut()
+            .get_resource_or_init::<HrtfLatencyInfo>()
+            .include(FFT_BUFFER_LEN as u32);
+
+        register_chain_template::<FyroxHrtfNode>(app, self.template.clone());
     }
 }
 
 /// Head-related transfer function (HRTF) node.
-#[derive(Debug, Default, Clone, Component, Diff, Patch)]
+///
+/// Spawning the node alone inserts a default [`HrtfConfig`].
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+#[require(Transform, HrtfConfig)]
 pub struct FyroxHrtfNode {
     /// The direction vector pointing from the listener to the
     /// emitter.
     pub direction: Vec3,
+    /// Pass the downmixed input to both ears unprocessed.
+    pub bypass: bool,
 }
 
 /// Configuration for [`FyroxHrtfNode`].
-#[derive(Debug, Clone, Component)]
+///
+/// Only the tuning fields are reflected. The shared cells are
+/// recreated on load, and the channel count falls back to stereo.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct HrtfConfig {
     /// The number of input channels.
     ///
@@ -35,24 +151,155 @@ pub struct HrtfConfig {
     /// before spatialization is applied.
     ///
     /// Defaults to [`NonZeroChannelCount::STEREO`].
+    #[reflect(ignore)]
     pub input_channels: NonZeroChannelCount,
+
+    /// How the input channels are weighted in the downmix.
+    ///
+    /// An invalid layout is logged and the inputs are averaged instead.
+    ///
+    /// Defaults to [`ChannelLayout::Auto`].
+    #[reflect(ignore)]
+    pub channel_layout: ChannelLayout,
+
+    /// How frequencies below `crossover_frequency` are rendered.
+    ///
+    /// Defaults to [`LowFrequencyMode::Full`].
+    pub low_freq_mode: LowFrequencyMode,
+
+    /// The crossover frequency in Hz when `low_freq_mode` is
+    /// [`LowFrequencyMode::SimplePan`].
+    ///
+    /// Clamped to [`CROSSOVER_RANGE`](crate::crossover::CROSSOVER_RANGE). Defaults to 400 Hz.
+    pub crossover_frequency: f32,
+
+    /// A cell the processor publishes its applied direction to.
+    ///
+    /// Defaults to `Some` in debug builds and `None` in release builds.
+    #[reflect(ignore)]
+    pub applied_direction: Option<Arc<AppliedDirection>>,
+
+    /// The safety stage applied to the output.
+    ///
+    /// Defaults to [`OutputSafety::Off`] on native targets and a
+    /// soft clip on the web, where there is no system limiter.
+    pub output_safety: OutputSafety,
+
+    /// Counts output samples that crossed the safety ceiling.
+    #[reflect(ignore)]
+    pub clip_counter: Arc<ClipCounter>,
+
+    /// Flagged when the processor falls back to passthrough.
+    #[reflect(ignore)]
+    pub init_failure: Arc<InitFailure>,
+
+    /// Carries the processor's reports to the ECS.
+    #[reflect(ignore)]
+    pub telemetry: Arc<Telemetry>,
+
+    /// Prime the renderer's history with a silent block before the
+    /// first real one. Set by [`HrtfStandby`].
+    pub standby: bool,
 }
 
 impl Default for HrtfConfig {
     fn default() -> Self {
         Self {
             input_channels: NonZeroChannelCount::STEREO,
+            channel_layout: ChannelLayout::Auto,
+            low_freq_mode: LowFrequencyMode::Full,
+            crossover_frequency: 400.0,
+            applied_direction: cfg!(debug_assertions).then(Default::default),
+            output_safety: OutputSafety::default(),
+            clip_counter: Default::default(),
+            init_failure: Default::default(),
+            telemetry: Default::default(),
+            standby: false,
         }
     }
 }
 
+/// Keeps a [`FyroxHrtfNode`] on warm standby.
+///
+/// The renderer's overlap history starts out empty, so the first
+/// block a processor renders isn't shaped like the ones after it.
+/// With this component, the processor renders one block of silence in
+/// the current direction just before its first audible block, so an
+/// emitter that suddenly starts playing sounds right from the start.
+///
+/// Only takes effect when inserted along with the node.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct HrtfStandby;
+
+fn apply_standby(mut configs: Query<&mut HrtfConfig, Added<HrtfStandby>>) {
+    for mut config in configs.iter_mut() {
+        config.standby = true;
+    }
+}
+
+impl HrtfNodeConfig for HrtfConfig {
+    fn applied_direction(&self) -> Option<&Arc<AppliedDirection>> {
+        self.applied_direction.as_ref()
+    }
+
+    fn clip_counter(&self) -> &Arc<ClipCounter> {
+        &self.clip_counter
+    }
+
+    fn latency_frames(&self) -> u32 {
+        FFT_BUFFER_LEN as u32
+    }
+
+    fn init_failure(&self) -> Option<&Arc<InitFailure>> {
+        Some(&self.init_failure)
+    }
+
+    fn telemetry(&self) -> Option<&Arc<Telemetry>> {
+        Some(&self.telemetry)
+    }
+}
+
+/// Where a processor is in its warm-up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HrtfNodeState {
+    /// Waiting to prime the history before the first audible block.
+    Initializing,
+    Active,
+}
+
 struct FyroxHrtfProcessor {
     renderer: HrtfProcessor,
+    state: HrtfNodeState,
     direction: Vec3,
     fft_input: Vec<f32>,
     fft_output: Vec<(f32, f32)>,
     prev_left_samples: Vec<f32>,
     prev_right_samples: Vec<f32>,
+    split: Option<LowBandSplit>,
+    sample_rate: f32,
+    applied_direction: Option<Arc<AppliedDirection>>,
+    output_safety: OutputSafety,
+    clip_counter: Arc<ClipCounter>,
+    telemetry: Arc<Telemetry>,
+    downmix: Downmix,
+    bypass: bool,
+    tail: SilenceTail,
+    /// Whether the tail has passed since the input last went silent.
+    flushed: bool,
+}
+
+impl HrtfNode for FyroxHrtfNode {
+    fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    fn set_direction(&mut self, direction: Vec3) {
+        self.direction = direction;
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
 }
 
 impl AudioNode for FyroxHrtfNode {
@@ -66,31 +313,190 @@ impl AudioNode for FyroxHrtfNode {
 
     fn construct_processor(
         &self,
-        _config: &Self::Configuration,
+        config: &Self::Configuration,
         cx: firewheel::node::ConstructProcessorContext,
     ) -> impl firewheel::node::AudioNodeProcessor {
         let sample_rate = cx.stream_info.sample_rate.get();
 
-        let sphere = include_bytes!("../assets/irc_1002_c.bin");
+        let fft_buffer_len = FFT_BUFFER_LEN;
 
-        let block_len = 256;
-        let interpolation_steps = 4;
+        // An unreadable sphere leaves the node passing audio through.
+        let sphere = load_sphere(sample_rate);
 
-        let fft_buffer_len = block_len * interpolation_steps;
-
-        let sphere = HrirSphere::new(std::io::Cursor::new(sphere), sample_rate).unwrap();
-        let renderer = HrtfProcessor::new(sphere, interpolation_steps, block_len);
+        // The low band is staged alongside `fft_input`, so it shares
+        // the block latency. One millisecond covers any head's ITD.
+        let max_itd = (sample_rate / 1000) as usize;
+        let split = LowBandSplit::new(
+            config.low_freq_mode,
+            config.crossover_frequency,
+            sample_rate as f32,
+            max_itd,
+            fft_buffer_len,
+        );
 
         let buffer_size = cx.stream_info.max_block_frames.get() as usize;
-        FyroxHrtfProcessor {
-            renderer,
-            direction: self.direction,
+        let processor = sphere.map(|sphere| FyroxHrtfProcessor {
+            renderer: HrtfProcessor::new(sphere, INTERPOLATION_STEPS, BLOCK_LEN),
+            state: if config.standby {
+                HrtfNodeState::Initializing
+            } else {
+                HrtfNodeState::Active
+            },
+            direction: usable_direction(self.direction).unwrap_or(FRONT),
             fft_input: Vec::with_capacity(fft_buffer_len),
             fft_output: Vec::with_capacity(buffer_size.max(fft_buffer_len)),
             prev_left_samples: Vec::with_capacity(fft_buffer_len),
             prev_right_samples: Vec::with_capacity(fft_buffer_len),
+            split,
+            sample_rate: sample_rate as f32,
+            applied_direction: config.applied_direction.clone(),
+            output_safety: config.output_safety,
+            clip_counter: config.clip_counter.clone(),
+            telemetry: config.telemetry.clone(),
+            downmix: Downmix::new_or_average(&config.channel_layout, config.input_channels),
+            bypass: self.bypass,
+            // The staging latency plus a generous bound on the HRIR length.
+            tail: SilenceTail::new(2 * fft_buffer_len),
+            flushed: true,
+        });
+
+        FallibleProcessor::new(processor, &config.init_failure)
+    }
+}
+
+/// Load the embedded HRIR sphere, resampled to `sample_rate`.
+///
+/// If `sample_rate` is too far from [`SPHERE_SAMPLE_RATE`], or resampling
+/// fails, the sphere is loaded at its native rate instead.
+pub fn load_sphere(sample_rate: u32) -> Result<HrirSphere, hrtf::HrtfError> {
+    static LOGGED: Once = Once::new();
+
+    let rate = DatasetRate::new(SPHERE_SAMPLE_RATE as f32, sample_rate as f32);
+    LOGGED.call_once(|| rate.log("irc_1002_c.bin"));
+
+    if rate.resample() {
+        match HrirSphere::new(std::io::Cursor::new(SPHERE), sample_rate) {
+            Ok(sphere) => return Ok(record_sphere_stats(sphere)),
+            Err(e) => warn!(
+                "failed to resample irc_1002_c.bin to {sample_rate} Hz, \
+                 loading at the native rate: {e:?}"
+            ),
         }
     }
+
+    HrirSphere::new(std::io::Cursor::new(SPHERE), SPHERE_SAMPLE_RATE).map(record_sphere_stats)
+}
+
+/// The embedded sphere's cost, once it's loaded.
+static SPHERE_STATS: OnceLock<DatasetStats> = OnceLock::new();
+
+fn record_sphere_stats(sphere: HrirSphere) -> HrirSphere {
+    SPHERE_STATS.get_or_init(|| {
+        let points = sphere.points().len();
+        let filter_len = sphere.len();
+
+        DatasetStats {
+            dataset: "irc_1002_c.bin",
+            filter_len,
+            measurements: Some(points),
+            // Every node decodes its own copy of the sphere, and its
+            // renderer keeps a few padded spectra per ear. On top of
+            // that are the FFT staging buffers: input, stereo output,
+            // and the previous block for each ear.
+            bytes_per_node: points * filter_len * 2 * size_of::<f32>()
+                + 2 * 3 * (BLOCK_LEN + filter_len) * 2 * size_of::<f32>()
+                + FFT_BUFFER_LEN * 5 * size_of::<f32>(),
+            shared_bytes: 0,
+            nodes: 0,
+            // Only the sofar loader analyzes its filters.
+            hrir: None,
+            conditioned_hrir: None,
+        }
+    });
+
+    sphere
+}
+
+impl HrtfDataset for FyroxHrtfNode {
+    fn dataset_stats() -> Option<DatasetStats> {
+        SPHERE_STATS.get().cloned()
+    }
+}
+
+/// Render the response of the HRTF for `direction` to a unit impulse.
+///
+/// This builds its own renderer, so it never disturbs live nodes.
+#[cfg_attr(
+    feature = "sofar",
+    expect(dead_code, reason = "sofar is preferred for exports")
+)]
+pub fn impulse_response(direction: Vec3, sample_rate: u32, left: &mut [f32], right: &mut [f32]) {
+    let sphere = HrirSphere::new(std::io::Cursor::new(SPHERE), sample_rate).unwrap();
+    let mut renderer = HrtfProcessor::new(sphere, INTERPOLATION_STEPS, BLOCK_LEN);
+
+    let direction = usable_direction(direction).unwrap_or(FRONT);
+    let direction = hrtf::Vec3::new(direction.x, direction.y, direction.z);
+
+    let mut source = vec![0.0; FFT_BUFFER_LEN];
+    let mut output = vec![(0.0, 0.0); FFT_BUFFER_LEN];
+    let mut prev_left_samples = Vec::with_capacity(FFT_BUFFER_LEN);
+    let mut prev_right_samples = Vec::with_capacity(FFT_BUFFER_LEN);
+
+    source[0] = 1.0;
+
+    for (left, right) in left
+        .chunks_mut(FFT_BUFFER_LEN)
+        .zip(right.chunks_mut(FFT_BUFFER_LEN))
+    {
+        output.fill((0.0, 0.0));
+
+        renderer.process_samples(HrtfContext {
+            source: &source,
+            output: &mut output,
+            new_sample_vector: direction,
+            prev_sample_vector: direction,
+            prev_left_samples: &mut prev_left_samples,
+            prev_right_samples: &mut prev_right_samples,
+            new_distance_gain: 1.0,
+            prev_distance_gain: 1.0,
+        });
+
+        for ((l, r), (out_l, out_r)) in left.iter_mut().zip(right.iter_mut()).zip(&output) {
+            *l = *out_l;
+            *r = *out_r;
+        }
+
+        // Only the first block carries the impulse.
+        source.fill(0.0);
+    }
+}
+
+impl FyroxHrtfProcessor {
+    /// Render a block of silence in the current direction to fill the
+    /// renderer's history, discarding the output.
+    ///
+    /// Only called before any audio has been staged, so both buffers
+    /// are empty and already have the capacity this needs.
+    fn prime(&mut self) {
+        let fft_len = self.fft_input.capacity();
+        self.fft_input.resize(fft_len, 0.0);
+        self.fft_output.resize(fft_len, (0.0, 0.0));
+
+        let direction = hrtf::Vec3::new(self.direction.x, self.direction.y, self.direction.z);
+        self.renderer.process_samples(HrtfContext {
+            source: &self.fft_input,
+            output: &mut self.fft_output,
+            new_sample_vector: direction,
+            prev_sample_vector: direction,
+            prev_left_samples: &mut self.prev_left_samples,
+            prev_right_samples: &mut self.prev_right_samples,
+            new_distance_gain: 1.0,
+            prev_distance_gain: 1.0,
+        });
+
+        self.fft_input.clear();
+        self.fft_output.clear();
+    }
 }
 
 impl AudioNodeProcessor for FyroxHrtfProcessor {
@@ -104,21 +510,53 @@ impl AudioNodeProcessor for FyroxHrtfProcessor {
     ) -> ProcessStatus {
         let mut previous_vector = self.direction;
 
-        events.for_each_patch::<FyroxHrtfNode>(|FyroxHrtfNodePatch::Direction(direction)| {
-            let direction = direction.normalize_or_zero();
-            self.direction = direction;
+        events.for_each_patch::<FyroxHrtfNode>(|patch| match patch {
+            // An unusable direction keeps the current one.
+            FyroxHrtfNodePatch::Direction(direction) => {
+                if let Some(direction) = usable_direction(direction) {
+                    self.direction = direction;
+                }
+            }
+            FyroxHrtfNodePatch::Bypass(bypass) => self.bypass = bypass,
         });
 
-        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
+        let silent = proc_info.in_silence_mask.all_channels_silent(inputs.len());
+        if !self.tail.process_block(silent, proc_info.frames) {
+            if !self.flushed {
+                self.flushed = true;
+                self.telemetry.send(TelemetryMessage::TailFinished);
+            }
+
             return ProcessStatus::ClearAllOutputs;
         }
+        self.flushed = false;
+
+        if self.bypass {
+            let (left, right) = outputs.split_at_mut(1);
+            let left = &mut left[0][..proc_info.frames];
+            for (frame, sample) in left.iter_mut().enumerate() {
+                *sample = self.downmix.sample(inputs, frame);
+            }
+            right[0][..proc_info.frames].copy_from_slice(left);
+
+            return ProcessStatus::outputs_not_silent();
+        }
+
+        if self.state == HrtfNodeState::Initializing {
+            self.prime();
+            previous_vector = self.direction;
+            self.state = HrtfNodeState::Active;
+        }
 
         for frame in 0..proc_info.frames {
-            let mut downmixed = 0.0;
-            for channel in inputs {
-                downmixed += channel[frame];
+            let mut downmixed = self.downmix.sample(inputs, frame);
+
+            // Only the high band is convolved when splitting.
+            if let Some(split) = &mut self.split {
+                let (low, high) = split.crossover.split(downmixed);
+                split.low.push(low);
+                downmixed = high;
             }
-            downmixed /= inputs.len() as f32;
 
             self.fft_input.push(downmixed);
 
@@ -153,6 +591,25 @@ impl AudioNodeProcessor for FyroxHrtfProcessor {
 
                 self.renderer.process_samples(context);
 
+                if let Some(split) = &mut self.split {
+                    split
+                        .pan
+                        .set_from_direction(self.direction, self.sample_rate, 0);
+
+                    for (output, low) in self.fft_output[output_start..]
+                        .iter_mut()
+                        .zip(split.low.drain(..))
+                    {
+                        let (left, right) = split.pan.process(low);
+                        output.0 += left;
+                        output.1 += right;
+                    }
+                }
+
+                if let Some(applied) = &self.applied_direction {
+                    applied.store(self.direction);
+                }
+
                 // in case we call this multiple times
                 previous_vector = self.direction;
                 self.fft_input.clear();
@@ -168,51 +625,17 @@ impl AudioNodeProcessor for FyroxHrtfProcessor {
             outputs[1][i] = right;
         }
 
-        ProcessStatus::outputs_not_silent()
-    }
-}
-
-fn update_hrtf_effects(
-    listeners: Query<&GlobalTransform, Or<(With<SpatialListener2D>, With<SpatialListener3D>)>>,
-    mut emitters: Query<(&mut FyroxHrtfNode, &EffectOf)>,
-    effect_parents: Query<&GlobalTransform>,
-) {
-    for (mut spatial, effect_of) in emitters.iter_mut() {
-        let Ok(transform) = effect_parents.get(effect_of.0) else {
-            continue;
-        };
-
-        let emitter_pos = transform.translation();
-        let closest_listener = find_closest_listener(
-            emitter_pos,
-            listeners.iter().map(GlobalTransform::translation),
-        );
-
-        let Some(listener_pos) = closest_listener else {
-            continue;
-        };
-
-        // TODO: factor in listener rotation
-        spatial.direction = emitter_pos - listener_pos;
-    }
-}
-
-fn find_closest_listener(emitter_pos: Vec3, listeners: impl Iterator<Item = Vec3>) -> Option<Vec3> {
-    let mut closest_listener: Option<(f32, Vec3)> = None;
+        if silence_non_finite(outputs, proc_info.frames) {
+            self.telemetry.send(TelemetryMessage::NanDetected);
+        }
 
-    for listener_pos in listeners {
-        let distance = emitter_pos.distance_squared(listener_pos);
+        let clipped = outputs
+            .iter_mut()
+            .map(|output| self.output_safety.process(&mut output[..proc_info.frames]))
+            .sum();
+        self.clip_counter.add(clipped);
+        self.telemetry.send_clipped(clipped);
 
-        match &mut closest_listener {
-            None => closest_listener = Some((distance, listener_pos)),
-            Some((old_distance, old_pos)) => {
-                if distance < *old_distance {
-                    *old_distance = distance;
-                    *old_pos = listener_pos;
-                }
-            }
-        }
+        ProcessStatus::outputs_not_silent()
     }
-
-    closest_listener.map(|l| l.1)
 }
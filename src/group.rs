@@ -0,0 +1,108 @@
+//! Shared mixing settings for groups of emitters.
+//!
+//! Emitters tagged with the same [`SpatialAudioGroup`] share a
+//! [`GroupConfig`] in [`SpatialAudioGroupConfig`], which sets the
+//! volume and reverb send of every effect chain in the group. Only
+//! groups whose settings changed, and emitters that just joined a
+//! group, are touched.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::falloff::FalloffVolume;
+use crate::mute::MutedVolume;
+
+/// Places an emitter in a mixing group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct SpatialAudioGroup(pub u32);
+
+/// Mixing settings for one [`SpatialAudioGroup`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupConfig {
+    /// Applied to the [`VolumeNode`]s in each emitter's chain.
+    ///
+    /// This replaces their volume, so chains that use a `VolumeNode`
+    /// for makeup gain should fold it into the group volume.
+    pub volume: Volume,
+    pub mute: bool,
+    /// Applied to the [`SendNode`]s in each emitter's chain.
+    pub reverb_send: Volume,
+}
+
+impl Default for GroupConfig {
+    fn default() -> Self {
+        Self {
+            volume: Volume::UNITY_GAIN,
+            mute: false,
+            reverb_send: Volume::Linear(0.5),
+        }
+    }
+}
+
+/// Settings for every group, keyed by group ID.
+///
+/// Groups without an entry are left alone.
+#[derive(Debug, Default, Resource)]
+pub struct SpatialAudioGroupConfig(pub HashMap<u32, GroupConfig>);
+
+pub fn apply_group_config(
+    config: Res<SpatialAudioGroupConfig>,
+    mut applied: Local<HashMap<u32, GroupConfig>>,
+    emitters: Query<Ref<SpatialAudioGroup>>,
+    mut volumes: Query<
+        (&EffectOf, &mut VolumeNode, Option<&mut MutedVolume>),
+        Without<FalloffVolume>,
+    >,
+    mut sends: Query<(&EffectOf, &mut SendNode)>,
+) {
+    let group_changed = config.is_changed();
+    let joined = emitters.iter().any(|group| group.is_changed());
+
+    if !group_changed && !joined {
+        return;
+    }
+
+    // Find the groups that need updating before touching any nodes.
+    let mut stale = HashMap::new();
+    for (id, group) in &config.0 {
+        if applied.get(id) != Some(group) {
+            stale.insert(*id, *group);
+        }
+    }
+    applied.retain(|id, _| config.0.contains_key(id));
+
+    let target = |parent: Entity| {
+        let group = emitters.get(parent).ok()?;
+        let settings = config.0.get(&group.0)?;
+
+        (stale.contains_key(&group.0) || group.is_changed()).then_some(*settings)
+    };
+
+    for (effect_of, mut volume, muted) in volumes.iter_mut() {
+        let Some(settings) = target(effect_of.0) else {
+            continue;
+        };
+
+        let level = if settings.mute {
+            Volume::Linear(0.0)
+        } else {
+            settings.volume
+        };
+
+        // A muted emitter restores to the group level when unmuted.
+        match muted {
+            Some(mut muted) => muted.0 = level,
+            None => volume.volume = level,
+        }
+    }
+
+    for (effect_of, mut send) in sends.iter_mut() {
+        if let Some(settings) = target(effect_of.0) {
+            send.send_volume = settings.reverb_send;
+        }
+    }
+
+    applied.extend(stale);
+}
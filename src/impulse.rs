@@ -0,0 +1,122 @@
+//! Offline capture of HRTF impulse responses.
+//!
+//! Spawning an [`ExportImpulseResponse`] renders a unit impulse through
+//! a standalone copy of the active backend's renderer and writes the
+//! result as a stereo WAV file. Live nodes are left untouched, so
+//! exports can be taken while the demo is playing.
+//!
+//! Press `I` to export the response for the first emitter's
+//! current direction.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::latency::HrtfLatencyInfo;
+use crate::spatial::{Listeners, closest_listener};
+use crate::wav;
+
+pub struct ImpulseResponsePlugin;
+
+impl Plugin for ImpulseResponsePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (export_on_key, export_impulse_responses).chain());
+    }
+}
+
+/// A request to export the impulse response for a direction.
+///
+/// The entity is despawned once the file is written.
+#[derive(Debug, Clone, Component)]
+pub struct ExportImpulseResponse {
+    /// The direction from the listener to the source, in the node frame.
+    pub direction: Vec3,
+    pub output_path: PathBuf,
+}
+
+/// The number of frames captured per ear.
+pub const IMPULSE_RESPONSE_FRAMES: usize = 2048;
+
+fn export_impulse_responses(
+    requests: Query<(Entity, &ExportImpulseResponse)>,
+    latency: Res<HrtfLatencyInfo>,
+    mut commands: Commands,
+) {
+    for (entity, request) in requests.iter() {
+        commands.entity(entity).despawn();
+
+        // Match the stream when it's running so the response
+        // is resampled exactly as the live nodes hear it.
+        let sample_rate = match latency.sample_rate {
+            0 => 48000,
+            rate => rate,
+        };
+
+        let mut left = vec![0.0; IMPULSE_RESPONSE_FRAMES];
+        let mut right = vec![0.0; IMPULSE_RESPONSE_FRAMES];
+
+        if !render(request.direction, sample_rate, &mut left, &mut right) {
+            warn!("no HRTF backend is enabled; nothing to export");
+            continue;
+        }
+
+        let interleaved: Vec<f32> = left
+            .iter()
+            .zip(&right)
+            .flat_map(|(l, r)| [*l, *r])
+            .collect();
+
+        match wav::write_wav(&request.output_path, 2, sample_rate, &interleaved) {
+            Ok(()) => info!(
+                "wrote impulse response to {}",
+                request.output_path.display()
+            ),
+            Err(e) => error!("failed to write impulse response: {e}"),
+        }
+    }
+}
+
+/// Render with the preferred enabled backend, returning
+/// `false` if there is none.
+#[allow(unused_variables)]
+fn render(direction: Vec3, sample_rate: u32, left: &mut [f32], right: &mut [f32]) -> bool {
+    #[cfg(feature = "sofar")]
+    {
+        crate::sofar_hrtf::impulse_response(direction, sample_rate as f32, left, right);
+        true
+    }
+
+    #[cfg(all(feature = "fyrox", not(feature = "sofar")))]
+    {
+        crate::fyrox_hrtf::impulse_response(direction, sample_rate, left, right);
+        true
+    }
+
+    #[cfg(not(any(feature = "sofar", feature = "fyrox")))]
+    false
+}
+
+fn export_on_key(
+    input: Res<ButtonInput<KeyCode>>,
+    listeners: Listeners,
+    emitters: Query<&GlobalTransform, With<crate::Spinner>>,
+    mut commands: Commands,
+) {
+    if !input.just_pressed(KeyCode::KeyI) {
+        return;
+    }
+
+    let Some(emitter) = emitters.iter().next() else {
+        return;
+    };
+
+    let position = emitter.translation();
+    let Some(listener) = closest_listener(&listeners, position) else {
+        return;
+    };
+
+    commands.spawn(ExportImpulseResponse {
+        direction: listener.relative_direction(position - listener.translation()),
+        output_path: PathBuf::from("impulse_response.wav"),
+    });
+}
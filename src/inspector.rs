@@ -0,0 +1,169 @@
+//! Live node inspection with `bevy_inspector_egui`.
+//!
+//! Enabled by the `inspector` feature. The HRTF and effect nodes are
+//! all reflected, so they show up in the entity inspector like any
+//! other component. [`SpatialAudioInspectorPlugin`] adds two
+//! renderers on top:
+//!
+//! - An HRTF node's `direction` is edited as azimuth and elevation in
+//!   degrees, which is how directions are talked about, rather than as
+//!   raw node-frame XYZ. Its other fields are shown as usual.
+//! - A [`Decibels`] gain is a decibel slider.
+
+use std::any::{Any, TypeId};
+
+use crate::binaural::Decibels;
+use crate::spatial::{HrtfNode, az_el_to_direction, direction_to_az_el};
+use bevy::{
+    prelude::*,
+    reflect::{GetTypeRegistration, Struct},
+};
+use bevy_inspector_egui::{
+    egui,
+    inspector_egui_impls::InspectorEguiImpl,
+    reflect_inspector::{InspectorUi, ProjectorReflect},
+};
+
+pub struct SpatialAudioInspectorPlugin;
+
+impl Plugin for SpatialAudioInspectorPlugin {
+    fn build(&self, app: &mut App) {
+        add_impl::<Decibels>(
+            app,
+            InspectorEguiImpl::new(decibels_ui, decibels_ui_readonly, no_multi_edit),
+        );
+
+        #[cfg(feature = "sofar")]
+        add_hrtf_node_impl::<crate::sofar_hrtf::SofarHrtfNode>(app);
+        #[cfg(feature = "fyrox")]
+        add_hrtf_node_impl::<crate::fyrox_hrtf::FyroxHrtfNode>(app);
+        #[cfg(feature = "realfft-backend")]
+        add_hrtf_node_impl::<crate::realfft_hrtf::RealFftHrtfNode>(app);
+        #[cfg(feature = "lite")]
+        add_hrtf_node_impl::<crate::simple_pan::SimplePanNode>(app);
+    }
+}
+
+/// The range of the [`Decibels`] slider.
+const DECIBELS_RANGE: core::ops::RangeInclusive<f32> = -60.0..=12.0;
+
+fn add_impl<T: GetTypeRegistration>(app: &mut App, inspector_impl: InspectorEguiImpl) {
+    app.register_type::<T>();
+
+    let registry = app.world().resource::<AppTypeRegistry>();
+    if let Some(registration) = registry.write().get_mut(TypeId::of::<T>()) {
+        registration.insert(inspector_impl);
+    }
+}
+
+fn add_hrtf_node_impl<N: HrtfNode + Struct + GetTypeRegistration>(app: &mut App) {
+    add_impl::<N>(
+        app,
+        InspectorEguiImpl::new(hrtf_node_ui::<N>, hrtf_node_ui_readonly::<N>, no_multi_edit),
+    );
+}
+
+fn decibels_ui(
+    value: &mut dyn Any,
+    ui: &mut egui::Ui,
+    _options: &dyn Any,
+    _id: egui::Id,
+    _env: InspectorUi<'_, '_>,
+) -> bool {
+    let decibels = value.downcast_mut::<Decibels>().unwrap();
+
+    ui.add(egui::Slider::new(&mut decibels.0, DECIBELS_RANGE).suffix(" dB"))
+        .changed()
+}
+
+fn decibels_ui_readonly(
+    value: &dyn Any,
+    ui: &mut egui::Ui,
+    _options: &dyn Any,
+    _id: egui::Id,
+    _env: InspectorUi<'_, '_>,
+) {
+    let decibels = value.downcast_ref::<Decibels>().unwrap();
+    ui.label(format!("{:.1} dB", decibels.0));
+}
+
+/// Edit the direction as azimuth and elevation, then every other field.
+fn hrtf_node_ui<N: HrtfNode + Struct>(
+    value: &mut dyn Any,
+    ui: &mut egui::Ui,
+    _options: &dyn Any,
+    id: egui::Id,
+    mut env: InspectorUi<'_, '_>,
+) -> bool {
+    let node = value.downcast_mut::<N>().unwrap();
+
+    let (mut azimuth, mut elevation) = direction_to_az_el(node.direction());
+    let mut changed = false;
+    changed |= ui
+        .add(egui::Slider::new(&mut azimuth, -180.0..=180.0).text("azimuth °"))
+        .changed();
+    changed |= ui
+        .add(egui::Slider::new(&mut elevation, -90.0..=90.0).text("elevation °"))
+        .changed();
+    if changed {
+        node.set_direction(az_el_to_direction(azimuth, elevation));
+    }
+
+    for index in 0..node.field_len() {
+        let Some(name) = node.name_at(index).map(str::to_owned) else {
+            continue;
+        };
+        if name == "direction" {
+            continue;
+        }
+        let Some(field) = node.field_at_mut(index) else {
+            continue;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(&name);
+            changed |= env.ui_for_reflect_with_options(field, ui, id.with(index), &());
+        });
+    }
+
+    changed
+}
+
+fn hrtf_node_ui_readonly<N: HrtfNode + Struct>(
+    value: &dyn Any,
+    ui: &mut egui::Ui,
+    _options: &dyn Any,
+    id: egui::Id,
+    mut env: InspectorUi<'_, '_>,
+) {
+    let node = value.downcast_ref::<N>().unwrap();
+
+    let (azimuth, elevation) = direction_to_az_el(node.direction());
+    ui.label(format!("azimuth {azimuth:.1}°, elevation {elevation:.1}°"));
+
+    for index in 0..node.field_len() {
+        let (Some(name), Some(field)) = (node.name_at(index), node.field_at(index)) else {
+            continue;
+        };
+        if name == "direction" {
+            continue;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(name);
+            env.ui_for_reflect_readonly_with_options(field, ui, id.with(index), &());
+        });
+    }
+}
+
+/// Editing several entities at once isn't supported.
+fn no_multi_edit(
+    _ui: &mut egui::Ui,
+    _options: &dyn Any,
+    _id: egui::Id,
+    _env: InspectorUi<'_, '_>,
+    _values: &mut [&mut dyn PartialReflect],
+    _projector: &dyn ProjectorReflect,
+) -> bool {
+    false
+}
@@ -0,0 +1,251 @@
+//! Visual compensation for HRTF processing latency.
+//!
+//! The block-based HRTF backends render an emitter a little after it
+//! moves, roughly `block_len / sample_rate` seconds. Visuals that should
+//! line up with what the listener hears, like impact flashes or particles
+//! spawned on a hit, can look up where an emitter was that long ago
+//! through [`DelayedTransforms`] instead of using its current
+//! [`GlobalTransform`].
+
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{ecs::system::SystemParam, prelude::*, transform::TransformSystem};
+use bevy_seedling::{context::AudioContext, prelude::*};
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig};
+use crate::sync::{CurrentAudioFrame, frame_to_seconds};
+
+pub struct LatencyCompensator;
+
+impl Plugin for LatencyCompensator {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HrtfLatencyInfo>()
+            .add_systems(
+                Update,
+                read_sample_rate.run_if(|info: Res<HrtfLatencyInfo>| info.sample_rate == 0),
+            )
+            .add_systems(Startup, spawn_chain_latency_hud)
+            .add_systems(Update, update_chain_latency_hud)
+            .add_systems(
+                PostUpdate,
+                record_transform_history.after(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// The latency introduced by the active HRTF backends.
+///
+/// Each backend plugin reports its block latency in frames,
+/// and the largest is used.
+#[derive(Debug, Default, Clone, Copy, Resource)]
+pub struct HrtfLatencyInfo {
+    pub latency_frames: u32,
+    /// The stream's sample rate, or zero before the stream starts.
+    pub sample_rate: u32,
+    /// The stream's maximum block size, as an estimate of device buffering.
+    pub device_frames: u32,
+}
+
+impl HrtfLatencyInfo {
+    /// Record a backend's latency, keeping the largest seen.
+    pub fn include(&mut self, latency_frames: u32) {
+        self.latency_frames = self.latency_frames.max(latency_frames);
+    }
+
+    pub fn latency(&self) -> Duration {
+        if self.sample_rate == 0 {
+            return Duration::ZERO;
+        }
+
+        Duration::from_secs_f64(self.latency_frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Convert a frame count at the stream's sample rate to milliseconds.
+    pub fn frames_to_ms(&self, frames: u32) -> f32 {
+        if self.sample_rate == 0 {
+            return 0.0;
+        }
+
+        frames as f32 * 1000.0 / self.sample_rate as f32
+    }
+}
+
+fn read_sample_rate(mut context: ResMut<AudioContext>, mut info: ResMut<HrtfLatencyInfo>) {
+    let stream = context.with(|ctx| {
+        ctx.stream_info()
+            .map(|s| (s.sample_rate.get(), s.max_block_frames.get()))
+    });
+
+    if let Some((sample_rate, device_frames)) = stream {
+        info!("audio stream: {sample_rate} Hz, blocks of up to {device_frames} frames");
+
+        info.sample_rate = sample_rate;
+        info.device_frames = device_frames;
+    }
+}
+
+/// The latency a node in an effect chain reports.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct NodeLatency {
+    pub name: &'static str,
+    pub frames: u32,
+}
+
+/// Tag `N` nodes with the latency their configuration reports,
+/// and include it in [`HrtfLatencyInfo`].
+///
+/// Some latencies are only known once the processor is built,
+/// so every node is checked each frame.
+pub fn tag_node_latency<N: HrtfNode>(
+    nodes: Query<(Entity, &N::Configuration, Option<&NodeLatency>), With<N>>,
+    mut info: ResMut<HrtfLatencyInfo>,
+    mut commands: Commands,
+) {
+    let name = core::any::type_name::<N>()
+        .rsplit("::")
+        .next()
+        .unwrap_or_default();
+
+    for (entity, config, tagged) in nodes.iter() {
+        let frames = config.latency_frames();
+        if tagged.is_some_and(|tagged| tagged.frames == frames) {
+            continue;
+        }
+
+        info.include(frames);
+        commands.entity(entity).insert(NodeLatency { name, frames });
+    }
+}
+
+#[derive(Component)]
+struct ChainLatencyHud;
+
+fn spawn_chain_latency_hud(mut commands: Commands) {
+    commands.spawn((
+        ChainLatencyHud,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+/// Show the latency of each node in the first compensated
+/// emitter's chain, plus device buffering.
+fn update_chain_latency_hud(
+    emitters: Query<&SampleEffects, With<LatencyCompensated>>,
+    nodes: Query<&NodeLatency>,
+    info: Res<HrtfLatencyInfo>,
+    audio_frame: Res<CurrentAudioFrame>,
+    mut hud: Single<&mut Text, With<ChainLatencyHud>>,
+) {
+    use core::fmt::Write;
+
+    let Some(effects) = emitters.iter().next() else {
+        return;
+    };
+
+    let mut report = format!("chain latency @ {} Hz\n", info.sample_rate);
+    let mut total = info.device_frames;
+
+    for node in effects.iter().filter_map(|effect| nodes.get(effect).ok()) {
+        total += node.frames;
+        let _ = writeln!(
+            report,
+            "  {}: {} frames ({:.2} ms)",
+            node.name,
+            node.frames,
+            info.frames_to_ms(node.frames)
+        );
+    }
+
+    let _ = writeln!(
+        report,
+        "  device buffer: {} frames ({:.2} ms)",
+        info.device_frames,
+        info.frames_to_ms(info.device_frames)
+    );
+    let _ = write!(report, "  total: {:.2} ms", info.frames_to_ms(total));
+
+    if info.sample_rate != 0 {
+        let _ = write!(
+            report,
+            "\naudio clock: {:.2} s",
+            frame_to_seconds(audio_frame.0, info.sample_rate)
+        );
+    }
+
+    if hud.0 != report {
+        hud.0 = report;
+    }
+}
+
+/// Keeps a short history of an emitter's [`GlobalTransform`]
+/// so it can be read back through [`DelayedTransforms`].
+#[derive(Debug, Default, Component)]
+#[require(TransformHistory)]
+pub struct LatencyCompensated;
+
+/// Recent `(elapsed seconds, transform)` snapshots, oldest first.
+#[derive(Debug, Default, Component)]
+pub struct TransformHistory(VecDeque<(f64, GlobalTransform)>);
+
+impl TransformHistory {
+    /// The latest snapshot taken at or before `time`, or the
+    /// oldest snapshot if the history doesn't reach back that far.
+    pub fn sample(&self, time: f64) -> Option<GlobalTransform> {
+        let index = self.0.partition_point(|(t, _)| *t <= time);
+        self.0
+            .get(index.saturating_sub(1))
+            .map(|(_, transform)| *transform)
+    }
+}
+
+fn record_transform_history(
+    mut emitters: Query<(&GlobalTransform, &mut TransformHistory), With<LatencyCompensated>>,
+    info: Res<HrtfLatencyInfo>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_secs_f64();
+    let horizon = now - info.latency().as_secs_f64();
+
+    for (transform, mut history) in emitters.iter_mut() {
+        history.0.push_back((now, *transform));
+
+        // Keep exactly one snapshot older than the horizon
+        // so lookups always have something to land on.
+        while history.0.get(1).is_some_and(|(t, _)| *t <= horizon) {
+            history.0.pop_front();
+        }
+    }
+}
+
+/// Reads emitter transforms delayed by the current [`HrtfLatencyInfo`].
+///
+/// ```ignore
+/// fn flash(delayed: DelayedTransforms, hits: Query<Entity, With<Hit>>) {
+///     for entity in hits.iter() {
+///         if let Some(transform) = delayed.get(entity) {
+///             // spawn the flash where the hit is heard, not where it is
+///         }
+///     }
+/// }
+/// ```
+#[derive(SystemParam)]
+pub struct DelayedTransforms<'w, 's> {
+    histories: Query<'w, 's, &'static TransformHistory>,
+    info: Res<'w, HrtfLatencyInfo>,
+    time: Res<'w, Time>,
+}
+
+impl DelayedTransforms<'_, '_> {
+    /// The transform `entity` had one HRTF latency ago.
+    pub fn get(&self, entity: Entity) -> Option<GlobalTransform> {
+        let target = self.time.elapsed_secs_f64() - self.info.latency().as_secs_f64();
+        self.histories.get(entity).ok()?.sample(target)
+    }
+}
@@ -0,0 +1,228 @@
+//! A safety stage at the output of the HRTF nodes.
+//!
+//! Hot datasets can push the binaural output past full scale. On
+//! native targets the system mixer usually absorbs this, but WebAudio
+//! has no limiter, so the nodes can optionally clamp or soft-clip
+//! their output. Every sample that crosses the ceiling is counted and
+//! reported through [`CLIPPED_SAMPLES`] so gain problems show up in
+//! diagnostics instead of only being heard.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::{
+    diagnostic::{DiagnosticPath, Diagnostics},
+    prelude::*,
+};
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig};
+
+/// The number of output samples that crossed an HRTF node's ceiling
+/// during the last frame.
+pub const CLIPPED_SAMPLES: DiagnosticPath = DiagnosticPath::const_new("hrtf/clipped_samples");
+
+/// The output stage of an HRTF node.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub enum OutputSafety {
+    /// The output is left untouched.
+    Off,
+    /// Samples are hard-clamped to `±ceiling`.
+    Clamp { ceiling: f32 },
+    /// Samples beyond `±ceiling` are smoothly saturated
+    /// towards full scale.
+    ///
+    /// `ceiling` should be below 1.0 to leave room for the knee.
+    SoftClip { ceiling: f32 },
+}
+
+impl Default for OutputSafety {
+    /// Off on native targets and a soft clip at 0.9 on the web.
+    fn default() -> Self {
+        if cfg!(target_arch = "wasm32") {
+            Self::SoftClip { ceiling: 0.9 }
+        } else {
+            Self::Off
+        }
+    }
+}
+
+impl OutputSafety {
+    /// The transfer curve for a single sample.
+    ///
+    /// Samples within the ceiling are returned unchanged.
+    pub fn apply(self, sample: f32) -> f32 {
+        match self {
+            Self::Off => sample,
+            Self::Clamp { ceiling } => {
+                let ceiling = sanitize_ceiling(ceiling);
+                sample.clamp(-ceiling, ceiling)
+            }
+            Self::SoftClip { ceiling } => {
+                let ceiling = sanitize_ceiling(ceiling);
+                let magnitude = sample.abs();
+                if magnitude <= ceiling {
+                    return sample;
+                }
+
+                // A rational saturator with unit slope at the knee,
+                // approaching full scale asymptotically.
+                let headroom = (1.0 - ceiling).max(f32::EPSILON);
+                let excess = (magnitude - ceiling) / headroom;
+                let saturated = ceiling + headroom * excess / (1.0 + excess);
+
+                saturated.copysign(sample)
+            }
+        }
+    }
+
+    /// Process a buffer in place, returning the number
+    /// of samples that crossed the ceiling.
+    pub fn process(self, samples: &mut [f32]) -> u64 {
+        let ceiling = match self {
+            Self::Off => return 0,
+            Self::Clamp { ceiling } | Self::SoftClip { ceiling } => sanitize_ceiling(ceiling),
+        };
+
+        let mut clipped = 0;
+        for sample in samples {
+            if sample.abs() > ceiling {
+                *sample = self.apply(*sample);
+                clipped += 1;
+            }
+        }
+
+        clipped
+    }
+}
+
+/// A ceiling that's safe to clamp to.
+///
+/// The ceiling is edited in the inspector, so a negative one is
+/// treated as zero and a NaN one as full scale rather than panicking
+/// in [`f32::clamp`] on the audio thread.
+fn sanitize_ceiling(ceiling: f32) -> f32 {
+    if ceiling.is_nan() {
+        1.0
+    } else {
+        ceiling.max(0.0)
+    }
+}
+
+/// Counts samples an HRTF processor has clipped.
+///
+/// Incremented on the audio thread and drained by the ECS.
+#[derive(Debug, Default)]
+pub struct ClipCounter(AtomicU64);
+
+impl ClipCounter {
+    pub fn add(&self, count: u64) {
+        if count > 0 {
+            self.0.fetch_add(count, Ordering::Relaxed);
+        }
+    }
+
+    /// Return the count and reset it to zero.
+    pub fn take(&self) -> u64 {
+        self.0.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Clipped samples accumulated across all HRTF nodes this frame.
+#[derive(Debug, Default, Resource)]
+pub struct ClippedSamples {
+    frame: u64,
+    /// The total since the app started.
+    pub total: u64,
+}
+
+/// Drain the clip counters of every `N` node into [`ClippedSamples`].
+pub fn collect_clipped_samples<N: HrtfNode>(
+    configs: Query<&N::Configuration, With<N>>,
+    mut clipped: ResMut<ClippedSamples>,
+) {
+    for config in configs.iter() {
+        let count = config.clip_counter().take();
+        clipped.frame += count;
+        clipped.total += count;
+    }
+}
+
+/// Publish this frame's clipped sample count to [`CLIPPED_SAMPLES`].
+pub fn report_clipped_samples(mut clipped: ResMut<ClippedSamples>, mut diagnostics: Diagnostics) {
+    let frame = core::mem::take(&mut clipped.frame);
+    diagnostics.add_measurement(&CLIPPED_SAMPLES, || frame as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn off_leaves_samples_untouched() {
+        let mut samples = [-2.0, -0.5, 0.0, 0.5, 2.0];
+        assert_eq!(OutputSafety::Off.process(&mut samples), 0);
+        assert_eq!(samples, [-2.0, -0.5, 0.0, 0.5, 2.0]);
+    }
+
+    #[test]
+    fn clamp_limits_to_the_ceiling() {
+        let clamp = OutputSafety::Clamp { ceiling: 0.8 };
+        let mut samples = [-2.0, -0.5, 0.0, 0.8, 0.9];
+
+        assert_eq!(clamp.process(&mut samples), 2);
+        assert_eq!(samples, [-0.8, -0.5, 0.0, 0.8, 0.8]);
+    }
+
+    #[test]
+    fn soft_clip_is_continuous_monotonic_and_bounded() {
+        let soft = OutputSafety::SoftClip { ceiling: 0.9 };
+
+        // Unchanged within the ceiling, and odd-symmetric.
+        assert_eq!(soft.apply(0.5), 0.5);
+        assert_eq!(soft.apply(-0.7), -0.7);
+        assert_eq!(soft.apply(-1.5), -soft.apply(1.5));
+
+        let mut previous = soft.apply(0.0);
+        for step in 1..=2000 {
+            let sample = step as f32 * 0.005;
+            let out = soft.apply(sample);
+            assert!(out >= previous, "not monotonic at {sample}");
+            assert!(out - previous <= 0.005 + 1e-6, "jumps at {sample}");
+            assert!(out < 1.0);
+            previous = out;
+        }
+    }
+
+    #[test]
+    fn invalid_ceilings_do_not_panic() {
+        for ceiling in [-1.0, f32::NAN, f32::INFINITY, 0.0] {
+            for safety in [
+                OutputSafety::Clamp { ceiling },
+                OutputSafety::SoftClip { ceiling },
+            ] {
+                let mut samples = [-2.0, -0.5, 0.0, 0.5, 2.0];
+                safety.process(&mut samples);
+                assert!(samples.iter().all(|sample| sample.is_finite()));
+                assert!(samples.iter().all(|sample| sample.abs() <= 2.0));
+            }
+        }
+
+        let mut samples = [0.5, -2.0];
+        OutputSafety::Clamp { ceiling: -1.0 }.process(&mut samples);
+        assert_eq!(samples, [0.0, 0.0]);
+
+        let mut samples = [0.5, -2.0];
+        OutputSafety::Clamp { ceiling: f32::NAN }.process(&mut samples);
+        assert_eq!(samples, [0.5, -1.0]);
+    }
+
+    #[test]
+    fn clip_counter_accumulates_until_taken() {
+        let counter = ClipCounter::default();
+        counter.add(3);
+        counter.add(0);
+        counter.add(4);
+
+        assert_eq!(counter.take(), 7);
+        assert_eq!(counter.take(), 0);
+    }
+}
@@ -0,0 +1,137 @@
+//! Switching the listener between 2D and 3D semantics at runtime.
+//!
+//! A [`SpatialListener2D`] hears every emitter as if it were in its
+//! horizontal plane, so elevation is flattened away. A
+//! [`SpatialListener3D`] hears the full direction. Press `D` to swap
+//! every listener between the two and hear what the flattening costs,
+//! for example on the elevation scene's emitter.
+//!
+//! The mode is kept in [`ListenerMode`], so listeners spawned later
+//! follow it, and can be chosen at startup with `--listener=2d` or
+//! `--listener=3d`.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::shadowing::WasdListener;
+
+pub struct ListenerModePlugin;
+
+impl Plugin for ListenerModePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ListenerMode::from_args(std::env::args().skip(1)))
+            .add_systems(Startup, spawn_hud)
+            .add_systems(
+                Update,
+                (
+                    // `D` moves the listener in the shadowing scene.
+                    toggle_listener_mode.run_if(not(resource_exists::<WasdListener>)),
+                    apply_listener_mode,
+                    update_hud,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Which listener component the demo's listeners use.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum ListenerMode {
+    /// [`SpatialListener2D`]: directions are flattened into the plane.
+    #[default]
+    Planar,
+    /// [`SpatialListener3D`]: elevation is kept.
+    Full,
+}
+
+impl ListenerMode {
+    /// Read `--listener=2d` or `--listener=3d` from the arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut mode = Self::default();
+
+        for arg in args {
+            match arg.strip_prefix("--listener=") {
+                Some("2d") => mode = Self::Planar,
+                Some("3d") => mode = Self::Full,
+                Some(other) => warn!("unknown listener mode `{other}`, expected `2d` or `3d`"),
+                None => {}
+            }
+        }
+
+        mode
+    }
+
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Planar => Self::Full,
+            Self::Full => Self::Planar,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Planar => "2D",
+            Self::Full => "3D",
+        }
+    }
+}
+
+/// Press `D` to swap between 2D and 3D listeners.
+fn toggle_listener_mode(input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<ListenerMode>) {
+    if input.just_pressed(KeyCode::KeyD) {
+        *mode = mode.toggled();
+        info!("listener mode: {}", mode.label());
+    }
+}
+
+/// Give every listener the component matching [`ListenerMode`].
+fn apply_listener_mode(
+    mode: Res<ListenerMode>,
+    listeners: Query<
+        (Entity, Has<SpatialListener2D>),
+        Or<(With<SpatialListener2D>, With<SpatialListener3D>)>,
+    >,
+    mut commands: Commands,
+) {
+    let planar = *mode == ListenerMode::Planar;
+
+    for (entity, is_planar) in listeners.iter() {
+        if is_planar == planar {
+            continue;
+        }
+
+        let mut listener = commands.entity(entity);
+        if planar {
+            listener
+                .remove::<SpatialListener3D>()
+                .insert(SpatialListener2D);
+        } else {
+            listener
+                .remove::<SpatialListener2D>()
+                .insert(SpatialListener3D);
+        }
+    }
+}
+
+#[derive(Component)]
+struct ListenerModeHud;
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        ListenerModeHud,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_hud(mode: Res<ListenerMode>, mut hud: Single<&mut Text, With<ListenerModeHud>>) {
+    if mode.is_changed() {
+        hud.0 = format!("listener: {} (D to toggle)", mode.label());
+    }
+}
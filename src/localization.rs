@@ -0,0 +1,407 @@
+//! Localization trials for comparing backends and datasets.
+//!
+//! Press `T` to start a session. Each trial plays a short burst from a
+//! hidden emitter at a random azimuth around the listener. Click where
+//! you heard it. The angular error is recorded against the active
+//! [`DemoChain`], so switching chains with `Tab` between trials builds
+//! a distribution per backend and dataset.
+//!
+//! While a session runs, the orbiting emitters are muted and gizmos are
+//! hidden so nothing gives the position away. Press `T` again to end
+//! it. The summary is shown on screen and every trial so far is
+//! written to `localization_trials.csv`.
+//!
+//! Azimuths are in degrees in the listener's frame, with 0 in front
+//! and positive to the right, as in [`direction_to_az_el`].
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::Path;
+
+use bevy::{prelude::*, window::PrimaryWindow};
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::mute::SpatialAudioMuted;
+use crate::rng::{DemoRng, RngStream};
+use crate::spatial::{Listeners, closest_listener, listener_frames};
+use crate::{DemoChain, Spinner, az_el_to_direction, direction_to_az_el, spawn_default_chain};
+
+pub struct LocalizationPlugin;
+
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, (init_session, spawn_summary))
+            .add_systems(
+                Update,
+                (
+                    toggle_session,
+                    mute_orbit,
+                    start_trial,
+                    record_response,
+                    update_summary,
+                )
+                    .chain()
+                    .run_if(resource_exists::<LocalizationSession>),
+            );
+    }
+}
+
+/// How far from the listener the hidden emitter plays, inside the
+/// orbit scene's wall so it's never occluded.
+const TRIAL_RADIUS: f32 = 120.0;
+
+/// The pause before each burst, in seconds.
+const TRIAL_DELAY_SECONDS: f32 = 1.0;
+
+const CSV_PATH: &str = "localization_trials.csv";
+
+/// One answered trial.
+#[derive(Debug, Clone, Copy)]
+pub struct TrialRecord {
+    pub chain: DemoChain,
+    pub target_azimuth: f32,
+    pub response_azimuth: f32,
+}
+
+impl TrialRecord {
+    /// The signed error from the target to the response,
+    /// in `[-180, 180)` degrees.
+    pub fn error(&self) -> f32 {
+        wrap_degrees(self.response_azimuth - self.target_azimuth)
+    }
+
+    /// Whether the response landed in the wrong front/back hemisphere,
+    /// closer to the target's mirror image across the interaural axis
+    /// than to the target itself.
+    pub fn front_back_confusion(&self) -> bool {
+        let target = self.target_azimuth.to_radians().cos();
+        let response = self.response_azimuth.to_radians().cos();
+        let mirrored = wrap_degrees(180.0 - self.response_azimuth);
+
+        target * response < 0.0
+            && wrap_degrees(mirrored - self.target_azimuth).abs() < self.error().abs()
+    }
+}
+
+fn wrap_degrees(degrees: f32) -> f32 {
+    (degrees + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Summary statistics for one chain's trials.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrialSummary {
+    pub trials: usize,
+    pub mean_absolute_error: f32,
+    /// The fraction of trials with a front/back confusion.
+    pub front_back_rate: f32,
+}
+
+impl TrialSummary {
+    fn from_trials<'a>(trials: impl IntoIterator<Item = &'a TrialRecord>) -> Self {
+        let mut count = 0;
+        let mut error = 0.0;
+        let mut confusions = 0;
+
+        for trial in trials {
+            count += 1;
+            error += trial.error().abs();
+            confusions += trial.front_back_confusion() as usize;
+        }
+
+        let count_f = count.max(1) as f32;
+        Self {
+            trials: count,
+            mean_absolute_error: error / count_f,
+            front_back_rate: confusions as f32 / count_f,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TrialPhase {
+    /// No session is running.
+    Idle,
+    /// The next burst plays once this many seconds have passed.
+    Delay(f32),
+    /// The burst is playing from `emitter` at `position`.
+    Listening { emitter: Entity, position: Vec3 },
+}
+
+/// The trials recorded so far and the one in progress.
+#[derive(Resource)]
+pub struct LocalizationSession {
+    pub trials: Vec<TrialRecord>,
+    phase: TrialPhase,
+    rng: RngStream,
+    /// Whether gizmos were enabled before the session hid them.
+    gizmos_enabled: bool,
+}
+
+impl LocalizationSession {
+    pub fn is_running(&self) -> bool {
+        !matches!(self.phase, TrialPhase::Idle)
+    }
+
+    /// Summaries keyed by chain, in the order they're listed.
+    pub fn summaries(&self) -> BTreeMap<String, TrialSummary> {
+        let mut chains: BTreeMap<String, Vec<&TrialRecord>> = BTreeMap::new();
+        for trial in &self.trials {
+            chains
+                .entry(format!("{:?}", trial.chain))
+                .or_default()
+                .push(trial);
+        }
+
+        chains
+            .into_iter()
+            .map(|(chain, trials)| (chain, TrialSummary::from_trials(trials)))
+            .collect()
+    }
+
+    /// Write every trial as CSV.
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+
+        writeln!(
+            file,
+            "trial,chain,target_azimuth,response_azimuth,error,front_back_confusion"
+        )?;
+        for (index, trial) in self.trials.iter().enumerate() {
+            writeln!(
+                file,
+                "{},{:?},{:.2},{:.2},{:.2},{}",
+                index + 1,
+                trial.chain,
+                trial.target_azimuth,
+                trial.response_azimuth,
+                trial.error(),
+                trial.front_back_confusion(),
+            )?;
+        }
+
+        file.flush()
+    }
+}
+
+fn init_session(rng: Res<DemoRng>, mut commands: Commands) {
+    commands.insert_resource(LocalizationSession {
+        trials: Vec::new(),
+        phase: TrialPhase::Idle,
+        rng: rng.stream("localization"),
+        gizmos_enabled: true,
+    });
+}
+
+/// Marks an emitter muted for the session, so only those are unmuted after.
+#[derive(Component)]
+struct MutedForTrials;
+
+/// Press `T` to start or end a session.
+fn toggle_session(
+    input: Res<ButtonInput<KeyCode>>,
+    mut session: ResMut<LocalizationSession>,
+    mut gizmos: ResMut<GizmoConfigStore>,
+    muted: Query<Entity, With<MutedForTrials>>,
+    mut commands: Commands,
+) {
+    if !input.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+
+    let (config, _) = gizmos.config_mut::<DefaultGizmoConfigGroup>();
+
+    if !session.is_running() {
+        info!("localization session started; click where you hear each burst");
+        session.gizmos_enabled = config.enabled;
+        config.enabled = false;
+        session.phase = TrialPhase::Delay(TRIAL_DELAY_SECONDS);
+        return;
+    }
+
+    if let TrialPhase::Listening { emitter, .. } = session.phase {
+        commands.entity(emitter).try_despawn();
+    }
+    session.phase = TrialPhase::Idle;
+    config.enabled = session.gizmos_enabled;
+
+    for entity in muted.iter() {
+        commands
+            .entity(entity)
+            .remove::<(SpatialAudioMuted, MutedForTrials)>();
+    }
+
+    for (chain, summary) in session.summaries() {
+        info!(
+            "{chain}: {} trials, {:.1}° mean absolute error, {:.0}% front/back confusions",
+            summary.trials,
+            summary.mean_absolute_error,
+            summary.front_back_rate * 100.0,
+        );
+    }
+
+    if !session.trials.is_empty() {
+        match session.write_csv(Path::new(CSV_PATH)) {
+            Ok(()) => info!("wrote {} trials to {CSV_PATH}", session.trials.len()),
+            Err(e) => error!("failed to write localization trials: {e}"),
+        }
+    }
+}
+
+/// Keep the orbiting emitters muted for the session, including
+/// any spawned while it runs.
+fn mute_orbit(
+    session: Res<LocalizationSession>,
+    emitters: Query<Entity, (With<Spinner>, Without<SpatialAudioMuted>)>,
+    mut commands: Commands,
+) {
+    if !session.is_running() {
+        return;
+    }
+
+    for entity in emitters.iter() {
+        commands
+            .entity(entity)
+            .insert((SpatialAudioMuted, MutedForTrials));
+    }
+}
+
+/// Play the next burst from a random azimuth once the delay has passed.
+fn start_trial(
+    mut session: ResMut<LocalizationSession>,
+    listeners: Listeners,
+    server: Res<AssetServer>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let TrialPhase::Delay(remaining) = session.phase else {
+        return;
+    };
+
+    let remaining = remaining - time.delta_secs();
+    if remaining > 0.0 {
+        session.phase = TrialPhase::Delay(remaining);
+        return;
+    }
+
+    let Some(listener) = listener_frames(&listeners).next() else {
+        return;
+    };
+
+    // Place the emitter in the listener's frame, so the azimuth is
+    // the one heard whatever the listener's orientation.
+    let azimuth = session.rng.range(-180.0, 180.0);
+    let direction = az_el_to_direction(azimuth, 0.0);
+    let position =
+        listener.translation() + listener.world_direction(direction).normalize() * TRIAL_RADIUS;
+
+    // No mesh, so only the burst gives the position away.
+    let mut emitter = commands.spawn((
+        SamplePlayer::new(server.load("caw.ogg")),
+        Transform::from_translation(position),
+    ));
+    spawn_default_chain(&mut emitter, &buses, *chain);
+
+    session.phase = TrialPhase::Listening {
+        emitter: emitter.id(),
+        position,
+    };
+}
+
+/// Record where the burst was heard when the window is clicked.
+fn record_response(
+    mut session: ResMut<LocalizationSession>,
+    input: Res<ButtonInput<MouseButton>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    listeners: Listeners,
+    chain: Res<DemoChain>,
+    mut commands: Commands,
+) {
+    let TrialPhase::Listening { emitter, position } = session.phase else {
+        return;
+    };
+    if !input.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let (camera, camera_transform) = *camera;
+    let Some(click) = window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+    else {
+        return;
+    };
+    let Some(listener) = closest_listener(&listeners, position) else {
+        return;
+    };
+
+    let azimuth_of = |point: Vec3| {
+        let (azimuth, _) =
+            direction_to_az_el(listener.relative_direction(point - listener.translation()));
+        azimuth
+    };
+
+    let trial = TrialRecord {
+        chain: *chain,
+        target_azimuth: azimuth_of(position),
+        response_azimuth: azimuth_of(click.extend(position.z)),
+    };
+    info!(
+        "trial {}: target {:.0}°, heard {:.0}°, error {:.0}°",
+        session.trials.len() + 1,
+        trial.target_azimuth,
+        trial.response_azimuth,
+        trial.error(),
+    );
+
+    session.trials.push(trial);
+    commands.entity(emitter).try_despawn();
+    session.phase = TrialPhase::Delay(TRIAL_DELAY_SECONDS);
+}
+
+#[derive(Component)]
+struct TrialSummaryHud;
+
+fn spawn_summary(mut commands: Commands) {
+    commands.spawn((
+        TrialSummaryHud,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(28.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_summary(
+    session: Res<LocalizationSession>,
+    mut hud: Single<&mut Text, With<TrialSummaryHud>>,
+) {
+    if !session.is_changed() {
+        return;
+    }
+
+    let mut text = String::new();
+    if session.is_running() {
+        text.push_str(&format!(
+            "localization trial {} (click where you hear it, T to end)\n",
+            session.trials.len() + 1
+        ));
+    }
+    for (chain, summary) in session.summaries() {
+        text.push_str(&format!(
+            "{chain}: {} trials, MAE {:.1}°, front/back {:.0}%\n",
+            summary.trials,
+            summary.mean_absolute_error,
+            summary.front_back_rate * 100.0,
+        ));
+    }
+
+    hud.0 = text;
+}
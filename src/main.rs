@@ -1,53 +1,297 @@
 #![allow(clippy::type_complexity)]
+// The lite profile and a build without any spatializer leave the
+// convolution backends' safety, crossover and debug stages unused.
+#![cfg_attr(
+    not(any(feature = "sofar", feature = "fyrox")),
+    expect(dead_code, reason = "only the convolution backends use every stage")
+)]
 
 use std::f32::consts::TAU;
 
 use bevy::{
-    color::palettes::css::{BLUE, GREEN},
+    color::palettes::css::{BLUE, GRAY, GREEN},
+    ecs::system::SystemParam,
     prelude::*,
 };
 use bevy_seedling::prelude::*;
 
+use automation::{AddAutomation, Automate, AutomationTarget, LfoShape, ReverbParam};
+use bus::{FreeverbConfig, ReverbBus, ReverbBuses, ReverbEntity};
+use falloff::{FalloffVolume, SpatialAudioFalloff};
+use group::{GroupConfig, SpatialAudioGroup, SpatialAudioGroupConfig};
+use latency::{DelayedTransforms, LatencyCompensated};
+use mute::SpatialAudioMuted;
+use occlusion::{Occluder, OcclusionFilterNode, OcclusionPath};
+use remap::AzimuthRemap;
+use spatial::{AxisConvention, ForwardAxis2D, SpatialAudioCommand, SpatialAudioCommandKind};
+use state::AppState;
+use strip::channel_strip;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+use template::Spatial;
+use throttle::{EmitterDescriptor, PendingEmitters};
+use timeline::TimelineTrack;
+use transition::{SceneTransition, TransitionSceneExt};
+
+pub use spatial::{az_el_to_direction, direction_to_az_el};
+
+mod ab_test;
+mod automation;
+mod bezier;
+mod binaural;
+mod biquad;
+#[cfg(feature = "sofar")]
+mod blocks;
+mod bus;
+#[cfg(feature = "sofar")]
+mod conditioning;
+mod config;
+#[cfg(any(feature = "sofar", feature = "fyrox", feature = "lite"))]
+mod crossover;
+#[cfg(feature = "sofar")]
+mod custom;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod dataset;
+#[cfg(feature = "demo-ui")]
+mod demo_ui;
+mod determinism;
+mod dilation;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod downmix;
+mod earphone;
+mod elevation;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod fallback;
+mod falloff;
 #[cfg(feature = "fyrox")]
 mod fyrox_hrtf;
+mod group;
+mod impulse;
+#[cfg(feature = "inspector")]
+mod inspector;
+mod latency;
+mod limiter;
+mod listener_mode;
+mod localization;
+#[cfg(feature = "sofar")]
+mod morph;
+mod mute;
+#[cfg(feature = "null-backend")]
+mod null_backend;
+mod occlusion;
+mod pin;
+mod preflight;
+#[cfg(feature = "sofar")]
+mod prewarm;
+mod probe;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod quad;
+mod radius;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod rate;
+#[cfg(feature = "realfft-backend")]
+mod realfft_hrtf;
+mod record;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod reflection;
+mod remap;
+mod rng;
+mod scene;
+mod shadowing;
+#[cfg(feature = "lite")]
+mod simple_pan;
+#[cfg(feature = "sofar")]
+mod sofar_ambisonic;
 #[cfg(feature = "sofar")]
 mod sofar_hrtf;
+mod spatial;
+#[cfg_attr(
+    not(feature = "demo-ui"),
+    expect(dead_code, reason = "only the control panel is backend-independent")
+)]
+mod spatial_audio;
+mod stale;
+mod state;
+mod strip;
+mod sync;
+#[cfg(any(feature = "sofar", feature = "fyrox", feature = "lite"))]
+mod tail;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod telemetry;
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+mod template;
+mod throttle;
+mod timeline;
+mod trail;
+mod transition;
+mod validate;
+mod vr;
+mod wav;
 
 fn main() {
     let mut app = App::new();
+    let scene = DemoScene::from_args(std::env::args().skip(1));
 
     app.add_plugins(DefaultPlugins.set(AssetPlugin {
         meta_check: bevy::asset::AssetMetaCheck::Never,
         ..Default::default()
     }))
-    .add_systems(Startup, startup)
-    .add_systems(Update, spinner);
-
-    #[cfg(target_arch = "wasm32")]
-    app.add_plugins(
-        bevy_seedling::SeedlingPlugin::<firewheel_web_audio::WebAudioBackend> {
-            config: Default::default(),
-            stream_config: Default::default(),
-            spawn_default_pool: true,
-            pool_size: 4..=32,
-        },
+    .init_resource::<SpinPeriod>()
+    .insert_resource(scene)
+    .init_resource::<DemoChain>()
+    .insert_resource(rng::DemoRng::from_args(std::env::args().skip(1)))
+    .insert_resource(determinism::time_strategy_from_args(
+        std::env::args().skip(1),
+    ))
+    .add_systems(
+        Startup,
+        (
+            rng::log_seed,
+            automate_reverb,
+            startup,
+            elevation::spawn_elevation_scene.run_if(resource_equals(DemoScene::Elevation)),
+            bezier::spawn_bezier_scene.run_if(resource_equals(DemoScene::Bezier)),
+            ab_test::spawn_ab_scene.run_if(resource_equals(DemoScene::ABTest)),
+            shadowing::spawn_shadow_scene.run_if(resource_equals(DemoScene::Shadow)),
+            #[cfg(any(feature = "sofar", feature = "fyrox"))]
+            quad::spawn_quad_scene.run_if(resource_equals(DemoScene::Quad)),
+        )
+            .chain(),
+    )
+    .add_systems(
+        Update,
+        (
+            (spinner, orbit_around_listener).run_if(in_state(AppState::Playing)),
+            draw_heard_position,
+            toggle_bypass,
+            toggle_mute,
+            toggle_group_mute,
+            cycle_axis_convention,
+            toggle_forward_axis,
+            cycle_azimuth_remap,
+            cycle_chain,
+        ),
     );
 
-    #[cfg(not(target_arch = "wasm32"))]
-    app.add_plugins(bevy_seedling::SeedlingPlugin::default());
+    let spatial = spatial::SpatialAudioPlugin::from_toml("audio_config.toml").unwrap_or_else(|e| {
+        error!("{e}, using the defaults");
+        default()
+    });
+
+    // We'll add a little reverb to make it epic,
+    // unless the config picks its own
+    let spatial = match spatial.reverb {
+        Some(_) => spatial,
+        None => spatial.with_reverb(FreeverbConfig {
+            room_size: 0.85,
+            damping: 0.9,
+            width: 0.9,
+        }),
+    }
+    .with_args(std::env::args().skip(1));
+
+    // `--freeze-directions` swaps the direction system for one that
+    // does nothing, so every node keeps the direction it spawned with.
+    let spatial = if std::env::args().any(|arg| arg == "--freeze-directions") {
+        spatial.with_custom_direction_system(|| {})
+    } else {
+        spatial
+    };
+
+    // `--debug-systems` runs the gizmos, trails and statistics
+    // in release builds too.
+    let spatial = if std::env::args().any(|arg| arg == "--debug-systems") {
+        spatial.with_debug_filter(|| true)
+    } else {
+        spatial
+    };
 
+    // `--passthrough-hrtf` swaps the sofar renderer for a plain
+    // downmix through the custom processor hook, for comparison.
     #[cfg(feature = "sofar")]
-    app.add_plugins(sofar_hrtf::SofarPlugin);
+    let spatial = if std::env::args().any(|arg| arg == "--passthrough-hrtf") {
+        spatial.with_custom_hrtf_processor(|_, _| fallback::PassthroughProcessor)
+    } else {
+        spatial
+    };
+
+    app.add_plugins((
+        spatial,
+        record::SpatialAudioRecordPlugin,
+        automation::AutomationPlugin,
+        timeline::TimelinePlugin,
+        latency::LatencyCompensator,
+        state::AppStatePlugin,
+        impulse::ImpulseResponsePlugin,
+        validate::SpatialValidationPlugin,
+        elevation::ElevationScenePlugin,
+        bezier::BezierEmitterPlugin,
+        sync::SpatialAudioSyncPlugin,
+        occlusion::OcclusionPlugin,
+        falloff::FalloffPlugin,
+        throttle::SpawnThrottlePlugin,
+        scene::SpatialAudioSceneHook,
+    ))
+    .add_plugins((
+        listener_mode::ListenerModePlugin,
+        transition::SceneTransitionPlugin,
+        localization::LocalizationPlugin,
+        ab_test::ABTestPlugin,
+        shadowing::AcousticShadowPlugin,
+        probe::ListenerProbePlugin,
+        radius::ImportanceRadiusPlugin,
+        binaural::BinauralBusPlugin::default(),
+        dilation::TimeDilationPlugin {
+            pitch: dilation::DilationPitch::from_args(std::env::args().skip(1)),
+        },
+        preflight::AssetPreflightPlugin {
+            samples: scene.samples(),
+        },
+        #[cfg(any(feature = "sofar", feature = "fyrox"))]
+        reflection::ReflectionPlugin,
+    ))
+    .add_automation::<SpinnerParam>();
+
+    #[cfg(feature = "sofar")]
+    app.add_plugins(sofar_hrtf::SofarPlugin {
+        conditioning: conditioning::HrirConditioning::from_args(std::env::args().skip(1)),
+        morph_target: morph::MorphTarget::from_args(std::env::args().skip(1)),
+        ..default()
+    });
     #[cfg(feature = "fyrox")]
-    app.add_plugins(fyrox_hrtf::FyroxPlugin);
+    app.add_plugins(fyrox_hrtf::FyroxPlugin::default());
+    #[cfg(feature = "realfft-backend")]
+    app.add_plugins(realfft_hrtf::RealFftPlugin::default());
+    #[cfg(feature = "lite")]
+    app.add_plugins(simple_pan::SimplePanPlugin);
+    #[cfg(feature = "demo-ui")]
+    app.add_plugins(demo_ui::SpatialAudioDemoUI);
+    #[cfg(feature = "inspector")]
+    app.add_plugins((
+        bevy_inspector_egui::quick::WorldInspectorPlugin::new(),
+        inspector::SpatialAudioInspectorPlugin,
+    ));
 
     app.run();
 }
 
+/// Press `L` to slowly grow the shared reverb's room while the orbit shrinks.
+fn automate_reverb(reverb: Res<ReverbEntity>, mut commands: Commands) {
+    commands.entity(reverb.0).insert(Automate::new(
+        ReverbParam::RoomSize,
+        LfoShape::Ramp,
+        1.0 / 30.0,
+        0.14,
+        0.85,
+    ));
+}
+
 fn startup(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
     server: Res<AssetServer>,
+    buses: Res<ReverbBuses>,
+    scene: Res<DemoScene>,
+    chain: Res<DemoChain>,
     mut commands: Commands,
 ) {
     commands.spawn(Camera2d);
@@ -58,33 +302,72 @@ fn startup(
     let listener_circle = meshes.add(Circle::new(35.0));
     let listener_material = materials.add(Color::from(BLUE));
 
-    // We'll add a little reverb to make it epic
-    let reverb = commands
-        .spawn(FreeverbNode {
-            room_size: 0.85,
-            damping: 0.9,
-            width: 0.9,
-        })
-        .id();
-
-    spawn_one(
-        &mut commands,
+    let assets = DemoAssets {
         emitter_circle,
         emitter_material,
-        &server,
-        reverb,
-        0.0,
-        Volume::Decibels(-12.0),
-    );
+    };
 
-    // spawn_n(
-    //     &mut commands,
-    //     emitter_circle,
-    //     emitter_material,
-    //     &server,
-    //     reverb,
-    //     128,
-    // );
+    if *scene == DemoScene::Orbit {
+        let emitter = spawn_one(
+            &mut commands,
+            &assets,
+            &server,
+            &buses,
+            *chain,
+            0.0,
+            ORBIT_VOLUME,
+        );
+        commands.entity(emitter).insert((
+            TimelineTrack(1),
+            // Fade a little as the orbit widens.
+            SpatialAudioFalloff::sigmoid(400.0, 0.7, 12.0),
+            // Surge and stall, so directions change at varying rates.
+            SpeedCurve::new(vec![(0.0, 1.0), (4.0, 2.5), (6.0, 0.4), (10.0, 1.0)]),
+        ));
+
+        // A wall for the orbit to pass behind.
+        let wall_size = Vec2::new(200.0, 16.0);
+        commands.spawn((
+            Mesh2d(meshes.add(Rectangle::from_size(wall_size))),
+            MeshMaterial2d(materials.add(Color::from(GRAY))),
+            Transform::from_xyz(0.0, 150.0, -1.0),
+            Occluder {
+                half_size: wall_size / 2.0,
+            },
+        ));
+
+        // A wall to the right of the listener that echoes the orbit as it passes.
+        #[cfg(any(feature = "sofar", feature = "fyrox"))]
+        commands.spawn((
+            Transform::from_xyz(280.0, 0.0, 0.0)
+                .with_rotation(Quat::from_rotation_z(core::f32::consts::FRAC_PI_2)),
+            reflection::ReflectiveSurface {
+                half_length: 200.0,
+                absorption: 0.3,
+                range: 200.0,
+            },
+        ));
+
+        // The smallest spatial emitter: the backend plugin builds its chain.
+        // It circles the listener wherever the listener goes.
+        #[cfg(any(feature = "sofar", feature = "fyrox"))]
+        commands.spawn((
+            SamplePlayer::new(server.load("caw.ogg"))
+                .looping()
+                .with_volume(Volume::Decibels(-18.0)),
+            Transform::from_xyz(-300.0, 0.0, 0.0),
+            OrbitAroundListener {
+                radius: 300.0,
+                period: 45.0,
+                angle: core::f32::consts::PI,
+            },
+            Spatial,
+        ));
+    }
+
+    // spawn_n(&mut pending, *chain, 128);
+
+    commands.insert_resource(assets);
 
     // Then, we'll spawn a simple listener.
     //
@@ -94,85 +377,550 @@ fn startup(
         Mesh2d(listener_circle),
         MeshMaterial2d(listener_material),
         SpatialListener2D,
+        TimelineTrack(0),
     ));
 }
 
+/// The volume of the single orbiting emitter.
+const ORBIT_VOLUME: Volume = Volume::Decibels(-12.0);
+
 fn spawn_one(
     commands: &mut Commands,
-    emitter_circle: Handle<Mesh>,
-    emitter_material: Handle<ColorMaterial>,
+    assets: &DemoAssets,
     server: &AssetServer,
-    reverb: Entity,
+    buses: &ReverbBuses,
+    chain: DemoChain,
     angle: f32,
     volume: Volume,
-) {
-    // Here we spawn a sample player with a spatial effect,
-    // making sure our sample player entity has a transform.
+) -> Entity {
+    // Here we spawn a sample player with a spatial effect.
+    // `Mesh2d` requires the transform the emitter is placed with.
     //
     // The emitter will circle the listener.
-    commands.spawn((
-        Mesh2d(emitter_circle.clone()),
-        MeshMaterial2d(emitter_material.clone()),
+    let mut emitter = commands.spawn((
+        Mesh2d(assets.emitter_circle.clone()),
+        MeshMaterial2d(assets.emitter_material.clone()),
         SamplePlayer::new(server.load("divine_comedy.ogg"))
             .looping()
             .with_volume(volume),
-        Transform::default(),
-        #[cfg(feature = "sofar")]
-        sample_effects![
-            SendNode::new(Volume::Linear(0.5), reverb),
-            sofar_hrtf::SofarHrtfNode::default(),
-        ],
-        #[cfg(feature = "fyrox")]
-        sample_effects![
-            SendNode::new(Volume::Linear(0.5), reverb),
-            fyrox_hrtf::FyroxHrtfNode::default(),
-            VolumeNode {
+        Spinner::new(angle),
+        SpatialAudioGroup(EMITTER_GROUP),
+        LatencyCompensated,
+        Automate::new(
+            SpinnerParam::Radius,
+            LfoShape::Ramp,
+            1.0 / 30.0,
+            -150.0,
+            250.0,
+        ),
+    ));
+
+    spawn_default_chain(&mut emitter, buses, chain);
+    emitter.id()
+}
+
+/// Spawn the spatial effect chain `kind` on `emitter`,
+/// sending to the main reverb bus if there is one.
+fn spawn_default_chain(emitter: &mut EntityCommands, buses: &ReverbBuses, kind: DemoChain) {
+    emitter.with_related_entities::<EffectOf>(|chain| {
+        // The dry path runs through both occlusion filters, the reverb send only the first.
+        chain.spawn((OcclusionFilterNode::default(), OcclusionPath::Send));
+        if let Some(send) = buses.send(ReverbBus::MAIN.0, Volume::Linear(0.5)) {
+            chain.spawn(send);
+        }
+        chain.spawn((OcclusionFilterNode::default(), OcclusionPath::Direct));
+        chain.spawn((VolumeNode::default(), FalloffVolume));
+    });
+
+    match kind {
+        DemoChain::Panner => channel_strip!(emitter, {
+            spatial: SpatialBasicNode::default(),
+            volume: VolumeNode::default(),
+        }),
+        #[cfg(feature = "lite")]
+        DemoChain::SimplePan => channel_strip!(emitter, {
+            hrtf: simple_pan::SimplePanNode::default(),
+            volume: VolumeNode::default(),
+        }),
+        #[cfg(all(feature = "fyrox", not(feature = "lite")))]
+        DemoChain::Fyrox => channel_strip!(emitter, {
+            hrtf: fyrox_hrtf::FyroxHrtfNode::default() => fyrox_hrtf::HrtfStandby,
+            volume: VolumeNode {
                 volume: Volume::Decibels(18.0),
             },
-        ],
-        Spinner(angle),
-    ));
+        }),
+        #[cfg(all(feature = "sofar", not(feature = "lite")))]
+        DemoChain::Sofar => channel_strip!(emitter, {
+            hrtf: sofar_hrtf::SofarHrtfNode::default(),
+            volume: VolumeNode::default(),
+        }),
+        #[cfg(all(feature = "realfft-backend", not(feature = "lite")))]
+        DemoChain::RealFft => channel_strip!(emitter, {
+            hrtf: realfft_hrtf::RealFftHrtfNode::default(),
+            volume: VolumeNode::default(),
+        }),
+    }
 }
 
-#[expect(unused)]
-fn spawn_n(
-    commands: &mut Commands,
-    emitter_circle: Handle<Mesh>,
-    emitter_material: Handle<ColorMaterial>,
-    server: &AssetServer,
-    reverb: Entity,
-    total: usize,
-) {
+/// The spatial effect chains this build can spawn.
+///
+/// Press `Tab` to respawn the orbiting emitters with the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+enum DemoChain {
+    /// bevy_seedling's own [`SpatialBasicNode`], for comparison.
+    Panner,
+    #[cfg(feature = "lite")]
+    SimplePan,
+    #[cfg(all(feature = "fyrox", not(feature = "lite")))]
+    Fyrox,
+    #[cfg(all(feature = "sofar", not(feature = "lite")))]
+    Sofar,
+    #[cfg(all(feature = "realfft-backend", not(feature = "lite")))]
+    RealFft,
+}
+
+impl DemoChain {
+    /// Every chain, in the order `Tab` cycles through them.
+    const ALL: &[Self] = &[
+        Self::Panner,
+        #[cfg(feature = "lite")]
+        Self::SimplePan,
+        #[cfg(all(feature = "fyrox", not(feature = "lite")))]
+        Self::Fyrox,
+        #[cfg(all(feature = "sofar", not(feature = "lite")))]
+        Self::Sofar,
+        #[cfg(all(feature = "realfft-backend", not(feature = "lite")))]
+        Self::RealFft,
+    ];
+
+    fn next(self) -> Self {
+        let index = Self::ALL
+            .iter()
+            .position(|c| *c == self)
+            .unwrap_or_default();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl Default for DemoChain {
+    /// The last backend enabled, or the stock panner without one.
+    fn default() -> Self {
+        Self::ALL[Self::ALL.len() - 1]
+    }
+}
+
+/// Queue `total` emitters evenly spaced around the orbit,
+/// replacing any that are still queued.
+#[cfg_attr(not(feature = "demo-ui"), expect(unused))]
+fn spawn_n(pending: &mut PendingEmitters, chain: DemoChain, total: usize) {
     let volume = 0.1;
 
+    pending.clear();
     for i in 0..total {
-        let angle = (i as f32 / total as f32) * TAU;
-
-        spawn_one(
-            commands,
-            emitter_circle.clone(),
-            emitter_material.clone(),
-            server,
-            reverb,
-            angle,
-            Volume::Linear(volume),
-        );
+        pending.push(EmitterDescriptor {
+            chain,
+            angle: (i as f32 / total as f32) * TAU,
+            volume: Volume::Linear(volume),
+            track: Some(TimelineTrack(1 + i as u32)),
+        });
     }
 }
 
+/// What's needed to spawn more emitters after startup.
+#[derive(Clone, Resource)]
+struct DemoAssets {
+    emitter_circle: Handle<Mesh>,
+    emitter_material: Handle<ColorMaterial>,
+}
+
+/// Which scene the demo starts in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+enum DemoScene {
+    /// An emitter circling the listener.
+    #[default]
+    Orbit,
+    /// An emitter sweeping in elevation, see [`elevation`].
+    Elevation,
+    /// An emitter following a curve past the listener, see [`bezier`].
+    Bezier,
+    /// Emitters in front and behind to swap, see [`ab_test`].
+    ABTest,
+    /// An emitter behind pillars, see [`shadowing`].
+    Shadow,
+    /// A four-channel emitter, see [`quad`].
+    #[cfg(any(feature = "sofar", feature = "fyrox"))]
+    Quad,
+}
+
+impl DemoScene {
+    /// Read `--scene=orbit`, `--scene=elevation`, `--scene=bezier`,
+    /// `--scene=ab`, `--scene=shadow` or `--scene=quad` from the arguments.
+    fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut scene = Self::default();
+
+        for arg in args {
+            match arg.strip_prefix("--scene=") {
+                Some("orbit") => scene = Self::Orbit,
+                Some("elevation") => scene = Self::Elevation,
+                Some("bezier") => scene = Self::Bezier,
+                Some("ab") => scene = Self::ABTest,
+                Some("shadow") => scene = Self::Shadow,
+                #[cfg(any(feature = "sofar", feature = "fyrox"))]
+                Some("quad") => scene = Self::Quad,
+                Some(other) => {
+                    warn!(
+                        "unknown scene `{other}`, expected `orbit`, `elevation`, `bezier`, `ab`, `shadow` or `quad`"
+                    )
+                }
+                None => {}
+            }
+        }
+
+        scene
+    }
+
+    /// The samples the scene plays, including the caw of
+    /// localization trials, which can run in any scene.
+    fn samples(self) -> Vec<&'static str> {
+        let samples: &[&'static str] = match self {
+            Self::Orbit | Self::Elevation | Self::Bezier | Self::ABTest | Self::Shadow => {
+                &["divine_comedy.ogg", "caw.ogg"]
+            }
+            #[cfg(any(feature = "sofar", feature = "fyrox"))]
+            Self::Quad => &["quad_test.wav", "caw.ogg"],
+        };
+
+        samples.to_vec()
+    }
+}
+
+/// How long the emitters take to orbit once, in seconds.
+#[derive(Resource)]
+struct SpinPeriod(f32);
+
+impl Default for SpinPeriod {
+    fn default() -> Self {
+        Self(20.0)
+    }
+}
+
+/// The mixing group of the spinning emitters.
+const EMITTER_GROUP: u32 = 0;
+
 #[derive(Component)]
-struct Spinner(f32);
+struct Spinner {
+    angle: f32,
+    radius: f32,
+}
+
+impl Spinner {
+    fn new(angle: f32) -> Self {
+        Self {
+            angle,
+            radius: 250.0,
+        }
+    }
+}
+
+/// Varies a [`Spinner`]'s speed over time.
+///
+/// Keyframes are `(time_seconds, speed_multiplier)` pairs, linearly
+/// interpolated. The curve loops after the last keyframe, so it
+/// should usually end on the value it starts with.
+#[derive(Debug, Clone, Component)]
+struct SpeedCurve {
+    keyframes: Vec<(f32, f32)>,
+}
+
+impl SpeedCurve {
+    fn new(mut keyframes: Vec<(f32, f32)>) -> Self {
+        keyframes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { keyframes }
+    }
+
+    /// The speed multiplier `seconds` into the curve.
+    fn multiplier(&self, seconds: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.keyframes.first(), self.keyframes.last()) else {
+            return 1.0;
+        };
+        if last.0 <= 0.0 {
+            return first.1;
+        }
+
+        let t = seconds.rem_euclid(last.0);
+        match self.keyframes.iter().position(|(time, _)| *time > t) {
+            // Before the first keyframe, hold its value.
+            Some(0) => first.1,
+            Some(next) => {
+                let (t0, v0) = self.keyframes[next - 1];
+                let (t1, v1) = self.keyframes[next];
+                v0.lerp(v1, (t - t0) / (t1 - t0))
+            }
+            None => last.1,
+        }
+    }
+}
+
+/// Automatable [`Spinner`] parameters.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Reflect)]
+enum SpinnerParam {
+    #[default]
+    Radius,
+}
+
+impl AutomationTarget for SpinnerParam {
+    type Component = Spinner;
 
-fn spinner(mut spinners: Query<(&mut Spinner, &mut Transform), With<Spinner>>, time: Res<Time>) {
-    for (mut spinner, mut transform) in spinners.iter_mut() {
-        let spin_radius = 250.0;
-        let spin_seconds = 20.0;
+    fn apply(self, component: &mut Self::Component, value: f32) {
+        match self {
+            Self::Radius => component.radius = value.max(0.0),
+        }
+    }
+}
+
+fn spinner(
+    mut spinners: Query<(&mut Spinner, &mut Transform, Option<&SpeedCurve>), With<Spinner>>,
+    period: Res<SpinPeriod>,
+    time: Res<Time>,
+) {
+    for (mut spinner, mut transform, curve) in spinners.iter_mut() {
+        let speed = curve.map_or(1.0, |curve| curve.multiplier(time.elapsed_secs()));
+        let spin_seconds = period.0.max(f32::EPSILON) / speed.max(0.0);
 
         let position =
-            Vec2::new(spinner.0.cos() * spin_radius, spinner.0.sin() * spin_radius).extend(0.0);
+            Vec2::new(spinner.angle.cos(), spinner.angle.sin()).extend(0.0) * spinner.radius;
 
         transform.translation = position;
 
-        spinner.0 += core::f32::consts::TAU * time.delta().as_secs_f32() / spin_seconds;
+        spinner.angle += core::f32::consts::TAU * time.delta().as_secs_f32() / spin_seconds;
+    }
+}
+
+/// Orbits an emitter around its closest listener rather than the origin,
+/// so it keeps circling a listener that moves.
+#[derive(Debug, Clone, Copy, Component)]
+struct OrbitAroundListener {
+    radius: f32,
+    /// How long one orbit takes, in seconds.
+    period: f32,
+    angle: f32,
+}
+
+fn orbit_around_listener(
+    mut emitters: Query<(&mut OrbitAroundListener, &mut Transform)>,
+    listeners: Query<
+        (Entity, &GlobalTransform),
+        Or<(With<SpatialListener2D>, With<SpatialListener3D>)>,
+    >,
+    time: Res<Time>,
+) {
+    for (mut orbit, mut transform) in emitters.iter_mut() {
+        // Follow the listener entities themselves, not a probe.
+        let center = spatial::find_closest_listener(
+            transform.translation,
+            listeners
+                .iter()
+                .map(|(entity, listener)| (listener.translation(), entity, ())),
+        )
+        .map_or(Vec3::ZERO, |(position, _)| position);
+
+        let offset = Vec2::from_angle(orbit.angle).extend(0.0) * orbit.radius;
+        transform.translation = center + offset;
+
+        orbit.angle += TAU * time.delta_secs() / orbit.period.max(f32::EPSILON);
+    }
+}
+
+/// Outline where each emitter is heard, one HRTF latency behind
+/// where it is drawn.
+fn draw_heard_position(
+    delayed: DelayedTransforms,
+    emitters: Query<Entity, With<LatencyCompensated>>,
+    mut gizmos: Gizmos,
+) {
+    for emitter in emitters.iter() {
+        if let Some(transform) = delayed.get(emitter) {
+            gizmos.circle_2d(transform.translation().truncate(), 30.0, Color::WHITE);
+        }
+    }
+}
+
+/// Press `B` to compare the spatialized emitters with the dry signal.
+fn toggle_bypass(
+    input: Res<ButtonInput<KeyCode>>,
+    emitters: Query<Entity, With<Spinner>>,
+    mut commands: EventWriter<SpatialAudioCommand>,
+    mut bypass: Local<bool>,
+) {
+    if !input.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    *bypass = !*bypass;
+    for target in emitters.iter() {
+        commands.write(SpatialAudioCommand {
+            target,
+            kind: SpatialAudioCommandKind::SetBypass(*bypass),
+        });
+    }
+}
+
+/// Press `M` to mute or unmute the emitters.
+fn toggle_mute(
+    input: Res<ButtonInput<KeyCode>>,
+    emitters: Query<(Entity, Has<SpatialAudioMuted>), With<Spinner>>,
+    mut commands: Commands,
+) {
+    if !input.just_pressed(KeyCode::KeyM) {
+        return;
+    }
+
+    for (emitter, muted) in emitters.iter() {
+        if muted {
+            commands.entity(emitter).remove::<SpatialAudioMuted>();
+        } else {
+            commands.entity(emitter).insert(SpatialAudioMuted);
+        }
+    }
+}
+
+/// Press `G` to mute or unmute the emitters' group.
+fn toggle_group_mute(
+    input: Res<ButtonInput<KeyCode>>,
+    mut groups: ResMut<SpatialAudioGroupConfig>,
+) {
+    if !input.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    let group = groups.0.entry(EMITTER_GROUP).or_insert(GroupConfig {
+        // Keep the fyrox chain's makeup gain.
+        #[cfg(feature = "fyrox")]
+        volume: Volume::Decibels(18.0),
+        ..default()
+    });
+    group.mute = !group.mute;
+}
+
+/// Everything [`spawn_one`] needs besides the emitter's own settings.
+#[derive(SystemParam)]
+struct EmitterResources<'w> {
+    assets: Option<Res<'w, DemoAssets>>,
+    server: Res<'w, AssetServer>,
+    buses: Res<'w, ReverbBuses>,
+}
+
+/// Press `Tab` to respawn the orbiting emitters with the next [`DemoChain`].
+fn cycle_chain(
+    input: Res<ButtonInput<KeyCode>>,
+    emitters: Query<(
+        Entity,
+        &Spinner,
+        &SamplePlayer,
+        Option<&TimelineTrack>,
+        Option<&SpatialAudioFalloff>,
+        Option<&SpeedCurve>,
+    )>,
+    resources: EmitterResources,
+    transition: Res<SceneTransition>,
+    mut chain: ResMut<DemoChain>,
+    mut commands: Commands,
+) {
+    // The emitters are captured below, so wait for
+    // any transition to finish replacing them.
+    if !input.just_pressed(KeyCode::Tab) || transition.is_active() {
+        return;
+    }
+    let Some(assets) = resources.assets else {
+        return;
+    };
+
+    *chain = chain.next();
+    info!("effect chain: {:?}", *chain);
+
+    let chain = *chain;
+    let assets = assets.clone();
+    let server = resources.server.clone();
+    let buses = resources.buses.clone();
+    let emitters: Vec<_> = emitters
+        .iter()
+        .map(|(entity, spinner, player, track, falloff, curve)| {
+            (
+                entity,
+                spinner.angle,
+                player.volume,
+                track.copied(),
+                falloff.cloned(),
+                curve.cloned(),
+            )
+        })
+        .collect();
+
+    commands.transition_scene(move |commands| {
+        for (entity, angle, volume, track, falloff, curve) in emitters {
+            commands.entity(entity).despawn();
+
+            let emitter = spawn_one(commands, &assets, &server, &buses, chain, angle, volume);
+            if let Some(track) = track {
+                commands.entity(emitter).insert(track);
+            }
+            if let Some(falloff) = falloff {
+                commands.entity(emitter).insert(falloff);
+            }
+            if let Some(curve) = curve {
+                commands.entity(emitter).insert(curve);
+            }
+        }
+    });
+}
+
+/// Press `C` to cycle the axis convention, moving where "front" is.
+fn cycle_axis_convention(input: Res<ButtonInput<KeyCode>>, mut convention: ResMut<AxisConvention>) {
+    if !input.just_pressed(KeyCode::KeyC) {
+        return;
     }
+
+    let index = AxisConvention::ALL
+        .iter()
+        .position(|c| c == &*convention)
+        .unwrap_or_default();
+    *convention = AxisConvention::ALL[(index + 1) % AxisConvention::ALL.len()];
+    info!("axis convention: {:?}", *convention);
+}
+
+/// Press `F` to turn 2D listeners between facing up, as in a top-down
+/// game, and facing right, as in a side-scroller.
+fn toggle_forward_axis(
+    input: Res<ButtonInput<KeyCode>>,
+    listeners: Query<(Entity, Option<&ForwardAxis2D>), With<SpatialListener2D>>,
+    mut commands: Commands,
+) {
+    if !input.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+
+    for (listener, forward) in listeners.iter() {
+        let forward = match forward.copied().unwrap_or_default() {
+            ForwardAxis2D::Up => ForwardAxis2D::Right,
+            ForwardAxis2D::Right => ForwardAxis2D::Up,
+        };
+        info!("listener forward axis: {forward:?}");
+        commands.entity(listener).insert(forward);
+    }
+}
+
+/// Press `K` to cycle the azimuth remap presets.
+fn cycle_azimuth_remap(input: Res<ButtonInput<KeyCode>>, mut remap: ResMut<AzimuthRemap>) {
+    if !input.just_pressed(KeyCode::KeyK) {
+        return;
+    }
+
+    let presets = AzimuthRemap::PRESETS;
+    let index = presets
+        .iter()
+        .position(|(_, preset)| preset() == *remap)
+        .map_or(0, |index| (index + 1) % presets.len());
+    let (name, preset) = presets[index];
+    *remap = preset();
+    info!("azimuth remap: {name}");
 }
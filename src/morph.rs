@@ -0,0 +1,320 @@
+//! Morphing between two SOFA datasets.
+//!
+//! Giving a sofar node's [`HrtfConfig`](crate::sofar_hrtf::HrtfConfig) a
+//! [`MorphTarget`] lets its `morph` parameter blend from the node's own
+//! dataset at 0 to the target's at 1, for example between two subjects
+//! while personalizing an HRTF.
+//!
+//! Both filters are looked up for the current direction. Each ear's
+//! broadband delay is estimated from its onset, both responses are
+//! aligned on it, and the taps are blended and placed at the blended
+//! delay. Blending unaligned taps would comb-filter the result instead.
+//!
+//! Each ear's onset is measured once per direction on a coarse grid
+//! when the target is first used at a sample rate, for both datasets,
+//! so the audio thread only looks onsets up.
+//!
+//! The two lookups each snap to their own dataset's measurement grid.
+//! Morphing between datasets measured on very different grids blends
+//! responses from slightly different directions, so it's approximate.
+//!
+//! Pass `--morph-target=path/to/dataset.sofa` to give the demo's
+//! sofar nodes a target.
+
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use sofar::reader::{Filter, OpenOptions, Sofar};
+
+use crate::sofar_hrtf::HrtfConfig;
+
+/// A second dataset for a sofar node to morph toward.
+#[derive(Clone)]
+pub struct MorphTarget {
+    pub name: String,
+    data: Arc<[u8]>,
+    /// The dataset as last loaded, resampled to the stream's rate.
+    loaded: Arc<Mutex<Option<Arc<MeasuredTarget>>>>,
+}
+
+impl core::fmt::Debug for MorphTarget {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MorphTarget")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A target loaded at one sample rate, with the onsets of
+/// both it and the node's own dataset.
+struct MeasuredTarget {
+    sample_rate: f32,
+    sofa: Sofar,
+    onsets: OnsetTable,
+    source_onsets: OnsetTable,
+}
+
+impl MorphTarget {
+    /// A target from the SOFA file in `data`.
+    ///
+    /// It's loaded and measured when a node first uses it.
+    pub fn new(name: impl Into<String>, data: impl Into<Arc<[u8]>>) -> Self {
+        Self {
+            name: name.into(),
+            data: data.into(),
+            loaded: Default::default(),
+        }
+    }
+
+    /// Read `--morph-target=path` from the arguments.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut target = None;
+
+        for arg in args {
+            let Some(path) = arg.strip_prefix("--morph-target=") else {
+                continue;
+            };
+
+            match std::fs::read(path) {
+                Ok(data) => target = Some(Self::new(path, data)),
+                Err(e) => warn!("failed to read morph target `{path}`: {e}"),
+            }
+        }
+
+        target
+    }
+
+    /// The target resampled to `sample_rate`, loading and
+    /// measuring it if it hasn't been at that rate yet.
+    ///
+    /// `source` is the node's own dataset at the same rate.
+    fn load(&self, source: &Sofar, sample_rate: f32) -> Option<Arc<MeasuredTarget>> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(target) = loaded.as_ref().filter(|t| t.sample_rate == sample_rate) {
+            return Some(target.clone());
+        }
+
+        let sofa = OpenOptions::new()
+            .sample_rate(sample_rate)
+            .open_data(&self.data)
+            .inspect_err(|e| warn!("failed to load morph target {}: {e:?}", self.name))
+            .ok()?;
+
+        let target = Arc::new(MeasuredTarget {
+            sample_rate,
+            onsets: OnsetTable::measure(&sofa),
+            source_onsets: OnsetTable::measure(source),
+            sofa,
+        });
+        *loaded = Some(target.clone());
+
+        Some(target)
+    }
+}
+
+/// Give every sofar node without a morph target `target`.
+///
+/// Configurations are filled in as they're inserted, before
+/// the node's processor is built.
+pub fn use_morph_target(app: &mut App, target: MorphTarget) {
+    app.add_observer(
+        move |trigger: Trigger<OnInsert, HrtfConfig>, mut configs: Query<&mut HrtfConfig>| {
+            let Ok(mut config) = configs.get_mut(trigger.target()) else {
+                return;
+            };
+
+            if config.morph_target.is_none() {
+                config.morph_target = Some(target.clone());
+            }
+        },
+    );
+}
+
+/// Taps below this fraction of an ear's peak don't count as its onset.
+const ONSET_THRESHOLD: f32 = 0.1;
+
+/// The index of the first tap within [`ONSET_THRESHOLD`] of the peak.
+fn onset(taps: &[f32]) -> usize {
+    let peak = taps.iter().fold(0.0f32, |peak, tap| peak.max(tap.abs()));
+    taps.iter()
+        .position(|tap| tap.abs() >= peak * ONSET_THRESHOLD)
+        .unwrap_or(0)
+}
+
+/// The spacing of the directions onsets are measured at, in degrees.
+const ONSET_GRID_DEGREES: usize = 5;
+const GRID_AZIMUTHS: usize = 360 / ONSET_GRID_DEGREES;
+const GRID_ELEVATIONS: usize = 180 / ONSET_GRID_DEGREES + 1;
+
+/// Each ear's onset on a grid of directions in a dataset's frame.
+struct OnsetTable {
+    /// `[left, right]`, by elevation and then azimuth.
+    onsets: Vec<[usize; 2]>,
+}
+
+impl OnsetTable {
+    fn measure(sofa: &Sofar) -> Self {
+        let mut filter = Filter::new(sofa.filter_len());
+        let onsets = (0..GRID_ELEVATIONS)
+            .flat_map(|elevation| (0..GRID_AZIMUTHS).map(move |azimuth| (azimuth, elevation)))
+            .map(|(azimuth, elevation)| {
+                let direction = grid_direction(azimuth, elevation);
+                sofa.filter(direction.x, direction.y, direction.z, &mut filter);
+                [onset(&filter.left), onset(&filter.right)]
+            })
+            .collect();
+
+        Self { onsets }
+    }
+
+    /// The onsets at the grid point nearest `direction`.
+    fn get(&self, direction: Vec3) -> [usize; 2] {
+        let direction = direction.normalize_or(Vec3::X);
+        let azimuth = direction
+            .y
+            .atan2(direction.x)
+            .to_degrees()
+            .rem_euclid(360.0);
+        let elevation = direction.z.clamp(-1.0, 1.0).asin().to_degrees() + 90.0;
+
+        let step = ONSET_GRID_DEGREES as f32;
+        let azimuth = (azimuth / step).round() as usize % GRID_AZIMUTHS;
+        let elevation = ((elevation / step).round() as usize).min(GRID_ELEVATIONS - 1);
+
+        self.onsets[elevation * GRID_AZIMUTHS + azimuth]
+    }
+}
+
+/// The unit vector at grid indices `azimuth` and `elevation`.
+fn grid_direction(azimuth: usize, elevation: usize) -> Vec3 {
+    let azimuth = ((azimuth * ONSET_GRID_DEGREES) as f32).to_radians();
+    let elevation = ((elevation * ONSET_GRID_DEGREES) as f32 - 90.0).to_radians();
+
+    Vec3::new(
+        elevation.cos() * azimuth.cos(),
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+    )
+}
+
+/// Blend `a` and `b` aligned on their onsets into `out`, placing
+/// the result at the blended onset.
+fn blend_ear(a: &[f32], onset_a: usize, b: &[f32], onset_b: usize, morph: f32, out: &mut [f32]) {
+    let onset = (onset_a as f32).lerp(onset_b as f32, morph).round() as usize;
+
+    let tap = |taps: &[f32], index: usize, taps_onset: usize| {
+        (index + taps_onset)
+            .checked_sub(onset)
+            .and_then(|index| taps.get(index))
+            .copied()
+            .unwrap_or(0.0)
+    };
+
+    for (index, out) in out.iter_mut().enumerate() {
+        *out = tap(a, index, onset_a) * (1.0 - morph) + tap(b, index, onset_b) * morph;
+    }
+}
+
+/// A node's morph toward a [`MorphTarget`], with its lookup buffers.
+pub struct FilterMorph {
+    target: Arc<MeasuredTarget>,
+    /// The node's own filter for the current direction.
+    source: Filter,
+    /// The target's filter for the current direction.
+    lookup: Filter,
+}
+
+impl FilterMorph {
+    /// Prepare to morph from `source`, the node's own dataset
+    /// at `sample_rate`, toward `target`.
+    ///
+    /// Returns `None` if the target can't be loaded.
+    pub fn new(target: &MorphTarget, source: &Sofar, sample_rate: f32) -> Option<Self> {
+        let target = target.load(source, sample_rate)?;
+
+        Some(Self {
+            lookup: Filter::new(target.sofa.filter_len()),
+            source: Filter::new(source.filter_len()),
+            target,
+        })
+    }
+
+    /// The buffer to look the node's own filter up into.
+    pub fn source_mut(&mut self) -> &mut Filter {
+        &mut self.source
+    }
+
+    /// Blend the source filter with the target's filter at the
+    /// already-rotated `direction` into `out`.
+    pub fn blend(&mut self, direction: Vec3, morph: f32, out: &mut Filter) {
+        let morph = morph.clamp(0.0, 1.0);
+
+        if morph == 0.0 {
+            out.left.copy_from_slice(&self.source.left);
+            out.right.copy_from_slice(&self.source.right);
+            return;
+        }
+
+        let target = &self.target;
+        target
+            .sofa
+            .filter(direction.x, direction.y, direction.z, &mut self.lookup);
+
+        let [source_left, source_right] = target.source_onsets.get(direction);
+        let [target_left, target_right] = target.onsets.get(direction);
+        blend_ear(
+            &self.source.left,
+            source_left,
+            &self.lookup.left,
+            target_left,
+            morph,
+            &mut out.left,
+        );
+        blend_ear(
+            &self.source.right,
+            source_right,
+            &self.lookup.right,
+            target_right,
+            morph,
+            &mut out.right,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_directions_look_up_their_own_onsets() {
+        let table = OnsetTable {
+            onsets: (0..GRID_ELEVATIONS * GRID_AZIMUTHS)
+                .map(|index| [index, index])
+                .collect(),
+        };
+
+        // The poles share a direction across every azimuth.
+        for elevation in 1..GRID_ELEVATIONS - 1 {
+            for azimuth in 0..GRID_AZIMUTHS {
+                let index = elevation * GRID_AZIMUTHS + azimuth;
+                assert_eq!(table.get(grid_direction(azimuth, elevation)), [index; 2]);
+            }
+        }
+    }
+
+    #[test]
+    fn blending_aligns_on_the_onsets() {
+        let mut a = [0.0; 32];
+        let mut b = [0.0; 32];
+        a[4] = 1.0;
+        b[12] = 1.0;
+
+        let mut out = [0.0; 32];
+        blend_ear(&a, onset(&a), &b, onset(&b), 0.5, &mut out);
+
+        // A single tap halfway between, rather than two half taps.
+        let mut expected = [0.0; 32];
+        expected[8] = 1.0;
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,45 @@
+//! Muting individual emitters.
+//!
+//! Inserting [`SpatialAudioMuted`] on an emitter silences the
+//! [`VolumeNode`]s in its effect chain, and removing it restores their
+//! previous volume. The chain's [`FalloffVolume`] is left to the
+//! falloff system. The HRTF nodes upstream keep processing, so their
+//! filter state is current the moment the emitter is unmuted.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::falloff::FalloffVolume;
+
+/// Silences an emitter while present.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct SpatialAudioMuted;
+
+/// The volume an effect had before its emitter was muted.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct MutedVolume(pub Volume);
+
+pub fn sync_muted_emitters(
+    muted: Query<(), With<SpatialAudioMuted>>,
+    mut volumes: Query<
+        (Entity, &EffectOf, &mut VolumeNode, Option<&MutedVolume>),
+        Without<FalloffVolume>,
+    >,
+    mut commands: Commands,
+) {
+    for (entity, effect_of, mut volume, saved) in volumes.iter_mut() {
+        let is_muted = muted.contains(effect_of.0);
+
+        match (is_muted, saved) {
+            (true, None) => {
+                commands.entity(entity).insert(MutedVolume(volume.volume));
+                volume.volume = Volume::Linear(0.0);
+            }
+            (false, Some(saved)) => {
+                commands.entity(entity).remove::<MutedVolume>();
+                volume.volume = saved.0;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! An audio backend that never touches hardware.
+//!
+//! The graph is processed on the main thread whenever the context is
+//! polled, at whatever rate keeps up with wall-clock time, and the
+//! most recent interleaved output is kept in a shared buffer. This
+//! lets the demo and its integration tests run on machines without an
+//! audio device, such as CI runners.
+
+use std::{
+    collections::VecDeque,
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use bevy::prelude::*;
+use firewheel::{
+    StreamInfo,
+    backend::{AudioBackend, DeviceInfo},
+    clock::ClockSeconds,
+    node::StreamStatus,
+    processor::FirewheelProcessor,
+};
+
+/// The captured interleaved output of a [`NullAudioBackend`].
+///
+/// Only the most recent samples are kept, so a long run doesn't
+/// grow it without bound. Read it with [`take`](Self::take).
+#[derive(Debug, Clone, Resource)]
+pub struct NullAudioOutput {
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    capacity: usize,
+}
+
+impl Default for NullAudioOutput {
+    /// Ten seconds of 48 kHz stereo.
+    fn default() -> Self {
+        Self::with_capacity(10 * 48_000 * 2)
+    }
+}
+
+impl NullAudioOutput {
+    /// Keep at most `capacity` interleaved samples.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Take every sample kept so far, oldest first.
+    #[cfg_attr(
+        not(test),
+        expect(dead_code, reason = "only the render tests read the output")
+    )]
+    pub fn take(&self) -> Vec<f32> {
+        self.samples.lock().unwrap().drain(..).collect()
+    }
+
+    /// Append `output`, dropping the oldest samples past the capacity.
+    fn push(&self, output: &[f32]) {
+        let mut samples = self.samples.lock().unwrap();
+        samples.extend(output);
+
+        let excess = samples.len().saturating_sub(self.capacity);
+        samples.drain(..excess);
+    }
+}
+
+/// Configuration for [`NullAudioBackend`].
+#[derive(Debug, Clone)]
+pub struct NullBackendConfig {
+    /// Defaults to 48 kHz.
+    pub sample_rate: NonZeroU32,
+    /// The number of frames processed per block.
+    ///
+    /// Defaults to 256.
+    pub block_frames: NonZeroU32,
+    /// The number of interleaved output channels.
+    ///
+    /// Defaults to 2.
+    pub output_channels: usize,
+    /// Where processed output is written.
+    ///
+    /// Insert a clone of this as a resource to read it from the ECS.
+    pub output: NullAudioOutput,
+}
+
+impl Default for NullBackendConfig {
+    fn default() -> Self {
+        Self {
+            sample_rate: NonZeroU32::new(48000).unwrap(),
+            block_frames: NonZeroU32::new(256).unwrap(),
+            output_channels: 2,
+            output: NullAudioOutput::default(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NullBackendError;
+
+impl core::fmt::Display for NullBackendError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("null audio backend error")
+    }
+}
+
+impl std::error::Error for NullBackendError {}
+
+/// A [`firewheel`] backend that renders into memory.
+pub struct NullAudioBackend {
+    config: NullBackendConfig,
+    processor: Option<FirewheelProcessor>,
+    scratch: Vec<f32>,
+    start: Instant,
+    frames_processed: u64,
+}
+
+impl AudioBackend for NullAudioBackend {
+    type Config = NullBackendConfig;
+    type StartStreamError = NullBackendError;
+    type StreamError = NullBackendError;
+
+    fn available_input_devices() -> Vec<DeviceInfo> {
+        Vec::new()
+    }
+
+    fn available_output_devices() -> Vec<DeviceInfo> {
+        Vec::new()
+    }
+
+    fn start_stream(config: Self::Config) -> Result<(Self, StreamInfo), Self::StartStreamError> {
+        let stream_info = StreamInfo {
+            sample_rate: config.sample_rate,
+            max_block_frames: config.block_frames,
+            num_stream_in_channels: 0,
+            num_stream_out_channels: config.output_channels as u32,
+            output_device_name: Some("null".into()),
+            // The engine fills in the rest.
+            ..Default::default()
+        };
+
+        let backend = Self {
+            scratch: vec![0.0; config.block_frames.get() as usize * config.output_channels],
+            config,
+            processor: None,
+            start: Instant::now(),
+            frames_processed: 0,
+        };
+
+        Ok((backend, stream_info))
+    }
+
+    fn set_processor(&mut self, processor: FirewheelProcessor) {
+        self.processor = Some(processor);
+    }
+
+    fn poll_status(&mut self) -> Result<(), Self::StreamError> {
+        let Some(processor) = &mut self.processor else {
+            return Ok(());
+        };
+
+        let sample_rate = self.config.sample_rate.get() as u64;
+        let block_frames = self.config.block_frames.get() as usize;
+        let channels = self.config.output_channels;
+
+        // Catch up to wall-clock time in whole blocks.
+        let elapsed = self.start.elapsed();
+        let due = (elapsed.as_secs_f64() * sample_rate as f64) as u64;
+
+        while self.frames_processed + block_frames as u64 <= due {
+            let clock = ClockSeconds(self.frames_processed as f64 / sample_rate as f64);
+
+            self.scratch.fill(0.0);
+            processor.process_interleaved(
+                &[],
+                &mut self.scratch,
+                0,
+                channels,
+                block_frames,
+                clock,
+                StreamStatus::empty(),
+            );
+
+            self.config.output.push(&self.scratch);
+            self.frames_processed += block_frames as u64;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use firewheel::{
+        FirewheelConfig, FirewheelCtx,
+        channel_config::{ChannelConfig, ChannelCount},
+        node::{
+            AudioNode, AudioNodeInfo, AudioNodeProcessor, ConstructProcessorContext, EmptyConfig,
+            ProcBuffers, ProcInfo, ProcessStatus,
+        },
+    };
+
+    use super::*;
+
+    /// Outputs a constant on both channels.
+    struct ConstantNode(f32);
+
+    struct ConstantProcessor(f32);
+
+    impl AudioNode for ConstantNode {
+        type Configuration = EmptyConfig;
+
+        fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+            AudioNodeInfo::new()
+                .debug_name("constant")
+                .channel_config(ChannelConfig::new(ChannelCount::ZERO, ChannelCount::STEREO))
+        }
+
+        fn construct_processor(
+            &self,
+            _config: &Self::Configuration,
+            _cx: ConstructProcessorContext,
+        ) -> impl AudioNodeProcessor {
+            ConstantProcessor(self.0)
+        }
+    }
+
+    impl AudioNodeProcessor for ConstantProcessor {
+        fn process(
+            &mut self,
+            ProcBuffers { outputs, .. }: ProcBuffers,
+            proc_info: &ProcInfo,
+            _events: firewheel::event::NodeEventList,
+        ) -> ProcessStatus {
+            for output in outputs.iter_mut() {
+                output[..proc_info.frames].fill(self.0);
+            }
+
+            ProcessStatus::outputs_not_silent()
+        }
+    }
+
+    #[test]
+    fn renders_a_graph_into_bounded_output() {
+        let output = NullAudioOutput::with_capacity(8 * 256 * 2);
+        let mut context = FirewheelCtx::<NullAudioBackend>::new(FirewheelConfig::default());
+        context
+            .start_stream(NullBackendConfig {
+                output: output.clone(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let constant = context.add_node(ConstantNode(0.25), None);
+        let graph_out = context.graph_out_node_id();
+        context
+            .connect(constant, graph_out, &[(0, 0), (1, 1)], false)
+            .unwrap();
+
+        // Long enough to render more blocks than the output keeps.
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(200) {
+            context.update().unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let rendered = output.take();
+        assert_eq!(rendered.len(), 8 * 256 * 2);
+        assert!(
+            rendered[rendered.len() - 256..]
+                .iter()
+                .all(|sample| *sample == 0.25)
+        );
+        assert!(output.take().is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+//! Coarse occlusion of emitters behind walls.
+//!
+//! An emitter is occluded when the line from its closest listener
+//! passes through an [`Occluder`]. Rounded obstacles, such as pillars
+//! or boulders, are [`AcousticObstacle`] spheres instead, which occlude
+//! gradually: a line grazing the sphere is barely affected, and one
+//! through its center fully. Occlusion fades in and out over
+//! [`OcclusionCurves::fade_seconds`] and drives two paths separately:
+//!
+//! - The direct path is low-passed and attenuated heavily, since a wall
+//!   blocks most of the sound that would reach the ear straight on.
+//! - The reverb send is low-passed and attenuated only a little, since
+//!   the room still carries the source's energy around the wall.
+//!
+//! Effect chains need an [`OcclusionFilterNode`] tagged
+//! [`OcclusionPath::Send`] before their [`SendNode`], and one tagged
+//! [`OcclusionPath::Direct`] after it. The direct path runs through
+//! both filters, so its cutoff should be the lower of the two.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+use firewheel::{
+    channel_config::ChannelConfig,
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+
+use crate::biquad::{Biquad, BiquadCoefficients};
+use crate::bus::{ReverbSend, SilencedSend};
+use crate::spatial::{Listeners, closest_listener};
+
+pub struct OcclusionPlugin;
+
+impl Plugin for OcclusionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OcclusionCurves>()
+            .register_node::<OcclusionFilterNode>()
+            .register_type::<OcclusionFilterNode>()
+            .add_systems(Update, (update_occlusion, apply_occlusion).chain());
+    }
+}
+
+/// The cutoff at which an [`OcclusionFilterNode`] passes audio untouched.
+pub const OPEN_CUTOFF: f32 = 20_000.0;
+
+/// Blocks sound passing through an axis-aligned rectangle
+/// centered on the entity's transform.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct Occluder {
+    pub half_size: Vec2,
+}
+
+/// Casts an acoustic shadow as a sphere of `radius`
+/// centered on the entity's transform.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct AcousticObstacle {
+    pub radius: f32,
+}
+
+/// How occluded an emitter is, from 0 (in the open) to 1 (fully behind a wall).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component)]
+pub struct Occlusion(pub f32);
+
+/// How full occlusion affects each path.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct OcclusionCurves {
+    /// The direct path's cutoff when fully occluded, in Hz.
+    pub direct_cutoff: f32,
+    /// The direct path's gain when fully occluded, in decibels.
+    pub direct_gain_db: f32,
+    /// The reverb send's cutoff when fully occluded, in Hz.
+    pub send_cutoff: f32,
+    /// The reverb send's gain when fully occluded, in decibels.
+    pub send_gain_db: f32,
+    /// Seconds to fade between open and fully occluded.
+    pub fade_seconds: f32,
+}
+
+impl Default for OcclusionCurves {
+    fn default() -> Self {
+        Self {
+            direct_cutoff: 700.0,
+            direct_gain_db: -9.0,
+            send_cutoff: 3000.0,
+            send_gain_db: -3.0,
+            fade_seconds: 0.15,
+        }
+    }
+}
+
+impl OcclusionCurves {
+    /// Interpolate a cutoff logarithmically, so it sweeps evenly in pitch.
+    fn cutoff(occlusion: f32, closed: f32) -> f32 {
+        OPEN_CUTOFF * (closed / OPEN_CUTOFF).powf(occlusion)
+    }
+}
+
+/// Which path an [`OcclusionFilterNode`] shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum OcclusionPath {
+    Direct,
+    Send,
+}
+
+/// A low-pass filter and gain driven by [`Occlusion`].
+#[derive(Debug, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct OcclusionFilterNode {
+    /// The low-pass cutoff in Hz. At [`OPEN_CUTOFF`] or above,
+    /// the filter is bypassed.
+    pub cutoff: f32,
+    /// A linear gain applied after the filter.
+    pub gain: f32,
+}
+
+impl Default for OcclusionFilterNode {
+    fn default() -> Self {
+        Self {
+            cutoff: OPEN_CUTOFF,
+            gain: 1.0,
+        }
+    }
+}
+
+/// Configuration for [`OcclusionFilterNode`].
+#[derive(Debug, Default, Clone, Component)]
+pub struct OcclusionFilterConfig;
+
+struct OcclusionFilterProcessor {
+    sample_rate: f32,
+    cutoff: f32,
+    gain: f32,
+    filters: [Biquad; 2],
+}
+
+impl OcclusionFilterProcessor {
+    fn set_cutoff(&mut self, cutoff: f32) {
+        self.cutoff = cutoff;
+
+        let frequency = cutoff.min(self.sample_rate * 0.45);
+        let coefficients = BiquadCoefficients::low_pass(
+            frequency,
+            core::f32::consts::FRAC_1_SQRT_2,
+            self.sample_rate,
+        );
+        for filter in &mut self.filters {
+            filter.coefficients = coefficients;
+        }
+    }
+}
+
+impl AudioNode for OcclusionFilterNode {
+    type Configuration = OcclusionFilterConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("occlusion filter")
+            .channel_config(ChannelConfig::new(2, 2))
+    }
+
+    fn construct_processor(
+        &self,
+        _config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let mut processor = OcclusionFilterProcessor {
+            sample_rate: cx.stream_info.sample_rate.get() as f32,
+            cutoff: OPEN_CUTOFF,
+            gain: self.gain,
+            filters: Default::default(),
+        };
+        processor.set_cutoff(self.cutoff);
+
+        processor
+    }
+}
+
+impl AudioNodeProcessor for OcclusionFilterProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        events.for_each_patch::<OcclusionFilterNode>(|patch| match patch {
+            OcclusionFilterNodePatch::Cutoff(cutoff) => self.set_cutoff(cutoff),
+            OcclusionFilterNodePatch::Gain(gain) => self.gain = gain,
+        });
+
+        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
+            self.filters.iter_mut().for_each(Biquad::reset);
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        if self.cutoff >= OPEN_CUTOFF && self.gain == 1.0 {
+            return ProcessStatus::Bypass;
+        }
+
+        let open = self.cutoff >= OPEN_CUTOFF;
+        for ((input, output), filter) in
+            inputs.iter().zip(outputs.iter_mut()).zip(&mut self.filters)
+        {
+            for frame in 0..proc_info.frames {
+                let sample = if open {
+                    input[frame]
+                } else {
+                    filter.process(input[frame])
+                };
+
+                output[frame] = sample * self.gain;
+            }
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
+
+/// Whether the segment from `start` to `end` passes through a rectangle
+/// of `half_size` at the origin, using the slab method.
+fn segment_hits_rect(start: Vec2, end: Vec2, half_size: Vec2) -> bool {
+    let delta = end - start;
+    let (mut enter, mut exit) = (0.0f32, 1.0f32);
+
+    for axis in 0..2 {
+        if delta[axis].abs() < f32::EPSILON {
+            if start[axis].abs() > half_size[axis] {
+                return false;
+            }
+            continue;
+        }
+
+        let a = (-half_size[axis] - start[axis]) / delta[axis];
+        let b = (half_size[axis] - start[axis]) / delta[axis];
+        enter = enter.max(a.min(b));
+        exit = exit.min(a.max(b));
+    }
+
+    enter <= exit
+}
+
+/// Whether the segment from `start` to `end` passes through `occluder`.
+pub fn occluder_blocks(
+    (transform, occluder): (&GlobalTransform, &Occluder),
+    start: Vec3,
+    end: Vec3,
+) -> bool {
+    let to_local = transform.affine().inverse();
+    segment_hits_rect(
+        to_local.transform_point3(start).truncate(),
+        to_local.transform_point3(end).truncate(),
+        occluder.half_size,
+    )
+}
+
+/// How deeply the segment from `start` to `end` passes through a sphere,
+/// from 0 where it misses or grazes it to 1 where it crosses the center.
+pub fn sphere_shadow(start: Vec3, end: Vec3, center: Vec3, radius: f32) -> f32 {
+    let delta = end - start;
+    let t = if delta.length_squared() < f32::EPSILON {
+        0.0
+    } else {
+        ((center - start).dot(delta) / delta.length_squared()).clamp(0.0, 1.0)
+    };
+
+    let distance = center.distance(start + delta * t);
+    (1.0 - distance / radius.max(f32::EPSILON)).clamp(0.0, 1.0)
+}
+
+/// Fade each emitter's [`Occlusion`] toward how much the walls and
+/// obstacles between it and its closest listener block it.
+fn update_occlusion(
+    mut emitters: Query<(Entity, &GlobalTransform, Option<&mut Occlusion>), With<SampleEffects>>,
+    occluders: Query<(&GlobalTransform, &Occluder)>,
+    obstacles: Query<(&GlobalTransform, &AcousticObstacle)>,
+    listeners: Listeners,
+    curves: Res<OcclusionCurves>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let step = time.delta_secs() / curves.fade_seconds.max(f32::EPSILON);
+
+    for (entity, transform, occlusion) in emitters.iter_mut() {
+        let position = transform.translation();
+        let target = closest_listener(&listeners, position).map_or(0.0, |listener| {
+            let start = listener.translation();
+
+            if occluders
+                .iter()
+                .any(|occluder| occluder_blocks(occluder, start, position))
+            {
+                return 1.0;
+            }
+
+            // Each obstacle passes on what the others let through.
+            let open = obstacles
+                .iter()
+                .fold(1.0, |open, (obstacle_transform, obstacle)| {
+                    let center = obstacle_transform.translation();
+                    open * (1.0 - sphere_shadow(start, position, center, obstacle.radius))
+                });
+            1.0 - open
+        });
+
+        match occlusion {
+            Some(mut occlusion) => {
+                if occlusion.0 != target {
+                    occlusion.0 += (target - occlusion.0).clamp(-step, step);
+                }
+            }
+            None => {
+                commands.entity(entity).insert(Occlusion(target));
+            }
+        }
+    }
+}
+
+/// The send volume a [`ReverbSend`] has in the open.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct UnoccludedSend(pub Volume);
+
+/// Drive each chain's occlusion filters and reverb send.
+fn apply_occlusion(
+    emitters: Query<(&Occlusion, &SampleEffects), Changed<Occlusion>>,
+    mut filters: Query<(&mut OcclusionFilterNode, &OcclusionPath)>,
+    mut sends: Query<
+        (Entity, &mut SendNode, Option<&UnoccludedSend>),
+        (With<ReverbSend>, Without<SilencedSend>),
+    >,
+    curves: Res<OcclusionCurves>,
+    mut commands: Commands,
+) {
+    for (occlusion, effects) in emitters.iter() {
+        let occlusion = occlusion.0;
+
+        for effect in effects.iter() {
+            if let Ok((mut filter, path)) = filters.get_mut(effect) {
+                let (closed_cutoff, closed_gain_db) = match path {
+                    OcclusionPath::Direct => (curves.direct_cutoff, curves.direct_gain_db),
+                    // The send's level is set on the send itself.
+                    OcclusionPath::Send => (curves.send_cutoff, 0.0),
+                };
+
+                let cutoff = OcclusionCurves::cutoff(occlusion, closed_cutoff);
+                let gain = Volume::Decibels(closed_gain_db * occlusion).linear();
+                if filter.cutoff != cutoff || filter.gain != gain {
+                    filter.cutoff = cutoff;
+                    filter.gain = gain;
+                }
+            }
+
+            if let Ok((entity, mut send, unoccluded)) = sends.get_mut(effect) {
+                let open = match unoccluded {
+                    Some(unoccluded) => unoccluded.0,
+                    None => {
+                        commands
+                            .entity(entity)
+                            .insert(UnoccludedSend(send.send_volume));
+                        send.send_volume
+                    }
+                };
+
+                let gain = Volume::Decibels(curves.send_gain_db * occlusion).linear();
+                send.send_volume = Volume::Linear(open.linear() * gain);
+            }
+        }
+    }
+}
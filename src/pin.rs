@@ -0,0 +1,71 @@
+//! Pinning the listener to a game entity.
+//!
+//! In many games the listener belongs on the player rather than the
+//! camera. [`SpatialAudioPlugin::with_listener_entity`] spawns a
+//! dedicated listener that follows an existing entity's
+//! [`GlobalTransform`], so the entity itself never needs a listener
+//! component. A [`TransformOffset`] on the followed entity moves the
+//! listener relative to it, for example up to head height.
+//!
+//! [`SpatialAudioPlugin::with_listener_entity`]: crate::spatial::SpatialAudioPlugin::with_listener_entity
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+/// The listener moves with `target`.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct ListenerPin {
+    pub target: Entity,
+    pub listener: Entity,
+}
+
+/// Offsets the pinned listener from the entity it follows,
+/// in that entity's frame.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Component)]
+pub struct TransformOffset(pub Transform);
+
+/// Spawn a listener that follows `target`.
+pub fn pin_listener(app: &mut App, target: Entity) {
+    let listener = app
+        .world_mut()
+        .spawn((SpatialListener2D, Name::new("Pinned listener")))
+        .id();
+
+    app.insert_resource(ListenerPin { target, listener })
+        .add_systems(
+            PostUpdate,
+            follow_target.after(TransformSystem::TransformPropagate),
+        );
+}
+
+/// Move the pinned listener onto its target.
+///
+/// Runs after propagation, so both transforms are written and
+/// the listener is in place for this frame's direction updates.
+fn follow_target(
+    pin: Res<ListenerPin>,
+    mut globals: Query<(&mut GlobalTransform, Option<&TransformOffset>)>,
+    mut transforms: Query<&mut Transform>,
+    mut warned: Local<bool>,
+) {
+    let Ok((target, offset)) = globals.get(pin.target) else {
+        if !*warned {
+            warn!("the pinned listener's target {} doesn't exist", pin.target);
+            *warned = true;
+        }
+        return;
+    };
+    let pinned = match offset {
+        Some(TransformOffset(offset)) => target.mul_transform(*offset),
+        None => *target,
+    };
+
+    let (Ok(mut transform), Ok((mut global, _))) = (
+        transforms.get_mut(pin.listener),
+        globals.get_mut(pin.listener),
+    ) else {
+        return;
+    };
+    *transform = pinned.compute_transform();
+    *global = pinned;
+}
@@ -0,0 +1,243 @@
+//! Checking the demo's assets before anything plays.
+//!
+//! A clone without Git LFS leaves small pointer files where the
+//! datasets and samples should be, and a stripped `assets` folder
+//! leaves nothing at all. Rather than failing one way per asset, the
+//! [`AssetPreflightPlugin`] checks everything the current scene and
+//! backends need while the app is built, lists what's missing in one
+//! [`PreflightReport`], shows it on screen, and lets the demo carry on
+//! without it:
+//!
+//! - A missing sample leaves the emitters that play it silent, but
+//!   still drawn and moving.
+//! - A broken embedded sofar dataset swaps every sofar node for a
+//!   plain downmix through the custom processor hook, rather than
+//!   panicking when the first node is built.
+//! - A broken embedded fyrox sphere already leaves its nodes passing
+//!   audio through.
+//!
+//! Samples that fail to load later, including every missing sample on
+//! the web where there's no filesystem to check, are added to the
+//! report as their load failures arrive.
+
+use bevy::{asset::AssetLoadFailedEvent, color::palettes::css::RED, prelude::*};
+use bevy_seedling::sample::Sample;
+
+pub struct AssetPreflightPlugin {
+    /// The samples the current scene plays, relative to the asset folder.
+    pub samples: Vec<&'static str>,
+}
+
+impl Plugin for AssetPreflightPlugin {
+    fn build(&self, app: &mut App) {
+        let mut report = PreflightReport::default();
+
+        #[cfg(feature = "sofar")]
+        if !embedded_ok(crate::sofar_hrtf::SOFA_DATA) {
+            report.missing.push(MissingAsset {
+                path: "sadie_h12.sofa".into(),
+                disables: "sofar HRTF, passing audio through instead",
+            });
+
+            crate::custom::use_custom_processor(
+                app,
+                crate::custom::HrtfProcessorFactory::new(|_, _| {
+                    crate::fallback::PassthroughProcessor
+                }),
+            );
+        }
+
+        #[cfg(feature = "fyrox")]
+        if !embedded_ok(crate::fyrox_hrtf::SPHERE) {
+            report.missing.push(MissingAsset {
+                path: "irc_1002_c.bin".into(),
+                disables: "fyrox HRTF, passing audio through instead",
+            });
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        for sample in &self.samples {
+            if !file_ok(sample) {
+                report.missing.push(MissingAsset {
+                    path: (*sample).into(),
+                    disables: "playback, so its emitters are silent",
+                });
+            }
+        }
+
+        for missing in &report.missing {
+            error!(
+                "missing asset {}: disables {}",
+                missing.path, missing.disables
+            );
+        }
+
+        app.insert_resource(report)
+            .add_systems(Startup, spawn_report)
+            .add_systems(Update, (record_failed_samples, update_report).chain());
+    }
+}
+
+/// An asset the demo needs but couldn't find.
+#[derive(Debug, Clone)]
+pub struct MissingAsset {
+    pub path: String,
+    /// What the demo does without it.
+    pub disables: &'static str,
+}
+
+/// Every missing asset found so far.
+#[derive(Debug, Default, Resource)]
+pub struct PreflightReport {
+    pub missing: Vec<MissingAsset>,
+}
+
+/// How every Git LFS pointer file starts.
+#[cfg(any(feature = "sofar", feature = "fyrox", not(target_arch = "wasm32")))]
+const LFS_POINTER: &[u8] = b"version https://git-lfs";
+
+/// Whether embedded `data` is the real file rather than empty
+/// or an LFS pointer.
+///
+/// The file isn't parsed, so one that's corrupt some other
+/// way still fails when it's loaded.
+#[cfg(any(feature = "sofar", feature = "fyrox"))]
+fn embedded_ok(data: &[u8]) -> bool {
+    !data.is_empty() && !data.starts_with(LFS_POINTER)
+}
+
+/// Whether the asset at `path` exists, can be read and isn't an LFS pointer.
+#[cfg(not(target_arch = "wasm32"))]
+fn file_ok(path: &str) -> bool {
+    use std::io::Read;
+
+    let full_path = bevy::asset::io::file::FileAssetReader::get_base_path()
+        .join("assets")
+        .join(path);
+
+    let mut start = [0; LFS_POINTER.len()];
+    let read = std::fs::File::open(&full_path).and_then(|mut file| file.read(&mut start));
+
+    match read {
+        Ok(0) => false,
+        Ok(len) => !start[..len].starts_with(LFS_POINTER),
+        Err(e) => {
+            debug!("can't read {}: {e}", full_path.display());
+            false
+        }
+    }
+}
+
+/// Add samples that failed to load to the report.
+fn record_failed_samples(
+    mut failures: EventReader<AssetLoadFailedEvent<Sample>>,
+    mut report: ResMut<PreflightReport>,
+) {
+    for failure in failures.read() {
+        let path = failure.path.to_string();
+        if report.missing.iter().any(|missing| missing.path == path) {
+            continue;
+        }
+
+        error!("failed to load {path}: {}", failure.error);
+        report.missing.push(MissingAsset {
+            path,
+            disables: "playback, so its emitters are silent",
+        });
+    }
+}
+
+#[derive(Component)]
+struct PreflightHud;
+
+fn spawn_report(mut commands: Commands) {
+    commands.spawn((
+        PreflightHud,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        TextColor(RED.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_report(report: Res<PreflightReport>, mut hud: Single<&mut Text, With<PreflightHud>>) {
+    if !report.is_changed() || report.missing.is_empty() {
+        return;
+    }
+
+    let mut text = String::from("missing assets:");
+    for missing in &report.missing {
+        text.push_str(&format!("\n  {}: no {}", missing.path, missing.disables));
+    }
+    hud.0 = text;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::{AssetLoadError, io::AssetReaderError};
+
+    use super::*;
+
+    #[cfg(any(feature = "sofar", feature = "fyrox"))]
+    #[test]
+    fn missing_embedded_datasets_are_caught() {
+        assert!(!embedded_ok(&[]));
+        assert!(!embedded_ok(
+            b"version https://git-lfs.github.com/spec/v1\noid sha256:0\nsize 1\n"
+        ));
+
+        #[cfg(feature = "sofar")]
+        assert!(embedded_ok(crate::sofar_hrtf::SOFA_DATA));
+        #[cfg(feature = "fyrox")]
+        assert!(embedded_ok(crate::fyrox_hrtf::SPHERE));
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn missing_samples_are_caught() {
+        let dir = std::env::temp_dir().join(format!("preflight-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let empty = dir.join("empty.wav");
+        let pointer = dir.join("pointer.wav");
+        std::fs::write(&empty, []).unwrap();
+        std::fs::write(&pointer, b"version https://git-lfs.github.com/spec/v1\n").unwrap();
+
+        // Absolute paths replace the asset folder when joined.
+        assert!(!file_ok(empty.to_str().unwrap()));
+        assert!(!file_ok(pointer.to_str().unwrap()));
+        assert!(!file_ok("no_such_sample.wav"));
+        assert!(file_ok("quad_test.wav"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn failed_loads_are_reported_once() {
+        let mut app = App::new();
+        app.add_event::<AssetLoadFailedEvent<Sample>>()
+            .init_resource::<PreflightReport>()
+            .add_systems(Update, record_failed_samples);
+
+        let failure = || AssetLoadFailedEvent::<Sample> {
+            id: AssetId::default(),
+            path: "missing.ogg".into(),
+            error: AssetLoadError::AssetReaderError(AssetReaderError::NotFound(
+                "missing.ogg".into(),
+            )),
+        };
+        app.world_mut().send_event(failure());
+        app.world_mut().send_event(failure());
+        app.update();
+        app.world_mut().send_event(failure());
+        app.update();
+
+        let report = app.world().resource::<PreflightReport>();
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].path, "missing.ogg");
+    }
+}
@@ -0,0 +1,140 @@
+//! Preparing the sofar dataset before any node needs it.
+//!
+//! The embedded dataset is otherwise parsed by the first node built,
+//! which stalls the frame that spawns it. [`HrtfPrewarmSystem`] loads
+//! it once the stream's sample rate is known and looks up filters
+//! for 26 directions spread around the head: toward the corners,
+//! edge midpoints and face centers of a cube. Nodes take the
+//! [`HrtfFilterCache`] when they're built, and directions within
+//! [`CACHE_RADIUS`] of a cached one use its filter as is.
+//!
+//! The cached directions are 45 degrees apart, far too coarse to
+//! blend between, so anything further from them is looked up in the
+//! dataset as before.
+
+use std::sync::{Arc, OnceLock};
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+use sofar::reader::{Filter, Sofar};
+
+use crate::sofar_hrtf::load_sofa;
+
+pub struct HrtfPrewarmPlugin;
+
+impl Plugin for HrtfPrewarmPlugin {
+    fn build(&self, app: &mut App) {
+        // The stream may not have started by `Startup`,
+        // so keep trying until it has.
+        app.add_systems(Startup, prewarm_hrtf_filters.in_set(HrtfPrewarmSystem))
+            .add_systems(
+                Update,
+                prewarm_hrtf_filters
+                    .in_set(HrtfPrewarmSystem)
+                    .run_if(|| HrtfFilterCache::get().is_none()),
+            );
+    }
+}
+
+/// Loads the sofar dataset and fills the [`HrtfFilterCache`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct HrtfPrewarmSystem;
+
+/// How close a direction must be to a cached one to use
+/// its filter, in radians.
+pub const CACHE_RADIUS: f32 = 2.0 * core::f32::consts::PI / 180.0;
+
+/// Filters looked up ahead of time, keyed by direction in the dataset's frame.
+pub struct HrtfFilterCache {
+    entries: Vec<(Vec3, Filter)>,
+}
+
+static FILTER_CACHE: OnceLock<Arc<HrtfFilterCache>> = OnceLock::new();
+
+impl HrtfFilterCache {
+    /// The cache, once it's been filled.
+    pub fn get() -> Option<Arc<Self>> {
+        FILTER_CACHE.get().cloned()
+    }
+
+    fn build(sofa: &Sofar) -> Self {
+        let entries = cube_directions()
+            .map(|direction| {
+                let mut filter = Filter::new(sofa.filter_len());
+                sofa.filter(direction.x, direction.y, direction.z, &mut filter);
+                (direction, filter)
+            })
+            .collect();
+
+        Self { entries }
+    }
+
+    /// Copy the cached filter for `direction` into `out`.
+    ///
+    /// Returns `false`, leaving `out` alone, if no cached
+    /// direction is within [`CACHE_RADIUS`].
+    pub fn lookup(&self, direction: Vec3, out: &mut Filter) -> bool {
+        let Some(direction) = direction.try_normalize() else {
+            return false;
+        };
+
+        let min_dot = CACHE_RADIUS.cos();
+        let Some((_, filter)) = self
+            .entries
+            .iter()
+            .find(|(cached, _)| cached.dot(direction) >= min_dot)
+        else {
+            return false;
+        };
+
+        out.left.copy_from_slice(&filter.left);
+        out.right.copy_from_slice(&filter.right);
+        true
+    }
+}
+
+/// The 26 unit directions toward a cube's corners, edge
+/// midpoints and face centers.
+fn cube_directions() -> impl Iterator<Item = Vec3> {
+    let steps = [-1.0, 0.0, 1.0];
+
+    steps
+        .into_iter()
+        .flat_map(move |x| steps.into_iter().map(move |y| (x, y)))
+        .flat_map(move |(x, y)| steps.into_iter().map(move |z| Vec3::new(x, y, z)))
+        .filter_map(Vec3::try_normalize)
+}
+
+/// Look `direction` up in the cache if it's close enough,
+/// or in `sofa` otherwise.
+pub fn lookup_filter(
+    sofa: &Sofar,
+    cache: Option<&HrtfFilterCache>,
+    direction: Vec3,
+    out: &mut Filter,
+) {
+    if !cache.is_some_and(|cache| cache.lookup(direction, out)) {
+        sofa.filter(direction.x, direction.y, direction.z, out);
+    }
+}
+
+fn prewarm_hrtf_filters(mut context: ResMut<AudioContext>) {
+    if HrtfFilterCache::get().is_some() {
+        return;
+    }
+
+    // The dataset is resampled to the stream, so it can't
+    // be loaded before the stream's rate is known.
+    let Some(sample_rate) =
+        context.with(|ctx| ctx.stream_info().map(|info| info.sample_rate.get()))
+    else {
+        return;
+    };
+
+    let sofa = load_sofa(sample_rate as f32);
+    let cache = FILTER_CACHE.get_or_init(|| Arc::new(HrtfFilterCache::build(&sofa)));
+    info!(
+        "prewarmed {} HRTF filters at {sample_rate} Hz",
+        cache.entries.len()
+    );
+}
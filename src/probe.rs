@@ -0,0 +1,155 @@
+//! Auditioning the scene from somewhere other than the listener.
+//!
+//! Press `O` to listen from a probe point under the cursor instead of
+//! from the listener, and drag the probe's marker to move it. The
+//! probe sets [`ListenerProbe`], which [`listener_frames`] applies to
+//! every listener, so directions, attenuation, occlusion and sends
+//! are all worked out from the probe. The listener entities keep
+//! their transforms, so anything else moving or reading them is
+//! unaffected.
+//!
+//! Entering and leaving the probe goes through a scene transition, so
+//! the jump in position is hidden under a fade.
+//!
+//! [`listener_frames`]: crate::spatial::listener_frames
+
+use bevy::{color::palettes::css::ORANGE, prelude::*, window::PrimaryWindow};
+
+use crate::spatial::ListenerProbe;
+use crate::transition::{SceneTransition, TransitionSceneExt};
+
+pub struct ListenerProbePlugin;
+
+impl Plugin for ListenerProbePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ProbeDrag>()
+            .add_systems(Startup, spawn_hud)
+            .add_systems(
+                Update,
+                (toggle_probe, drag_probe, draw_probe, update_hud).chain(),
+            );
+    }
+}
+
+/// The probe marker's radius, which is also how close
+/// a click must be to pick it up.
+const PROBE_RADIUS: f32 = 12.0;
+
+/// Whether the probe marker is being dragged.
+#[derive(Debug, Default, Resource)]
+struct ProbeDrag {
+    /// The offset from the cursor to the probe.
+    grab: Option<Vec2>,
+}
+
+fn cursor_position(
+    window: &Window,
+    (camera, camera_transform): (&Camera, &GlobalTransform),
+) -> Option<Vec2> {
+    window
+        .cursor_position()
+        .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+}
+
+/// Press `O` to move the listening position to the cursor, and again to return.
+fn toggle_probe(
+    input: Res<ButtonInput<KeyCode>>,
+    probe: Option<Res<ListenerProbe>>,
+    transition: Res<SceneTransition>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut commands: Commands,
+) {
+    if !input.just_pressed(KeyCode::KeyO) || transition.is_active() {
+        return;
+    }
+
+    if probe.is_some() {
+        info!("listening from the listener");
+        commands.transition_scene(|commands| commands.remove_resource::<ListenerProbe>());
+        return;
+    }
+
+    let Some(cursor) = cursor_position(&window, *camera) else {
+        return;
+    };
+    info!("listening from the probe at {cursor}");
+    commands.transition_scene(move |commands| {
+        commands.insert_resource(ListenerProbe {
+            position: cursor.extend(0.0),
+        })
+    });
+}
+
+/// Drag the probe marker with the left mouse button.
+fn drag_probe(
+    input: Res<ButtonInput<MouseButton>>,
+    probe: Option<ResMut<ListenerProbe>>,
+    mut drag: ResMut<ProbeDrag>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+) {
+    let Some(mut probe) = probe else {
+        drag.grab = None;
+        return;
+    };
+    if !input.pressed(MouseButton::Left) {
+        drag.grab = None;
+        return;
+    }
+    let Some(cursor) = cursor_position(&window, *camera) else {
+        return;
+    };
+
+    let position = probe.position.truncate();
+    if input.just_pressed(MouseButton::Left) && cursor.distance(position) <= PROBE_RADIUS {
+        drag.grab = Some(position - cursor);
+    }
+
+    if let Some(grab) = drag.grab {
+        let target = (cursor + grab).extend(probe.position.z);
+        if probe.position != target {
+            probe.position = target;
+        }
+    }
+}
+
+fn draw_probe(probe: Option<Res<ListenerProbe>>, mut gizmos: Gizmos) {
+    if let Some(probe) = probe {
+        let center = probe.position.truncate();
+        gizmos.circle_2d(center, PROBE_RADIUS, ORANGE);
+        gizmos.cross_2d(center, PROBE_RADIUS * 0.5, ORANGE);
+    }
+}
+
+#[derive(Component)]
+struct ProbeHud;
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        ProbeHud,
+        Text::default(),
+        TextFont::from_font_size(14.0),
+        TextColor(ORANGE.into()),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(28.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+    ));
+}
+
+fn update_hud(probe: Option<Res<ListenerProbe>>, mut hud: Single<&mut Text, With<ProbeHud>>) {
+    let text = match probe {
+        Some(probe) => format!(
+            "PROBE at ({:.0}, {:.0}) (drag to move, O to leave)",
+            probe.position.x, probe.position.y
+        ),
+        None => String::new(),
+    };
+
+    if hud.0 != text {
+        hud.0 = text;
+    }
+}
@@ -0,0 +1,91 @@
+//! A scene that spatializes a four-channel stem.
+//!
+//! Run with `--scene=quad` to replace the orbiting emitter with a still
+//! one playing `quad_test.wav`, where each channel in turn sounds a tone
+//! at its own pitch: front left, front right, back left, back right.
+//! HRTF chains are built with four inputs and [`ChannelLayout::Quad`],
+//! so every tone should arrive from the emitter at the same level.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+#[cfg(not(feature = "lite"))]
+use firewheel::channel_config::NonZeroChannelCount;
+
+use crate::bus::ReverbBuses;
+#[cfg(not(feature = "lite"))]
+use crate::downmix::ChannelLayout;
+use crate::latency::LatencyCompensated;
+use crate::{DemoAssets, DemoChain, ORBIT_VOLUME, spawn_default_chain};
+
+/// Spawn the four-channel emitter.
+pub fn spawn_quad_scene(
+    assets: Res<DemoAssets>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    server: Res<AssetServer>,
+    mut commands: Commands,
+) {
+    let mut emitter = commands.spawn((
+        Mesh2d(assets.emitter_circle.clone()),
+        MeshMaterial2d(assets.emitter_material.clone()),
+        SamplePlayer::new(server.load("quad_test.wav"))
+            .looping()
+            .with_volume(ORBIT_VOLUME),
+        Transform::from_xyz(150.0, 150.0, 0.0),
+        LatencyCompensated,
+    ));
+
+    if !spawn_quad_chain(&mut emitter, *chain) {
+        warn!("the {chain:?} chain can't take four channels, using its default downmix");
+        spawn_default_chain(&mut emitter, &buses, *chain);
+    }
+}
+
+/// Spawn `kind` with four inputs, if it's an HRTF chain.
+fn spawn_quad_chain(emitter: &mut EntityCommands, kind: DemoChain) -> bool {
+    #[cfg(not(feature = "lite"))]
+    let quad = NonZeroChannelCount::new(4).unwrap();
+
+    let mut spawned = true;
+    emitter.with_related_entities::<EffectOf>(|chain| match kind {
+        #[cfg(all(feature = "fyrox", not(feature = "lite")))]
+        DemoChain::Fyrox => {
+            chain.spawn((
+                crate::fyrox_hrtf::FyroxHrtfNode::default(),
+                crate::fyrox_hrtf::HrtfConfig {
+                    input_channels: quad,
+                    channel_layout: ChannelLayout::Quad,
+                    ..default()
+                },
+            ));
+            chain.spawn(VolumeNode {
+                volume: Volume::Decibels(18.0),
+            });
+        }
+        #[cfg(all(feature = "sofar", not(feature = "lite")))]
+        DemoChain::Sofar => {
+            chain.spawn((
+                crate::sofar_hrtf::SofarHrtfNode::default(),
+                crate::sofar_hrtf::HrtfConfig {
+                    input_channels: quad,
+                    channel_layout: ChannelLayout::Quad,
+                    ..default()
+                },
+            ));
+        }
+        #[cfg(all(feature = "realfft-backend", not(feature = "lite")))]
+        DemoChain::RealFft => {
+            chain.spawn((
+                crate::realfft_hrtf::RealFftHrtfNode::default(),
+                crate::realfft_hrtf::HrtfConfig {
+                    input_channels: quad,
+                    channel_layout: ChannelLayout::Quad,
+                    ..default()
+                },
+            ));
+        }
+        _ => spawned = false,
+    });
+
+    spawned
+}
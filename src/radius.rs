@@ -0,0 +1,160 @@
+//! Drawing where each emitter's falloff changes.
+//!
+//! Press `V` to cycle the overlay between off, every emitter with a
+//! [`SpatialAudioFalloff`] at low alpha, and only the emitter under
+//! the cursor. Each emitter gets a circle, in its own color, for:
+//!
+//! - [`HALF_LEVEL_DB`], where it's noticeably quieter,
+//! - [`INAUDIBLE_DB`], past which it can't be heard,
+//! - its `max_distance`, past which its level stops changing.
+//!
+//! The boundaries are found by sampling [`SpatialAudioFalloff::gain`],
+//! the same function [`apply_distance_attenuation`] sets volumes with,
+//! so moving the listener or probe across a circle is where the level
+//! crosses it, to within [`SAMPLES`] steps of `max_distance`. A line to the closest listener takes the
+//! color of the innermost circle the listener is inside, or white
+//! outside them all.
+//!
+//! [`apply_distance_attenuation`]: crate::falloff::apply_distance_attenuation
+
+use bevy::{
+    color::palettes::css::{LIME, RED, YELLOW},
+    prelude::*,
+    window::PrimaryWindow,
+};
+
+use crate::falloff::SpatialAudioFalloff;
+use crate::spatial::{Listeners, closest_listener};
+
+pub struct ImportanceRadiusPlugin;
+
+impl Plugin for ImportanceRadiusPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RadiusOverlay>().add_systems(
+            Update,
+            (
+                cycle_overlay,
+                draw_radii.run_if(not(resource_equals(RadiusOverlay::Off))),
+            )
+                .chain(),
+        );
+    }
+}
+
+/// Which emitters have their radii drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum RadiusOverlay {
+    #[default]
+    Off,
+    /// Every emitter, at low alpha.
+    All,
+    /// The emitter closest to the cursor.
+    Hovered,
+}
+
+/// The level that marks an emitter as noticeably quieter, in decibels.
+pub const HALF_LEVEL_DB: f32 = -6.0;
+
+/// The level below which an emitter can't be heard, in decibels.
+pub const INAUDIBLE_DB: f32 = -60.0;
+
+/// How many distances are sampled between the emitter and `max_distance`.
+pub const SAMPLES: usize = 512;
+
+/// The alpha emitters are drawn with in [`RadiusOverlay::All`].
+const DIM_ALPHA: f32 = 0.25;
+
+/// The distance at which `falloff` first drops below `db`, if it
+/// does before `max_distance`.
+///
+/// Curves can rise again, so this is the first crossing only.
+pub fn boundary(falloff: &SpatialAudioFalloff, db: f32) -> Option<f32> {
+    let threshold = 10f32.powf(db / 20.0);
+
+    (0..=SAMPLES)
+        .map(|i| falloff.max_distance * i as f32 / SAMPLES as f32)
+        .find(|distance| falloff.gain(*distance) < threshold)
+}
+
+/// Press `V` to cycle through the overlay's modes.
+fn cycle_overlay(input: Res<ButtonInput<KeyCode>>, mut overlay: ResMut<RadiusOverlay>) {
+    if !input.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+
+    *overlay = match *overlay {
+        RadiusOverlay::Off => RadiusOverlay::All,
+        RadiusOverlay::All => RadiusOverlay::Hovered,
+        RadiusOverlay::Hovered => RadiusOverlay::Off,
+    };
+    info!("radius overlay: {:?}", *overlay);
+}
+
+fn draw_radii(
+    overlay: Res<RadiusOverlay>,
+    emitters: Query<(Entity, &GlobalTransform, &SpatialAudioFalloff)>,
+    listeners: Listeners,
+    window: Single<&Window, With<PrimaryWindow>>,
+    camera: Single<(&Camera, &GlobalTransform), With<Camera2d>>,
+    mut gizmos: Gizmos,
+) {
+    let (alpha, hovered) = match *overlay {
+        RadiusOverlay::Off => return,
+        RadiusOverlay::All => (DIM_ALPHA, None),
+        RadiusOverlay::Hovered => {
+            let (camera, camera_transform) = *camera;
+            let Some(cursor) = window
+                .cursor_position()
+                .and_then(|cursor| camera.viewport_to_world_2d(camera_transform, cursor).ok())
+            else {
+                return;
+            };
+
+            let hovered = emitters.iter().min_by(|(_, a, _), (_, b, _)| {
+                let a = a.translation().truncate().distance_squared(cursor);
+                let b = b.translation().truncate().distance_squared(cursor);
+                a.total_cmp(&b)
+            });
+            let Some((hovered, ..)) = hovered else {
+                return;
+            };
+
+            (1.0, Some(hovered))
+        }
+    };
+
+    for (emitter, transform, falloff) in emitters.iter() {
+        if hovered.is_some_and(|hovered| hovered != emitter) {
+            continue;
+        }
+
+        let position = transform.translation();
+        let center = position.truncate();
+        let boundaries = [
+            (boundary(falloff, HALF_LEVEL_DB), YELLOW),
+            (boundary(falloff, INAUDIBLE_DB), RED),
+            (Some(falloff.max_distance), LIME),
+        ];
+
+        for (radius, color) in boundaries {
+            if let Some(radius) = radius {
+                gizmos.circle_2d(center, radius, color.with_alpha(alpha));
+            }
+        }
+
+        let Some(listener) = closest_listener(&listeners, position) else {
+            continue;
+        };
+        let distance = listener.translation().distance(position);
+        let inside = boundaries
+            .iter()
+            .find(|(radius, _)| radius.is_some_and(|radius| distance < radius))
+            .map_or(Color::WHITE, |(_, color)| (*color).into());
+
+        gizmos.line_2d(
+            center,
+            listener.translation().truncate(),
+            inside.with_alpha(alpha),
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Matching an HRIR dataset's native sample rate to the stream's.
+//!
+//! Both dataset loaders resample the HRIRs to the stream rate when
+//! they're opened. That's transparent between common rates, but a large
+//! ratio, such as a 44.1 kHz dataset on a 192 kHz stream, degrades the
+//! filters or fails outright. Backends check the ratio with
+//! [`DatasetRate`] first, and load the dataset at its native rate when
+//! it's out of [`RESAMPLE_RATIO_RANGE`] or resampling fails.
+
+use core::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+/// The stream-to-dataset rate ratios the loaders are trusted to resample.
+pub const RESAMPLE_RATIO_RANGE: RangeInclusive<f32> = 0.25..=4.0;
+
+/// A dataset's native sample rate compared with the stream's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DatasetRate {
+    pub native: f32,
+    pub stream: f32,
+}
+
+impl DatasetRate {
+    pub fn new(native: f32, stream: f32) -> Self {
+        Self { native, stream }
+    }
+
+    /// The stream rate over the native rate.
+    pub fn ratio(&self) -> f32 {
+        self.stream / self.native
+    }
+
+    /// Whether the loader should resample to the stream rate.
+    pub fn resample(&self) -> bool {
+        RESAMPLE_RATIO_RANGE.contains(&self.ratio())
+    }
+
+    /// Log how `dataset` will be loaded.
+    pub fn log(&self, dataset: &str) {
+        let ratio = self.ratio();
+
+        if ratio == 1.0 {
+            debug!("{dataset}: loading at its native {} Hz", self.native);
+        } else if self.resample() {
+            info!(
+                "{dataset}: resampling from {} Hz to {} Hz (ratio {ratio:.3})",
+                self.native, self.stream
+            );
+        } else {
+            warn!(
+                "{dataset}: {} Hz stream is too far from the native {} Hz (ratio {ratio:.3}) \
+                 to resample, loading at the native rate",
+                self.stream, self.native
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extreme_ratios_load_at_the_native_rate() {
+        for (native, stream, resample) in [
+            (48_000.0, 48_000.0, true),
+            // Both shipped datasets on a 96 kHz stream.
+            (48_000.0, 96_000.0, true),
+            (44_100.0, 96_000.0, true),
+            // The range is inclusive at both ends.
+            (48_000.0, 192_000.0, true),
+            (48_000.0, 12_000.0, true),
+            (44_100.0, 192_000.0, false),
+            (48_000.0, 8_000.0, false),
+        ] {
+            let rate = DatasetRate::new(native, stream);
+            assert_eq!(
+                rate.resample(),
+                resample,
+                "{native} Hz on a {stream} Hz stream"
+            );
+        }
+    }
+
+    #[cfg(feature = "sofar")]
+    #[test]
+    fn sofa_dataset_resamples_to_96k() {
+        use sofar::reader::{Filter, OpenOptions};
+
+        use crate::sofar_hrtf::{SOFA_DATA, SOFA_SAMPLE_RATE};
+
+        assert!(DatasetRate::new(SOFA_SAMPLE_RATE, 96_000.0).resample());
+
+        let open = |sample_rate| {
+            OpenOptions::new()
+                .sample_rate(sample_rate)
+                .open_data(SOFA_DATA)
+                .unwrap()
+        };
+        let native = open(SOFA_SAMPLE_RATE);
+        let resampled = open(96_000.0);
+        assert_eq!(resampled.filter_len(), 2 * native.filter_len());
+
+        let mut filter = Filter::new(resampled.filter_len());
+        resampled.filter(0.0, 1.0, 0.0, &mut filter);
+        assert!(
+            filter
+                .left
+                .iter()
+                .chain(&filter.right)
+                .all(|tap| tap.is_finite())
+        );
+        assert!(filter.left.iter().any(|tap| *tap != 0.0));
+    }
+
+    #[cfg(feature = "fyrox")]
+    #[test]
+    fn sphere_resamples_to_96k() {
+        use crate::fyrox_hrtf::{SPHERE_SAMPLE_RATE, load_sphere};
+
+        assert!(DatasetRate::new(SPHERE_SAMPLE_RATE as f32, 96_000.0).resample());
+
+        let sphere = load_sphere(96_000).unwrap();
+        for point in sphere.points() {
+            let mut taps = point.left_hrir().iter().chain(point.right_hrir());
+            assert!(taps.clone().all(|tap| tap.is_finite()));
+            assert!(taps.any(|tap| *tap != 0.0));
+        }
+    }
+}
@@ -0,0 +1,725 @@
+//! Head-related transfer function (HRTF) node using `realfft` convolution.
+//!
+//! This node renders the same SOFA dataset as the `sofar` backend, but
+//! performs the convolution itself with block-based overlap-add on top
+//! of `realfft`. It stages input in fixed blocks exactly like the
+//! `fyrox` backend, so the two are interchangeable in an effect chain.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_seedling::{SeedlingSystems, prelude::*};
+use firewheel::{
+    channel_config::{ChannelConfig, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex, num_complex::Complex};
+use sofar::reader::{Filter, Sofar};
+
+use crate::crossover::{LowBandSplit, LowFrequencyMode};
+use crate::downmix::{ChannelLayout, Downmix};
+use crate::fallback::{FallibleProcessor, HrtfInitFailed, report_init_failures};
+use crate::latency::{HrtfLatencyInfo, tag_node_latency};
+use crate::limiter::{ClipCounter, OutputSafety, collect_clipped_samples, report_clipped_samples};
+use crate::sofar_hrtf::load_sofa;
+use crate::spatial::{
+    AppliedDirection, CustomDirectionSystem, FRONT, HrtfNode, HrtfNodeConfig, InitFailure,
+    SpatialAudioDebugSystems, apply_spatial_audio_commands, draw_direction_gizmos,
+    rotate_90_degrees, update_hrtf_effects, usable_direction, warn_unhandled_commands,
+};
+use crate::stale::{report_stale_directions, track_direction_age};
+use crate::state::AppState;
+use crate::tail::SilenceTail;
+use crate::template::{ChainTemplate, register_chain_template};
+use crate::trail::{draw_direction_trails, record_direction_trail};
+use crate::validate::Spatializer;
+use crate::vr::update_vr_controller_hrtf;
+
+#[derive(Default)]
+pub struct RealFftPlugin {
+    /// The chain built for [`Spatial`](crate::template::Spatial) emitters.
+    pub template: ChainTemplate,
+}
+
+impl Plugin for RealFftPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Last,
+            (
+                apply_spatial_audio_commands::<RealFftHrtfNode>.before(warn_unhandled_commands),
+                (
+                    update_hrtf_effects::<RealFftHrtfNode>,
+                    update_vr_controller_hrtf::<RealFftHrtfNode>,
+                )
+                    .run_if(
+                        not(in_state(AppState::Menu))
+                            .and(not(resource_exists::<CustomDirectionSystem>)),
+                    ),
+            )
+                .chain()
+                .before(SeedlingSystems::Acquire),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    draw_direction_gizmos::<RealFftHrtfNode>,
+                    record_direction_trail::<RealFftHrtfNode>.before(draw_direction_trails),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                tag_node_latency::<RealFftHrtfNode>,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                (
+                    collect_clipped_samples::<RealFftHrtfNode>.before(report_clipped_samples),
+                    track_direction_age::<RealFftHrtfNode>.before(report_stale_directions),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                report_init_failures::<RealFftHrtfNode>,
+            ),
+        )
+        .add_event::<HrtfInitFailed>()
+        .register_node::<RealFftHrtfNode>()
+        .register_required_components::<RealFftHrtfNode, Spatializer>()
+        .register_type::<RealFftHrtfNode>()
+        .register_type::<HrtfConfig>();
+
+        app.world_mut()
+            .get_resource_or_init::<HrtfLatencyInfo>()
+            .include(HrtfConfig::default().block_len as u32);
+
+        register_chain_template::<RealFftHrtfNode>(app, self.template.clone());
+    }
+}
+
+/// Head-related transfer function (HRTF) node.
+///
+/// Spawning the node alone inserts a default [`HrtfConfig`].
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+#[require(Transform, HrtfConfig)]
+pub struct RealFftHrtfNode {
+    /// The direction vector pointing from the listener to the
+    /// emitter.
+    pub direction: Vec3,
+    /// Pass the downmixed input to both ears unprocessed.
+    pub bypass: bool,
+}
+
+/// Configuration for [`RealFftHrtfNode`].
+///
+/// Only the tuning fields are reflected. The shared cells are
+/// recreated on load, and the channel count falls back to stereo.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct HrtfConfig {
+    /// The number of input channels.
+    ///
+    /// The inputs are downmixed to a mono signal
+    /// before spatialization is applied.
+    ///
+    /// Defaults to [`NonZeroChannelCount::STEREO`].
+    #[reflect(ignore)]
+    pub input_channels: NonZeroChannelCount,
+
+    /// How the input channels are weighted in the downmix.
+    ///
+    /// An invalid layout is logged and the inputs are averaged instead.
+    ///
+    /// Defaults to [`ChannelLayout::Auto`].
+    #[reflect(ignore)]
+    pub channel_layout: ChannelLayout,
+
+    /// How frequencies below `crossover_frequency` are rendered.
+    ///
+    /// Defaults to [`LowFrequencyMode::Full`].
+    pub low_freq_mode: LowFrequencyMode,
+
+    /// The crossover frequency in Hz when `low_freq_mode` is
+    /// [`LowFrequencyMode::SimplePan`].
+    ///
+    /// Clamped to [`CROSSOVER_RANGE`](crate::crossover::CROSSOVER_RANGE). Defaults to 400 Hz.
+    pub crossover_frequency: f32,
+
+    /// The number of frames convolved at once.
+    ///
+    /// This is also the node's latency. A node with a
+    /// `block_len` of zero passes audio through.
+    ///
+    /// Defaults to 256.
+    pub block_len: usize,
+
+    /// A cell the processor publishes its applied direction to.
+    ///
+    /// Defaults to `Some` in debug builds and `None` in release builds.
+    #[reflect(ignore)]
+    pub applied_direction: Option<Arc<AppliedDirection>>,
+
+    /// The safety stage applied to the output.
+    ///
+    /// Defaults to [`OutputSafety::Off`] on native targets and a
+    /// soft clip on the web, where there is no system limiter.
+    pub output_safety: OutputSafety,
+
+    /// Counts output samples that crossed the safety ceiling.
+    #[reflect(ignore)]
+    pub clip_counter: Arc<ClipCounter>,
+
+    /// Flagged when the processor falls back to passthrough.
+    #[reflect(ignore)]
+    pub init_failure: Arc<InitFailure>,
+}
+
+impl Default for HrtfConfig {
+    fn default() -> Self {
+        Self {
+            input_channels: NonZeroChannelCount::STEREO,
+            channel_layout: ChannelLayout::Auto,
+            low_freq_mode: LowFrequencyMode::Full,
+            crossover_frequency: 400.0,
+            block_len: 256,
+            applied_direction: cfg!(debug_assertions).then(Default::default),
+            output_safety: OutputSafety::default(),
+            clip_counter: Default::default(),
+            init_failure: Default::default(),
+        }
+    }
+}
+
+impl HrtfNodeConfig for HrtfConfig {
+    fn applied_direction(&self) -> Option<&Arc<AppliedDirection>> {
+        self.applied_direction.as_ref()
+    }
+
+    fn clip_counter(&self) -> &Arc<ClipCounter> {
+        &self.clip_counter
+    }
+
+    fn latency_frames(&self) -> u32 {
+        self.block_len as u32
+    }
+
+    fn init_failure(&self) -> Option<&Arc<InitFailure>> {
+        Some(&self.init_failure)
+    }
+}
+
+impl HrtfNode for RealFftHrtfNode {
+    fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    fn set_direction(&mut self, direction: Vec3) {
+        self.direction = direction;
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+}
+
+/// The frequency-domain filters for one ear.
+struct EarSpectrum {
+    current: Vec<Complex<f32>>,
+    previous: Vec<Complex<f32>>,
+    overlap: Vec<f32>,
+}
+
+struct RealFftHrtfProcessor {
+    sofa: Arc<Sofar>,
+    filter: Filter,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    block_len: usize,
+    ears: [EarSpectrum; 2],
+    /// Set when the filters changed since the last rendered block.
+    crossfade: bool,
+    fft_input: Vec<f32>,
+    time_buffer: Vec<f32>,
+    previous_time_buffer: Vec<f32>,
+    input_spectrum: Vec<Complex<f32>>,
+    product: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+    fft_output: Vec<(f32, f32)>,
+    split: Option<LowBandSplit>,
+    applied_direction: Option<Arc<AppliedDirection>>,
+    output_safety: OutputSafety,
+    clip_counter: Arc<ClipCounter>,
+    downmix: Downmix,
+    bypass: bool,
+    tail: SilenceTail,
+}
+
+impl AudioNode for RealFftHrtfNode {
+    type Configuration = HrtfConfig;
+
+    fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("realfft hrtf node")
+            .channel_config(ChannelConfig::new(config.input_channels.get(), 2))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let processor = RealFftHrtfProcessor::new(
+            config,
+            self,
+            cx.stream_info.sample_rate.get() as f32,
+            cx.stream_info.max_block_frames.get() as usize,
+        );
+
+        FallibleProcessor::new(processor, &config.init_failure)
+    }
+}
+
+/// A [`HrtfConfig::block_len`] of zero.
+#[derive(Debug)]
+pub struct ZeroBlockLen;
+
+impl core::fmt::Display for ZeroBlockLen {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("the block length must be at least one frame")
+    }
+}
+
+impl std::error::Error for ZeroBlockLen {}
+
+impl RealFftHrtfProcessor {
+    fn new(
+        config: &HrtfConfig,
+        node: &RealFftHrtfNode,
+        sample_rate: f32,
+        max_block_frames: usize,
+    ) -> Result<Self, ZeroBlockLen> {
+        let block_len = config.block_len;
+        if block_len == 0 {
+            return Err(ZeroBlockLen);
+        }
+
+        let sofa = load_sofa(sample_rate);
+        let filt_len = sofa.filter_len();
+        let fft_len = (block_len + filt_len - 1).next_power_of_two();
+
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(fft_len);
+        let inverse = planner.plan_fft_inverse(fft_len);
+
+        let ear = || EarSpectrum {
+            current: forward.make_output_vec(),
+            previous: forward.make_output_vec(),
+            overlap: vec![0.0; fft_len],
+        };
+
+        let mut processor = RealFftHrtfProcessor {
+            filter: Filter::new(filt_len),
+            ears: [ear(), ear()],
+            crossfade: false,
+            fft_input: Vec::with_capacity(block_len),
+            time_buffer: forward.make_input_vec(),
+            previous_time_buffer: forward.make_input_vec(),
+            input_spectrum: forward.make_output_vec(),
+            product: forward.make_output_vec(),
+            scratch: vec![
+                Complex::default();
+                forward.get_scratch_len().max(inverse.get_scratch_len())
+            ],
+            fft_output: Vec::with_capacity(max_block_frames.max(block_len)),
+            split: LowBandSplit::new(
+                config.low_freq_mode,
+                config.crossover_frequency,
+                sample_rate,
+                filt_len,
+                block_len,
+            ),
+            block_len,
+            sofa,
+            forward,
+            inverse,
+            applied_direction: config.applied_direction.clone(),
+            output_safety: config.output_safety,
+            clip_counter: config.clip_counter.clone(),
+            downmix: Downmix::new_or_average(&config.channel_layout, config.input_channels),
+            bypass: node.bypass,
+            // The filter plus the staging latency.
+            tail: SilenceTail::new(filt_len + block_len),
+        };
+
+        processor.set_direction(usable_direction(node.direction).unwrap_or(FRONT));
+        processor.crossfade = false;
+
+        Ok(processor)
+    }
+
+    /// Look up the filter for `direction`, keeping the
+    /// current one if it's unusable.
+    fn set_direction(&mut self, direction: Vec3) {
+        let Some(direction) = usable_direction(direction) else {
+            return;
+        };
+
+        if let Some(applied) = &self.applied_direction {
+            applied.store(direction);
+        }
+
+        // rotate the vector by 90 degrees about the head
+        let direction = rotate_90_degrees(direction, Vec3::NEG_Z);
+
+        self.sofa
+            .filter(direction.x, direction.y, direction.z, &mut self.filter);
+
+        if let Some(split) = &mut self.split {
+            split
+                .pan
+                .set_from_hrir(&self.filter.left, &self.filter.right);
+        }
+
+        // The inverse transform is unnormalized, so fold
+        // the scaling into the filter spectra.
+        let scale = 1.0 / self.time_buffer.len() as f32;
+
+        for (ear, taps) in self
+            .ears
+            .iter_mut()
+            .zip([&self.filter.left, &self.filter.right])
+        {
+            // Only keep the oldest filter around if the previous
+            // one was never rendered.
+            if !self.crossfade {
+                core::mem::swap(&mut ear.current, &mut ear.previous);
+            }
+
+            self.time_buffer.fill(0.0);
+            self.time_buffer[..taps.len()].copy_from_slice(taps);
+            self.forward
+                .process_with_scratch(&mut self.time_buffer, &mut ear.current, &mut self.scratch)
+                .unwrap();
+
+            for bin in &mut ear.current {
+                *bin *= scale;
+            }
+        }
+
+        self.crossfade = true;
+    }
+
+    /// Convolve a full block of staged input.
+    fn render_block(&mut self) {
+        let block_len = self.block_len;
+
+        self.time_buffer.fill(0.0);
+        self.time_buffer[..block_len].copy_from_slice(&self.fft_input);
+        self.forward
+            .process_with_scratch(
+                &mut self.time_buffer,
+                &mut self.input_spectrum,
+                &mut self.scratch,
+            )
+            .unwrap();
+
+        let output_start = self.fft_output.len();
+        self.fft_output
+            .extend(std::iter::repeat_n((0.0, 0.0), block_len));
+
+        for (channel, ear) in self.ears.iter_mut().enumerate() {
+            convolve(
+                &self.input_spectrum,
+                &ear.current,
+                &mut self.product,
+                &mut self.time_buffer,
+                &mut self.scratch,
+                self.inverse.as_ref(),
+            );
+
+            if self.crossfade {
+                convolve(
+                    &self.input_spectrum,
+                    &ear.previous,
+                    &mut self.product,
+                    &mut self.previous_time_buffer,
+                    &mut self.scratch,
+                    self.inverse.as_ref(),
+                );
+
+                // Linearly fade from the previous filter to the new one over
+                // the block. The tail beyond the block uses the new filter.
+                for i in 0..block_len {
+                    let t = i as f32 / block_len as f32;
+                    self.time_buffer[i] =
+                        self.previous_time_buffer[i] * (1.0 - t) + self.time_buffer[i] * t;
+                }
+            }
+
+            for (overlap, sample) in ear.overlap.iter_mut().zip(&self.time_buffer) {
+                *overlap += sample;
+            }
+
+            for (frame, sample) in self.fft_output[output_start..]
+                .iter_mut()
+                .zip(&ear.overlap[..block_len])
+            {
+                if channel == 0 {
+                    frame.0 = *sample;
+                } else {
+                    frame.1 = *sample;
+                }
+            }
+
+            ear.overlap.copy_within(block_len.., 0);
+            let len = ear.overlap.len();
+            ear.overlap[len - block_len..].fill(0.0);
+        }
+
+        if let Some(split) = &mut self.split {
+            for (frame, low) in self.fft_output[output_start..]
+                .iter_mut()
+                .zip(split.low.drain(..))
+            {
+                let (left, right) = split.pan.process(low);
+                frame.0 += left;
+                frame.1 += right;
+            }
+        }
+
+        self.crossfade = false;
+        self.fft_input.clear();
+    }
+}
+
+fn convolve(
+    input: &[Complex<f32>],
+    filter: &[Complex<f32>],
+    product: &mut [Complex<f32>],
+    output: &mut [f32],
+    scratch: &mut [Complex<f32>],
+    inverse: &dyn ComplexToReal<f32>,
+) {
+    for ((product, input), filter) in product.iter_mut().zip(input).zip(filter) {
+        *product = input * filter;
+    }
+
+    // The DC and Nyquist bins of a real signal have no imaginary part,
+    // but rounding can leave a tiny residue `realfft` rejects.
+    product[0].im = 0.0;
+    if let Some(last) = product.last_mut() {
+        last.im = 0.0;
+    }
+
+    inverse
+        .process_with_scratch(product, output, scratch)
+        .unwrap();
+}
+
+impl AudioNodeProcessor for RealFftHrtfProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        // Only the latest direction matters, so superseded
+        // patches are dropped rather than each triggering a lookup.
+        let mut latest_direction = None;
+        events.for_each_patch::<RealFftHrtfNode>(|patch| match patch {
+            RealFftHrtfNodePatch::Direction(direction) => latest_direction = Some(direction),
+            RealFftHrtfNodePatch::Bypass(bypass) => self.bypass = bypass,
+        });
+
+        if let Some(direction) = latest_direction {
+            self.set_direction(direction);
+        }
+
+        let silent = proc_info.in_silence_mask.all_channels_silent(inputs.len());
+        if !self.tail.process_block(silent, proc_info.frames) {
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        if self.bypass {
+            let (left, right) = outputs.split_at_mut(1);
+            let left = &mut left[0][..proc_info.frames];
+            for (frame, sample) in left.iter_mut().enumerate() {
+                *sample = self.downmix.sample(inputs, frame);
+            }
+            right[0][..proc_info.frames].copy_from_slice(left);
+
+            return ProcessStatus::outputs_not_silent();
+        }
+
+        for frame in 0..proc_info.frames {
+            let mut downmixed = self.downmix.sample(inputs, frame);
+
+            // Only the high band is convolved when splitting.
+            if let Some(split) = &mut self.split {
+                let (low, high) = split.crossover.split(downmixed);
+                split.low.push(low);
+                downmixed = high;
+            }
+
+            self.fft_input.push(downmixed);
+
+            if self.fft_input.len() == self.block_len {
+                self.render_block();
+            }
+        }
+
+        for (i, (left, right)) in self
+            .fft_output
+            .drain(..proc_info.frames.min(self.fft_output.len()))
+            .enumerate()
+        {
+            outputs[0][i] = left;
+            outputs[1][i] = right;
+        }
+
+        let clipped = outputs
+            .iter_mut()
+            .map(|output| self.output_safety.process(&mut output[..proc_info.frames]))
+            .sum();
+        self.clip_counter.add(clipped);
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::DemoRng;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    fn processor(block_len: usize) -> RealFftHrtfProcessor {
+        let config = HrtfConfig {
+            block_len,
+            ..default()
+        };
+        RealFftHrtfProcessor::new(&config, &RealFftHrtfNode::default(), SAMPLE_RATE, block_len)
+            .unwrap()
+    }
+
+    /// Convolve `input` block by block, the way `process` stages it.
+    fn convolve_blocks(processor: &mut RealFftHrtfProcessor, input: &[f32]) -> [Vec<f32>; 2] {
+        let mut output = [Vec::new(), Vec::new()];
+
+        for block in input.chunks_exact(processor.block_len) {
+            processor.fft_input.extend_from_slice(block);
+            processor.render_block();
+
+            for (left, right) in processor.fft_output.drain(..) {
+                output[0].push(left);
+                output[1].push(right);
+            }
+        }
+
+        output
+    }
+
+    #[test]
+    fn zero_block_lengths_are_rejected() {
+        let config = HrtfConfig {
+            block_len: 0,
+            ..default()
+        };
+        let node = RealFftHrtfNode::default();
+
+        assert!(RealFftHrtfProcessor::new(&config, &node, SAMPLE_RATE, 256).is_err());
+    }
+
+    /// The FFT convolution matches a direct one to within single-precision rounding.
+    ///
+    /// The worst error measured against the direct convolution is
+    /// around 1e-5 of full scale at each block length.
+    #[test]
+    fn matches_direct_convolution() {
+        let mut rng = DemoRng { seed: 5 }.stream("realfft");
+        let input: Vec<f32> = (0..4096).map(|_| rng.range(-1.0, 1.0)).collect();
+
+        for block_len in [128, 256, 512] {
+            let mut processor = processor(block_len);
+            let output = convolve_blocks(&mut processor, &input);
+
+            for (output, taps) in output
+                .iter()
+                .zip([&processor.filter.left, &processor.filter.right])
+            {
+                let mut worst = 0.0f32;
+                for (frame, sample) in output.iter().enumerate() {
+                    let direct: f32 = taps
+                        .iter()
+                        .take(frame + 1)
+                        .enumerate()
+                        .map(|(tap, weight)| weight * input[frame - tap])
+                        .sum();
+                    worst = worst.max((sample - direct).abs());
+                }
+
+                assert!(worst < 1e-4, "block length {block_len}: error {worst}");
+            }
+        }
+    }
+
+    /// Time both FFT backends at each block length.
+    ///
+    /// Run with `cargo test --release --features realfft-backend,fyrox
+    /// benchmark -- --ignored --nocapture`. The two render different
+    /// datasets, so only their speed is compared here.
+    #[cfg(feature = "fyrox")]
+    #[test]
+    #[ignore = "a benchmark, not a check"]
+    fn benchmark_against_fyrox() {
+        use std::time::Instant;
+
+        use hrtf::{HrtfContext, HrtfProcessor};
+
+        const SECONDS: usize = 10;
+
+        let mut rng = DemoRng { seed: 5 }.stream("realfft");
+        let input: Vec<f32> = (0..SAMPLE_RATE as usize * SECONDS)
+            .map(|_| rng.range(-1.0, 1.0))
+            .collect();
+
+        // `hrtf` can't plan 256-frame blocks, see `fyrox_hrtf::BLOCK_LEN`.
+        for block_len in [128, 257, 512] {
+            let mut realfft = processor(block_len);
+            let start = Instant::now();
+            std::hint::black_box(convolve_blocks(&mut realfft, &input));
+            let realfft = start.elapsed();
+
+            let sphere = crate::fyrox_hrtf::load_sphere(SAMPLE_RATE as u32).unwrap();
+            let mut fyrox = HrtfProcessor::new(sphere, 1, block_len);
+            let direction = hrtf::Vec3::new(FRONT.x, FRONT.y, FRONT.z);
+            let mut output = vec![(0.0, 0.0); block_len];
+            let (mut prev_left, mut prev_right) = (Vec::new(), Vec::new());
+            let start = Instant::now();
+            for block in input.chunks_exact(block_len) {
+                output.fill((0.0, 0.0));
+                fyrox.process_samples(HrtfContext {
+                    source: block,
+                    output: &mut output,
+                    new_sample_vector: direction,
+                    prev_sample_vector: direction,
+                    prev_left_samples: &mut prev_left,
+                    prev_right_samples: &mut prev_right,
+                    new_distance_gain: 1.0,
+                    prev_distance_gain: 1.0,
+                });
+                std::hint::black_box(&output);
+            }
+            let fyrox = start.elapsed();
+
+            println!(
+                "{block_len} frames: realfft {:.1} ms, fyrox {:.1} ms per {SECONDS} s of audio",
+                realfft.as_secs_f64() * 1000.0,
+                fyrox.as_secs_f64() * 1000.0,
+            );
+        }
+    }
+}
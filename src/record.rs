@@ -0,0 +1,364 @@
+//! Binaural output recording synchronized with screen captures.
+//!
+//! While Bevy's screenshot pipeline is active (that is, a [`Screenshot`]
+//! is requested every frame), the final mix is captured into a buffer
+//! and written to a WAV file once the captures stop.
+//!
+//! The audio thread can't lock or allocate, so the [`RecordNode`]
+//! writes into a fixed [`RecordBuffer`] ring, and the ECS drains it
+//! into the recording every frame. A block that doesn't fit because
+//! the ring wasn't drained in time is dropped and reported.
+
+use std::{
+    fmt,
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+    },
+};
+
+use bevy::{diagnostic::FrameCount, prelude::*, render::view::screenshot::Screenshot};
+use bevy_seedling::{SeedlingSystems, prelude::*};
+use firewheel::{
+    channel_config::ChannelConfig,
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+
+use crate::wav;
+
+pub struct SpatialAudioRecordPlugin;
+
+impl Plugin for SpatialAudioRecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialAudioRecord>()
+            .register_node::<RecordNode>()
+            .register_type::<RecordNode>()
+            .add_systems(Startup, spawn_recorder)
+            .add_systems(
+                Last,
+                (drain_recordings, sync_recording)
+                    .chain()
+                    .before(SeedlingSystems::Acquire),
+            );
+    }
+}
+
+/// Settings and state for audio capture alongside screenshots.
+#[derive(Debug, Resource)]
+pub struct SpatialAudioRecord {
+    /// The directory recordings are written to.
+    pub output_dir: PathBuf,
+    /// The frame the current recording started on, if any.
+    ///
+    /// Recordings are named after this frame so they
+    /// can be aligned with the corresponding screenshots.
+    start_frame: Option<u32>,
+    last_capture_frame: u32,
+}
+
+impl Default for SpatialAudioRecord {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("captures"),
+            start_frame: None,
+            last_capture_frame: 0,
+        }
+    }
+}
+
+impl SpatialAudioRecord {
+    /// Returns `true` if audio is currently being captured.
+    pub fn is_recording(&self) -> bool {
+        self.start_frame.is_some()
+    }
+}
+
+/// A sink node that captures its stereo input while recording.
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct RecordNode {
+    /// Whether incoming audio should be captured.
+    pub recording: bool,
+}
+
+/// Configuration for [`RecordNode`].
+///
+/// Holds the buffer shared between the audio thread and the ECS.
+#[derive(Debug, Default, Clone, Component)]
+pub struct RecordConfig {
+    pub buffer: Arc<RecordBuffer>,
+}
+
+/// How many interleaved samples a [`RecordBuffer`] holds, a little
+/// over a second of stereo at 48 kHz.
+pub const RECORD_RING_LEN: usize = 1 << 17;
+
+/// Interleaved stereo samples on their way from a [`RecordNode`]
+/// to the ECS.
+///
+/// A ring with a single writer, the processor, and a single reader,
+/// the ECS. Samples are stored as their bits.
+pub struct RecordBuffer {
+    ring: Box<[AtomicU32]>,
+    /// The number of samples ever written.
+    written: AtomicUsize,
+    /// The number of samples ever read.
+    read: AtomicUsize,
+    /// Frames dropped because the ring was full.
+    overruns: AtomicU64,
+    sample_rate: AtomicU32,
+}
+
+impl Default for RecordBuffer {
+    fn default() -> Self {
+        Self {
+            ring: (0..RECORD_RING_LEN).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicUsize::new(0),
+            read: AtomicUsize::new(0),
+            overruns: AtomicU64::new(0),
+            sample_rate: AtomicU32::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for RecordBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordBuffer")
+            .field("written", &self.written)
+            .field("read", &self.read)
+            .field("overruns", &self.overruns)
+            .field("sample_rate", &self.sample_rate)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecordBuffer {
+    /// Write a block of frames, or drop all of it if it doesn't fit.
+    ///
+    /// Never waits, locks or allocates.
+    pub fn push(&self, left: &[f32], right: &[f32]) {
+        let frames = left.len().min(right.len());
+        let written = self.written.load(Ordering::Relaxed);
+        let free = self.ring.len() - (written - self.read.load(Ordering::Acquire));
+        if 2 * frames > free {
+            self.overruns.fetch_add(frames as u64, Ordering::Relaxed);
+            return;
+        }
+
+        for (i, (left, right)) in left.iter().zip(right).enumerate() {
+            let index = written + 2 * i;
+            self.ring[index % self.ring.len()].store(left.to_bits(), Ordering::Relaxed);
+            self.ring[(index + 1) % self.ring.len()].store(right.to_bits(), Ordering::Relaxed);
+        }
+
+        self.written.store(written + 2 * frames, Ordering::Release);
+    }
+
+    /// Move everything written so far onto the end of `samples`.
+    pub fn drain_into(&self, samples: &mut Vec<f32>) {
+        let written = self.written.load(Ordering::Acquire);
+        let read = self.read.load(Ordering::Relaxed);
+
+        samples.extend((read..written).map(|index| {
+            f32::from_bits(self.ring[index % self.ring.len()].load(Ordering::Relaxed))
+        }));
+
+        self.read.store(written, Ordering::Release);
+    }
+
+    /// The number of frames dropped since the last call.
+    pub fn take_overruns(&self) -> u64 {
+        self.overruns.swap(0, Ordering::Relaxed)
+    }
+
+    /// The sample rate of the captured audio.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate.load(Ordering::Relaxed)
+    }
+}
+
+/// The samples a [`RecordNode`] has captured this recording.
+#[derive(Debug, Default, Component)]
+pub struct Recording(Vec<f32>);
+
+struct RecordProcessor {
+    recording: bool,
+    buffer: Arc<RecordBuffer>,
+}
+
+impl AudioNode for RecordNode {
+    type Configuration = RecordConfig;
+
+    fn info(&self, _config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("record node")
+            .channel_config(ChannelConfig::new(2, 0))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        config
+            .buffer
+            .sample_rate
+            .store(cx.stream_info.sample_rate.get(), Ordering::Relaxed);
+
+        RecordProcessor {
+            recording: self.recording,
+            buffer: config.buffer.clone(),
+        }
+    }
+}
+
+impl AudioNodeProcessor for RecordProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers { inputs, .. }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        events.for_each_patch::<RecordNode>(|RecordNodePatch::Recording(recording)| {
+            self.recording = recording;
+        });
+
+        if !self.recording {
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        self.buffer.push(
+            &inputs[0][..proc_info.frames],
+            &inputs[1][..proc_info.frames],
+        );
+
+        ProcessStatus::ClearAllOutputs
+    }
+}
+
+fn spawn_recorder(main_bus: Single<Entity, With<MainBus>>, mut commands: Commands) {
+    let recorder = commands
+        .spawn((RecordNode::default(), Recording::default()))
+        .id();
+    commands.entity(*main_bus).connect(recorder);
+}
+
+/// Move what each recorder captured out of its ring,
+/// before the ring fills.
+fn drain_recordings(mut recorders: Query<(Entity, &RecordConfig, &mut Recording)>) {
+    for (entity, config, mut recording) in recorders.iter_mut() {
+        config.buffer.drain_into(&mut recording.0);
+
+        let overruns = config.buffer.take_overruns();
+        if overruns > 0 {
+            warn!("recorder {entity} dropped {overruns} frames that it couldn't keep up with");
+        }
+    }
+}
+
+fn sync_recording(
+    captures: Query<(), Added<Screenshot>>,
+    mut recorders: Query<(&mut RecordNode, &RecordConfig, &mut Recording)>,
+    mut record: ResMut<SpatialAudioRecord>,
+    frame: Res<FrameCount>,
+) {
+    if !captures.is_empty() {
+        record.last_capture_frame = frame.0;
+
+        if !record.is_recording() {
+            record.start_frame = Some(frame.0);
+
+            for (mut recorder, _, mut recording) in recorders.iter_mut() {
+                // Discard anything left over from a previous recording.
+                recording.0.clear();
+                recorder.recording = true;
+            }
+        }
+
+        return;
+    }
+
+    // Captures are considered finished once a full frame
+    // passes without a screenshot request.
+    let Some(start_frame) = record.start_frame else {
+        return;
+    };
+
+    if frame.0.wrapping_sub(record.last_capture_frame) <= 1 {
+        return;
+    }
+
+    record.start_frame = None;
+
+    if let Err(e) = std::fs::create_dir_all(&record.output_dir) {
+        error!("failed to create audio capture directory: {e}");
+        return;
+    }
+
+    for (i, (mut recorder, config, mut recording)) in recorders.iter_mut().enumerate() {
+        recorder.recording = false;
+
+        let samples = core::mem::take(&mut recording.0);
+        let path = record
+            .output_dir
+            .join(format!("audio-{start_frame}-{i}.wav"));
+
+        match wav::write_wav(&path, 2, config.buffer.sample_rate(), &samples) {
+            Ok(()) => info!(
+                "wrote {:.2}s of audio for frames {start_frame}..={} to {}",
+                samples.len() as f32 / 2.0 / config.buffer.sample_rate() as f32,
+                record.last_capture_frame,
+                path.display()
+            ),
+            Err(e) => error!("failed to write audio capture: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_arrive_interleaved_and_in_order() {
+        let buffer = RecordBuffer::default();
+        let mut samples = Vec::new();
+
+        // Enough blocks to wrap around the ring several times.
+        let block: Vec<f32> = (0..1000).map(|i| i as f32).collect();
+        for round in 0..10 * RECORD_RING_LEN / block.len() {
+            let right: Vec<f32> = block.iter().map(|sample| -sample - round as f32).collect();
+            buffer.push(&block, &right);
+
+            let start = samples.len();
+            buffer.drain_into(&mut samples);
+            assert_eq!(samples.len() - start, 2 * block.len());
+            for (i, frame) in samples[start..].chunks(2).enumerate() {
+                assert_eq!(frame, [block[i], right[i]]);
+            }
+        }
+
+        assert_eq!(buffer.take_overruns(), 0);
+    }
+
+    #[test]
+    fn a_full_ring_drops_whole_blocks_and_counts_them() {
+        let buffer = RecordBuffer::default();
+        let block = vec![0.5; RECORD_RING_LEN / 2 - 10];
+
+        buffer.push(&block, &block);
+        buffer.push(&block, &block);
+        assert_eq!(buffer.take_overruns(), block.len() as u64);
+        assert_eq!(buffer.take_overruns(), 0);
+
+        let mut samples = Vec::new();
+        buffer.drain_into(&mut samples);
+        assert_eq!(samples.len(), 2 * block.len());
+
+        // Draining makes room again.
+        buffer.push(&block, &block);
+        assert_eq!(buffer.take_overruns(), 0);
+    }
+}
@@ -0,0 +1,214 @@
+//! Mirror-image reflections off single walls.
+//!
+//! Short of simulating early reflections, one mirrored copy of a source
+//! is enough to hear that there's a wall nearby. For every emitter
+//! within range of a [`ReflectiveSurface`], an image emitter plays the
+//! same sample from the emitter's mirror image across the surface, so
+//! its HRTF places it where the reflection would arrive from.
+//!
+//! The image is quieter than the source by the surface's absorption
+//! and by how much longer the reflected path is, and slightly
+//! low-passed. It's despawned once the source leaves the range, the
+//! reflection point falls off the surface, or an [`Occluder`] blocks
+//! either leg of the reflected path.
+//!
+//! Images start their sample from the beginning rather than in step
+//! with the source, so they suit textures and loops better than
+//! speech.
+
+use std::collections::BTreeMap;
+
+use bevy::{color::palettes::css::SILVER, prelude::*};
+use bevy_seedling::prelude::*;
+
+use crate::occlusion::{Occluder, OcclusionFilterNode, occluder_blocks};
+use crate::spatial::{Listeners, closest_listener};
+use crate::template::spawn_template_chain;
+
+pub struct ReflectionPlugin;
+
+impl Plugin for ReflectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (update_image_emitters, draw_surfaces));
+    }
+}
+
+/// A wall that reflects nearby emitters.
+///
+/// The surface is a segment along the entity's local X axis, centered
+/// on its transform, and reflects toward both sides.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ReflectiveSurface {
+    pub half_length: f32,
+    /// The fraction of energy the surface absorbs, from 0 to 1.
+    pub absorption: f32,
+    /// How close an emitter must be to the surface to be reflected.
+    pub range: f32,
+}
+
+/// Plays the reflection of `source` off `surface`.
+#[derive(Debug, Clone, Copy, Component)]
+pub struct ImageEmitter {
+    pub source: Entity,
+    pub surface: Entity,
+}
+
+/// The filter shaping an [`ImageEmitter`]'s level and tone.
+#[derive(Component)]
+struct ImageFilter;
+
+/// The cutoff of every image's low-pass, in Hz.
+const IMAGE_CUTOFF: f32 = 6000.0;
+
+/// The reflected path from an emitter to a listener.
+struct Reflection {
+    /// The emitter's mirror image across the surface.
+    image: Vec3,
+    /// Where the path bounces off the surface.
+    point: Vec3,
+    gain: f32,
+}
+
+impl ReflectiveSurface {
+    /// Reflect `emitter` toward `listener` off this surface placed at `transform`.
+    ///
+    /// Returns `None` if the emitter is out of range, either is on the
+    /// surface or they're on opposite sides, or the reflection point
+    /// misses the surface.
+    fn reflect(
+        &self,
+        transform: &GlobalTransform,
+        emitter: Vec3,
+        listener: Vec3,
+    ) -> Option<Reflection> {
+        let center = transform.translation();
+        let tangent = transform.right().as_vec3();
+        let normal = transform.up().as_vec3();
+
+        let along = (emitter - center)
+            .dot(tangent)
+            .clamp(-self.half_length, self.half_length);
+        if emitter.distance(center + tangent * along) > self.range {
+            return None;
+        }
+
+        let emitter_side = (emitter - center).dot(normal);
+        let listener_side = (listener - center).dot(normal);
+        if emitter_side * listener_side <= 0.0 {
+            return None;
+        }
+
+        // The image sits as far behind the surface as the emitter is
+        // in front, so the line from the listener crosses the surface
+        // where the sides' distances split it.
+        let image = emitter - normal * (2.0 * emitter_side);
+        let point = listener.lerp(image, listener_side / (listener_side + emitter_side));
+        if (point - center).dot(tangent).abs() > self.half_length {
+            return None;
+        }
+
+        let direct = emitter.distance(listener).max(f32::EPSILON);
+        let reflected = image.distance(listener).max(direct);
+
+        Some(Reflection {
+            image,
+            point,
+            gain: (1.0 - self.absorption.clamp(0.0, 1.0)) * direct / reflected,
+        })
+    }
+}
+
+/// Keep one image per emitter and surface it reflects off,
+/// spawning and despawning them as reflections come and go.
+fn update_image_emitters(
+    emitters: Query<
+        (Entity, &SamplePlayer, &GlobalTransform),
+        (With<SampleEffects>, Without<ImageEmitter>),
+    >,
+    surfaces: Query<(Entity, &GlobalTransform, &ReflectiveSurface)>,
+    mut images: Query<(
+        Entity,
+        &ImageEmitter,
+        &mut Transform,
+        Option<&SampleEffects>,
+    )>,
+    mut filters: Query<&mut OcclusionFilterNode, With<ImageFilter>>,
+    occluders: Query<(&GlobalTransform, &Occluder)>,
+    listeners: Listeners,
+    mut commands: Commands,
+) {
+    let mut existing: BTreeMap<(Entity, Entity), Entity> = images
+        .iter()
+        .map(|(entity, image, ..)| ((image.source, image.surface), entity))
+        .collect();
+
+    for (source, player, transform) in emitters.iter() {
+        let position = transform.translation();
+        let Some(listener) = closest_listener(&listeners, position) else {
+            continue;
+        };
+        let listener = listener.translation();
+
+        for (surface, surface_transform, reflective) in surfaces.iter() {
+            let reflection = reflective
+                .reflect(surface_transform, position, listener)
+                .filter(|Reflection { point, .. }| {
+                    !occluders.iter().any(|occluder| {
+                        occluder_blocks(occluder, position, *point)
+                            || occluder_blocks(occluder, *point, listener)
+                    })
+                });
+            let Some(Reflection { image, gain, .. }) = reflection else {
+                continue;
+            };
+
+            match existing.remove(&(source, surface)) {
+                Some(entity) => {
+                    let Ok((_, _, mut image_transform, effects)) = images.get_mut(entity) else {
+                        continue;
+                    };
+                    image_transform.translation = image;
+
+                    for effect in effects.into_iter().flat_map(|effects| effects.iter()) {
+                        let Ok(mut filter) = filters.get_mut(effect) else {
+                            continue;
+                        };
+                        if filter.gain != gain {
+                            filter.gain = gain;
+                        }
+                    }
+                }
+                None => {
+                    let mut emitter = commands.spawn((
+                        player.clone(),
+                        Transform::from_translation(image),
+                        ImageEmitter { source, surface },
+                    ));
+                    spawn_template_chain(&mut emitter, move |chain| {
+                        chain.spawn((
+                            OcclusionFilterNode {
+                                cutoff: IMAGE_CUTOFF,
+                                gain,
+                            },
+                            ImageFilter,
+                        ));
+                    });
+                }
+            }
+        }
+    }
+
+    // Whatever's left no longer reflects.
+    for entity in existing.into_values() {
+        commands.entity(entity).despawn();
+    }
+}
+
+fn draw_surfaces(surfaces: Query<(&GlobalTransform, &ReflectiveSurface)>, mut gizmos: Gizmos) {
+    for (transform, surface) in surfaces.iter() {
+        let center = transform.translation().truncate();
+        let half = transform.right().truncate() * surface.half_length;
+
+        gizmos.line_2d(center - half, center + half, SILVER);
+    }
+}
@@ -0,0 +1,225 @@
+//! Stylized, non-physical azimuths.
+//!
+//! Game audio sometimes wants spatialization that isn't true to life,
+//! such as squeezing the rear so anything behind the listener sounds
+//! clearly behind, or widening the front for a cinematic stage. An
+//! [`AzimuthRemap`] bends the listener-relative azimuth before it's
+//! sent to the HRTF nodes, and leaves elevation alone.
+//!
+//! The curve is defined on the right, from 0° in front to 180° behind,
+//! and mirrored on the left. Its control points are joined by straight
+//! lines, and 0° and 180° always map to themselves, so nothing swaps
+//! front and back or left and right. Points must increase in both
+//! azimuths, so no two directions are ever heard as one.
+
+use core::fmt;
+
+use bevy::prelude::*;
+
+use crate::spatial::{az_el_to_direction, direction_to_az_el};
+
+/// Maps heard azimuths to rendered ones.
+///
+/// The default, with no points, is the identity, and leaves
+/// directions exactly as they were.
+#[derive(Debug, Default, Clone, PartialEq, Resource)]
+pub struct AzimuthRemap {
+    /// `(heard, rendered)` azimuths in degrees, between
+    /// 0 and 180 and increasing in both.
+    points: Vec<Vec2>,
+}
+
+/// Why control points don't make an [`AzimuthRemap`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum AzimuthRemapError {
+    /// A point isn't strictly between 0° and 180°.
+    OutOfRange(Vec2),
+    /// A point doesn't increase in both azimuths from the one
+    /// before, which would fold two directions together.
+    NotIncreasing { previous: Vec2, point: Vec2 },
+}
+
+impl fmt::Display for AzimuthRemapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfRange(point) => write!(
+                f,
+                "azimuth remap point {point} must be strictly between 0° and 180°"
+            ),
+            Self::NotIncreasing { previous, point } => write!(
+                f,
+                "azimuth remap point {point} must increase in both azimuths from {previous}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AzimuthRemapError {}
+
+impl AzimuthRemap {
+    /// The presets, by name.
+    pub const PRESETS: [(&'static str, fn() -> Self); 3] = [
+        ("identity", Self::identity),
+        ("exaggerate rear", Self::exaggerate_rear),
+        ("widen front", Self::widen_front),
+    ];
+
+    /// A curve through `(heard, rendered)` control points in degrees.
+    pub fn new(points: impl IntoIterator<Item = Vec2>) -> Result<Self, AzimuthRemapError> {
+        let points: Vec<_> = points.into_iter().collect();
+
+        let mut previous = Vec2::ZERO;
+        for &point in &points {
+            let in_range = |azimuth: f32| azimuth > 0.0 && azimuth < 180.0;
+            if !in_range(point.x) || !in_range(point.y) {
+                return Err(AzimuthRemapError::OutOfRange(point));
+            }
+            if point.x <= previous.x || point.y <= previous.y {
+                return Err(AzimuthRemapError::NotIncreasing { previous, point });
+            }
+            previous = point;
+        }
+
+        // Points on the diagonal don't bend anything.
+        if points.iter().all(|point| point.x == point.y) {
+            return Ok(Self::identity());
+        }
+
+        Ok(Self { points })
+    }
+
+    /// Leave azimuths as they are.
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// Push the rear toward straight behind, so sources behind
+    /// the listener are more clearly behind.
+    pub fn exaggerate_rear() -> Self {
+        Self::new([Vec2::new(90.0, 100.0), Vec2::new(135.0, 165.0)]).unwrap()
+    }
+
+    /// Spread the front out, so sources ahead sound wider apart.
+    pub fn widen_front() -> Self {
+        Self::new([Vec2::new(30.0, 50.0), Vec2::new(60.0, 85.0)]).unwrap()
+    }
+
+    /// Whether this is the identity.
+    pub fn is_identity(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The rendered azimuth for a heard one, both in degrees.
+    pub fn remap_azimuth(&self, azimuth: f32) -> f32 {
+        if self.is_identity() {
+            return azimuth;
+        }
+
+        let heard = azimuth.abs().min(180.0);
+
+        let knots = core::iter::once(Vec2::ZERO)
+            .chain(self.points.iter().copied())
+            .chain(core::iter::once(Vec2::splat(180.0)));
+        let mut previous = Vec2::ZERO;
+        for knot in knots {
+            if heard <= knot.x {
+                let t = (heard - previous.x) / (knot.x - previous.x).max(f32::EPSILON);
+                return (previous.y + (knot.y - previous.y) * t).copysign(azimuth);
+            }
+            previous = knot;
+        }
+
+        azimuth
+    }
+
+    /// Remap a node-frame direction's azimuth, keeping
+    /// its elevation and length.
+    pub fn apply(&self, direction: Vec3) -> Vec3 {
+        if self.is_identity() {
+            return direction;
+        }
+
+        let (azimuth, elevation) = direction_to_az_el(direction);
+        az_el_to_direction(self.remap_azimuth(azimuth), elevation) * direction.length()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_non_monotonic_curves() {
+        let falling = AzimuthRemap::new([Vec2::new(60.0, 90.0), Vec2::new(120.0, 80.0)]);
+        assert_eq!(
+            falling,
+            Err(AzimuthRemapError::NotIncreasing {
+                previous: Vec2::new(60.0, 90.0),
+                point: Vec2::new(120.0, 80.0),
+            })
+        );
+
+        let backwards = AzimuthRemap::new([Vec2::new(90.0, 90.0), Vec2::new(45.0, 120.0)]);
+        assert!(matches!(
+            backwards,
+            Err(AzimuthRemapError::NotIncreasing { .. })
+        ));
+
+        let repeated = AzimuthRemap::new([Vec2::new(90.0, 100.0), Vec2::new(90.0, 120.0)]);
+        assert!(matches!(
+            repeated,
+            Err(AzimuthRemapError::NotIncreasing { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_points_out_of_range() {
+        for point in [
+            Vec2::new(0.0, 10.0),
+            Vec2::new(90.0, 180.0),
+            Vec2::new(-30.0, 30.0),
+            Vec2::new(f32::NAN, 90.0),
+        ] {
+            assert!(matches!(
+                AzimuthRemap::new([point]),
+                Err(AzimuthRemapError::OutOfRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn identity_is_bit_exact() {
+        let diagonal = AzimuthRemap::new([Vec2::splat(45.0), Vec2::splat(135.0)]).unwrap();
+        assert!(diagonal.is_identity());
+
+        for remap in [AzimuthRemap::identity(), diagonal] {
+            for step in -360..=360 {
+                let azimuth = step as f32 * 0.5 + 0.123;
+                assert_eq!(remap.remap_azimuth(azimuth).to_bits(), azimuth.to_bits());
+            }
+
+            for direction in [
+                Vec3::new(0.3, -0.2, -0.9),
+                Vec3::new(-5.0, 1.0, 2.0),
+                Vec3::X,
+                Vec3::ZERO,
+            ] {
+                assert_eq!(remap.apply(direction), direction);
+            }
+        }
+    }
+
+    #[test]
+    fn presets_keep_the_ends_and_the_side() {
+        for (name, preset) in AzimuthRemap::PRESETS {
+            let remap = preset();
+            assert_eq!(remap.remap_azimuth(0.0), 0.0, "{name}");
+            assert!((remap.remap_azimuth(180.0) - 180.0).abs() < 1e-4, "{name}");
+            assert_eq!(
+                remap.remap_azimuth(-60.0),
+                -remap.remap_azimuth(60.0),
+                "{name}"
+            );
+        }
+    }
+}
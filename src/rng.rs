@@ -0,0 +1,83 @@
+//! Reproducible randomness for the demo.
+//!
+//! Every randomized system draws from its own [`RngStream`], derived
+//! from the [`DemoRng`] seed and the stream's name. Adding a stream
+//! doesn't shift the sequences of the others, so a recording made
+//! with one seed replays the same way after new randomized features
+//! land.
+//!
+//! The seed is random unless `--seed=N` is passed, and is logged at
+//! startup so any run can be repeated. Saved timelines carry it too.
+
+use std::hash::{BuildHasher, Hasher, RandomState};
+
+use bevy::prelude::*;
+
+/// The seed every [`RngStream`] is derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Resource)]
+pub struct DemoRng {
+    pub seed: u64,
+}
+
+impl DemoRng {
+    /// Read `--seed=N` from the arguments, or pick a random seed.
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Self {
+        let mut seed = None;
+
+        for arg in args {
+            if let Some(value) = arg.strip_prefix("--seed=") {
+                match value.parse() {
+                    Ok(value) => seed = Some(value),
+                    Err(e) => warn!("invalid seed `{value}`: {e}"),
+                }
+            }
+        }
+
+        Self {
+            seed: seed.unwrap_or_else(|| RandomState::new().build_hasher().finish()),
+        }
+    }
+
+    /// The stream for the subsystem called `name`.
+    pub fn stream(&self, name: &str) -> RngStream {
+        // FNV-1a, so stream seeds are stable across Rust versions.
+        let name = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+
+        RngStream(splitmix64(&mut (self.seed ^ name)))
+    }
+}
+
+/// A SplitMix64 generator for one subsystem.
+#[derive(Debug, Clone)]
+pub struct RngStream(u64);
+
+impl RngStream {
+    pub fn next_u64(&mut self) -> u64 {
+        splitmix64(&mut self.0)
+    }
+
+    /// A value in `[0, 1)`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    /// A value in `[min, max)`.
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+pub fn log_seed(rng: Res<DemoRng>) {
+    info!("random seed: {0} (rerun with --seed={0})", rng.seed);
+}
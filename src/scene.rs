@@ -0,0 +1,87 @@
+//! Keeping effect chains intact across scenes.
+//!
+//! The HRTF nodes and their configurations are reflected, so they're
+//! saved in a [`DynamicScene`], but the [`EffectOf`] tying each node to
+//! its emitter isn't. A scene loaded back would have emitters with no
+//! effects and nodes that belong to nothing.
+//!
+//! Every effect is mirrored into a reflected [`SceneEffectOf`], which
+//! Bevy remaps to the new entities when the scene is instantiated.
+//! Once the instance is ready, [`SpatialAudioSceneHook`] rebuilds each
+//! emitter's chain from them in the original order.
+
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, scene::SceneInstanceReady};
+use bevy_seedling::prelude::*;
+
+pub struct SpatialAudioSceneHook;
+
+impl Plugin for SpatialAudioSceneHook {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SceneEffectOf>()
+            .add_systems(PostUpdate, mirror_effect_of)
+            .add_observer(rewire_scene_effects);
+    }
+}
+
+/// A reflected copy of an effect's [`EffectOf`], and
+/// its position in the emitter's chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct SceneEffectOf {
+    #[entities]
+    pub emitter: Entity,
+    pub index: usize,
+}
+
+fn mirror_effect_of(
+    emitters: Query<(Entity, &SampleEffects), Changed<SampleEffects>>,
+    mirrors: Query<&SceneEffectOf>,
+    mut commands: Commands,
+) {
+    for (emitter, effects) in emitters.iter() {
+        for (index, effect) in effects.iter().enumerate() {
+            let mirror = SceneEffectOf { emitter, index };
+            if mirrors.get(effect).ok() != Some(&mirror) {
+                commands.entity(effect).insert(mirror);
+            }
+        }
+    }
+}
+
+/// Reattach the effects in a newly instantiated scene to their emitters.
+fn rewire_scene_effects(
+    trigger: Trigger<SceneInstanceReady>,
+    spawner: Res<SceneSpawner>,
+    mirrors: Query<(&SceneEffectOf, Option<&EffectOf>)>,
+    mut commands: Commands,
+) {
+    // Ordered, so chains are rewired the same way every run.
+    let mut chains: BTreeMap<Entity, Vec<(usize, Entity)>> = BTreeMap::new();
+
+    for entity in spawner.iter_instance_entities(trigger.event().instance_id) {
+        let Ok((mirror, effect_of)) = mirrors.get(entity) else {
+            continue;
+        };
+
+        if effect_of.is_some_and(|effect_of| effect_of.0 == mirror.emitter) {
+            continue;
+        }
+
+        chains
+            .entry(mirror.emitter)
+            .or_default()
+            .push((mirror.index, entity));
+    }
+
+    // Effects are chained in the order they're attached.
+    for (emitter, mut chain) in chains {
+        chain.sort_unstable_by_key(|(index, _)| *index);
+
+        debug!("rewiring {} scene effects to {emitter}", chain.len());
+        for (_, effect) in chain {
+            commands.entity(effect).insert(EffectOf(emitter));
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Acoustic shadows cast by rounded obstacles.
+//!
+//! Run with `--scene=shadow` for a looping emitter behind a cluster of
+//! [`AcousticObstacle`] pillars. Move the listener with `W`, `A`, `S`
+//! and `D` and hear the direct path darken as a pillar comes between
+//! you: the deeper the line to the emitter cuts through a pillar, the
+//! lower the [`OcclusionFilterNode`](crate::occlusion::OcclusionFilterNode)
+//! cutoff.
+//!
+//! Each pillar's shadow, the region it hides from the emitter, is
+//! drawn as the two tangent lines leaving it. The line from the
+//! listener to the emitter turns red as it's shadowed.
+
+use bevy::{
+    color::palettes::css::{DARK_GRAY, GRAY, RED},
+    prelude::*,
+};
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::latency::LatencyCompensated;
+use crate::occlusion::{AcousticObstacle, Occlusion};
+use crate::{DemoAssets, DemoChain, ORBIT_VOLUME, spawn_default_chain};
+
+pub struct AcousticShadowPlugin;
+
+impl Plugin for AcousticShadowPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (move_listener, draw_shadows).run_if(resource_exists::<WasdListener>),
+        );
+    }
+}
+
+/// Present while `W`, `A`, `S` and `D` move the listeners.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct WasdListener {
+    /// Listener speed in units per second.
+    pub speed: f32,
+}
+
+/// How far past each pillar its shadow is drawn.
+const SHADOW_LENGTH: f32 = 800.0;
+
+/// Spawn an emitter behind a few pillars.
+pub fn spawn_shadow_scene(
+    assets: Res<DemoAssets>,
+    buses: Res<ReverbBuses>,
+    chain: Res<DemoChain>,
+    server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    let mut emitter = commands.spawn((
+        Mesh2d(assets.emitter_circle.clone()),
+        MeshMaterial2d(assets.emitter_material.clone()),
+        SamplePlayer::new(server.load("divine_comedy.ogg"))
+            .looping()
+            .with_volume(ORBIT_VOLUME),
+        Transform::from_xyz(0.0, 300.0, 0.0),
+        LatencyCompensated,
+    ));
+    spawn_default_chain(&mut emitter, &buses, *chain);
+
+    let material = materials.add(Color::from(GRAY));
+    for (position, radius) in [
+        (Vec2::new(0.0, 150.0), 50.0),
+        (Vec2::new(-180.0, 120.0), 35.0),
+        (Vec2::new(170.0, 60.0), 60.0),
+    ] {
+        commands.spawn((
+            Mesh2d(meshes.add(Circle::new(radius))),
+            MeshMaterial2d(material.clone()),
+            Transform::from_translation(position.extend(-1.0)),
+            AcousticObstacle { radius },
+        ));
+    }
+
+    commands.insert_resource(WasdListener { speed: 250.0 });
+}
+
+fn move_listener(
+    settings: Res<WasdListener>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut listeners: Query<&mut Transform, Or<(With<SpatialListener2D>, With<SpatialListener3D>)>>,
+    time: Res<Time>,
+) {
+    let mut direction = Vec2::ZERO;
+    for (key, step) in [
+        (KeyCode::KeyW, Vec2::Y),
+        (KeyCode::KeyS, Vec2::NEG_Y),
+        (KeyCode::KeyA, Vec2::NEG_X),
+        (KeyCode::KeyD, Vec2::X),
+    ] {
+        if input.pressed(key) {
+            direction += step;
+        }
+    }
+
+    let step = direction.normalize_or_zero() * settings.speed * time.delta_secs();
+    for mut transform in listeners.iter_mut() {
+        transform.translation += step.extend(0.0);
+    }
+}
+
+/// Draw each obstacle's shadow from every emitter, and each
+/// emitter's line to the listeners, red while occluded.
+fn draw_shadows(
+    emitters: Query<(&GlobalTransform, &Occlusion)>,
+    obstacles: Query<(&GlobalTransform, &AcousticObstacle)>,
+    listeners: Query<&GlobalTransform, Or<(With<SpatialListener2D>, With<SpatialListener3D>)>>,
+    mut gizmos: Gizmos,
+) {
+    for (emitter, occlusion) in emitters.iter() {
+        let source = emitter.translation().truncate();
+
+        for (obstacle, AcousticObstacle { radius }) in obstacles.iter() {
+            let center = obstacle.translation().truncate();
+            let offset = center - source;
+            let distance = offset.length();
+            if distance <= *radius {
+                continue;
+            }
+
+            // The tangents from the source bound the shadow.
+            let half_angle = (radius / distance).asin();
+            let tangent_length = (distance * distance - radius * radius).sqrt();
+            for side in [-half_angle, half_angle] {
+                let direction = Vec2::from_angle(side).rotate(offset / distance);
+                let tangent = source + direction * tangent_length;
+                gizmos.line_2d(tangent, tangent + direction * SHADOW_LENGTH, DARK_GRAY);
+            }
+        }
+
+        let color = Color::WHITE.mix(&Color::from(RED), occlusion.0);
+        for listener in listeners.iter() {
+            gizmos.line_2d(listener.translation().truncate(), source, color);
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! A lightweight interaural time and level difference spatializer.
+//!
+//! This node has no convolution at all, so it's the only per-emitter
+//! spatializer in the `lite` profile for low-end targets. It implements
+//! [`HrtfNode`], so the direction, bypass and command systems drive it
+//! exactly like the HRTF backends.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy_seedling::{SeedlingSystems, prelude::*};
+use firewheel::{
+    channel_config::{ChannelConfig, NonZeroChannelCount},
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+
+use crate::crossover::SimplePan;
+use crate::latency::tag_node_latency;
+use crate::limiter::ClipCounter;
+use crate::spatial::{
+    AppliedDirection, CustomDirectionSystem, HrtfNode, HrtfNodeConfig,
+    apply_spatial_audio_commands, update_hrtf_effects, warn_unhandled_commands,
+};
+use crate::state::AppState;
+use crate::tail::SilenceTail;
+use crate::validate::Spatializer;
+use crate::vr::update_vr_controller_hrtf;
+
+pub struct SimplePanPlugin;
+
+impl Plugin for SimplePanPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Last,
+            (
+                apply_spatial_audio_commands::<SimplePanNode>.before(warn_unhandled_commands),
+                (
+                    update_hrtf_effects::<SimplePanNode>,
+                    update_vr_controller_hrtf::<SimplePanNode>,
+                )
+                    .run_if(
+                        not(in_state(AppState::Menu))
+                            .and(not(resource_exists::<CustomDirectionSystem>)),
+                    ),
+            )
+                .chain()
+                .before(SeedlingSystems::Acquire),
+        )
+        .add_systems(Update, tag_node_latency::<SimplePanNode>)
+        .register_node::<SimplePanNode>()
+        .register_required_components::<SimplePanNode, Spatializer>()
+        .register_type::<SimplePanNode>();
+    }
+}
+
+/// Pans a downmixed signal with per-ear delay and gain.
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct SimplePanNode {
+    /// The direction vector pointing from the listener to the
+    /// emitter.
+    pub direction: Vec3,
+    /// Pass the downmixed input to both ears unprocessed.
+    pub bypass: bool,
+}
+
+/// Configuration for [`SimplePanNode`].
+#[derive(Debug, Clone, Component)]
+pub struct SimplePanConfig {
+    /// The number of input channels.
+    ///
+    /// Defaults to [`NonZeroChannelCount::STEREO`].
+    pub input_channels: NonZeroChannelCount,
+
+    /// A cell the processor publishes its applied direction to.
+    ///
+    /// Always `None` by default, since the lite profile skips debug
+    /// visualization.
+    pub applied_direction: Option<Arc<AppliedDirection>>,
+
+    /// The panner has no safety stage, so this stays at zero.
+    pub clip_counter: Arc<ClipCounter>,
+}
+
+impl Default for SimplePanConfig {
+    fn default() -> Self {
+        Self {
+            input_channels: NonZeroChannelCount::STEREO,
+            applied_direction: None,
+            clip_counter: Default::default(),
+        }
+    }
+}
+
+impl HrtfNodeConfig for SimplePanConfig {
+    fn applied_direction(&self) -> Option<&Arc<AppliedDirection>> {
+        self.applied_direction.as_ref()
+    }
+
+    fn clip_counter(&self) -> &Arc<ClipCounter> {
+        &self.clip_counter
+    }
+
+    fn latency_frames(&self) -> u32 {
+        0
+    }
+}
+
+impl HrtfNode for SimplePanNode {
+    fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    fn set_direction(&mut self, direction: Vec3) {
+        self.direction = direction;
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
+}
+
+struct SimplePanProcessor {
+    pan: SimplePan,
+    tail: SilenceTail,
+    sample_rate: f32,
+    bypass: bool,
+    applied_direction: Option<Arc<AppliedDirection>>,
+}
+
+impl AudioNode for SimplePanNode {
+    type Configuration = SimplePanConfig;
+
+    fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
+        AudioNodeInfo::new()
+            .debug_name("simple pan node")
+            .channel_config(ChannelConfig::new(config.input_channels.get(), 2))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let sample_rate = cx.stream_info.sample_rate.get() as f32;
+
+        // One millisecond covers any head's ITD.
+        let max_delay = (sample_rate / 1000.0) as usize;
+        let mut pan = SimplePan::new(max_delay);
+        pan.set_from_direction(self.direction, sample_rate, 0);
+
+        SimplePanProcessor {
+            pan,
+            tail: SilenceTail::new(max_delay + 1),
+            sample_rate,
+            bypass: self.bypass,
+            applied_direction: config.applied_direction.clone(),
+        }
+    }
+}
+
+impl AudioNodeProcessor for SimplePanProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        let mut latest_direction = None;
+        events.for_each_patch::<SimplePanNode>(|patch| match patch {
+            SimplePanNodePatch::Direction(direction) => latest_direction = Some(direction),
+            SimplePanNodePatch::Bypass(bypass) => self.bypass = bypass,
+        });
+
+        if let Some(direction) = latest_direction {
+            let direction = direction.normalize_or_zero();
+
+            if let Some(applied) = &self.applied_direction {
+                applied.store(direction);
+            }
+
+            self.pan.set_from_direction(direction, self.sample_rate, 0);
+        }
+
+        let silent = proc_info.in_silence_mask.all_channels_silent(inputs.len());
+        if !self.tail.process_block(silent, proc_info.frames) {
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        for frame in 0..proc_info.frames {
+            let mut downmixed = 0.0;
+            for channel in inputs {
+                downmixed += channel[frame];
+            }
+            downmixed /= inputs.len() as f32;
+
+            let (left, right) = if self.bypass {
+                (downmixed, downmixed)
+            } else {
+                self.pan.process(downmixed)
+            };
+
+            outputs[0][frame] = left;
+            outputs[1][frame] = right;
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
@@ -0,0 +1,233 @@
+//! Binaural decoding of ambisonic B-format input.
+//!
+//! The sound field is decoded to a set of virtual speakers, each of
+//! which is rendered through the HRTF for its direction, and the
+//! results are summed.
+
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use firewheel::{
+    channel_config::{ChannelConfig, ChannelCount},
+    diff::{Diff, Patch},
+    node::{AudioNode, AudioNodeInfo, AudioNodeProcessor, ProcBuffers, ProcessStatus},
+};
+use sofar::{reader::Filter, render::Renderer};
+
+use crate::blocks::FixedBlocks;
+use crate::fallback::FallibleProcessor;
+use crate::sofar_hrtf::{load_sofa, partition_len};
+use crate::spatial::InitFailure;
+use crate::tail::SilenceTail;
+
+/// Decodes first-order B-format (W, X, Y, Z) to binaural stereo.
+///
+/// Input channels use the FuMa convention: W carries a -3 dB gain,
+/// X points forward, Y to the left, and Z up.
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+pub struct SofarAmbisonicHrtfNode {
+    /// Rotation of the sound field about the vertical axis, in radians.
+    ///
+    /// Positive values rotate counter-clockwise when viewed from above.
+    pub yaw: f32,
+}
+
+/// The order of an ambisonic signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmbisonicOrder(pub u8);
+
+impl AmbisonicOrder {
+    pub const FIRST: Self = Self(1);
+
+    /// The number of channels in a full-sphere signal of this order.
+    pub fn channel_count(self) -> u32 {
+        let order = self.0 as u32 + 1;
+        order * order
+    }
+}
+
+/// Configuration for [`SofarAmbisonicHrtfNode`].
+#[derive(Debug, Clone, Component)]
+pub struct AmbisonicConfig {
+    /// The order of the incoming signal.
+    ///
+    /// Only first order is currently decoded. Higher-order
+    /// channels are accepted but ignored.
+    ///
+    /// Defaults to [`AmbisonicOrder::FIRST`].
+    pub order: AmbisonicOrder,
+
+    /// Flagged when the processor falls back to passthrough.
+    pub init_failure: Arc<InitFailure>,
+}
+
+impl Default for AmbisonicConfig {
+    fn default() -> Self {
+        Self {
+            order: AmbisonicOrder::FIRST,
+            init_failure: Default::default(),
+        }
+    }
+}
+
+/// Virtual speakers on the vertices of a cube, in SOFA coordinates.
+fn speaker_directions() -> [Vec3; 8] {
+    let mut directions = [Vec3::ZERO; 8];
+
+    for (i, direction) in directions.iter_mut().enumerate() {
+        let sign = |bit: usize| if i & bit == 0 { 1.0 } else { -1.0 };
+        *direction = Vec3::new(sign(1), sign(2), sign(4)).normalize();
+    }
+
+    directions
+}
+
+struct VirtualSpeaker {
+    direction: Vec3,
+    renderer: Renderer,
+    /// Hands the renderer whole partitions.
+    blocks: FixedBlocks,
+}
+
+struct AmbisonicProcessor {
+    yaw: f32,
+    speakers: Vec<VirtualSpeaker>,
+    feed: Vec<f32>,
+    left: Vec<f32>,
+    right: Vec<f32>,
+    tail: SilenceTail,
+}
+
+impl AudioNode for SofarAmbisonicHrtfNode {
+    type Configuration = AmbisonicConfig;
+
+    fn info(&self, config: &Self::Configuration) -> AudioNodeInfo {
+        // Past seventh order there are more channels than a node can
+        // have, and only the first order is decoded anyway.
+        let inputs = ChannelCount::new(config.order.channel_count()).unwrap_or(ChannelCount::MAX);
+
+        AudioNodeInfo::new()
+            .debug_name("ambisonic hrtf node")
+            .channel_config(ChannelConfig::new(inputs, ChannelCount::STEREO))
+    }
+
+    fn construct_processor(
+        &self,
+        config: &Self::Configuration,
+        cx: firewheel::node::ConstructProcessorContext,
+    ) -> impl firewheel::node::AudioNodeProcessor {
+        let sample_rate = cx.stream_info.sample_rate.get() as f32;
+        let sofa = load_sofa(sample_rate);
+
+        let filt_len = sofa.filter_len();
+        let mut filter = Filter::new(filt_len);
+
+        let buffer_size = cx.stream_info.max_block_frames.get() as usize;
+        let partition_len = partition_len(buffer_size, filt_len);
+
+        let speakers: Result<Vec<_>, _> = speaker_directions()
+            .into_iter()
+            .map(|direction| {
+                sofa.filter(direction.x, direction.y, direction.z, &mut filter);
+
+                let mut renderer = Renderer::builder(filt_len)
+                    .with_sample_rate(sample_rate)
+                    .with_partition_len(partition_len)
+                    .build()?;
+                renderer.set_filter(&filter)?;
+
+                Ok::<_, sofar::render::Error>(VirtualSpeaker {
+                    direction,
+                    renderer,
+                    blocks: FixedBlocks::new(partition_len, buffer_size),
+                })
+            })
+            .collect();
+
+        let processor = speakers.map(|speakers| AmbisonicProcessor {
+            yaw: self.yaw,
+            speakers,
+            feed: vec![0.0; buffer_size],
+            left: vec![0.0; buffer_size],
+            right: vec![0.0; buffer_size],
+            // The filters, the partition latency, and
+            // the latency `FixedBlocks` adds.
+            tail: SilenceTail::new(filt_len + 2 * partition_len),
+        });
+
+        FallibleProcessor::new(processor, &config.init_failure)
+    }
+}
+
+impl AudioNodeProcessor for AmbisonicProcessor {
+    fn process(
+        &mut self,
+        ProcBuffers {
+            inputs, outputs, ..
+        }: ProcBuffers,
+        proc_info: &firewheel::node::ProcInfo,
+        mut events: firewheel::event::NodeEventList,
+    ) -> ProcessStatus {
+        events.for_each_patch::<SofarAmbisonicHrtfNode>(|SofarAmbisonicHrtfNodePatch::Yaw(yaw)| {
+            self.yaw = yaw;
+        });
+
+        let silent = proc_info.in_silence_mask.all_channels_silent(inputs.len());
+        if !self.tail.process_block(silent, proc_info.frames) {
+            // The output has flushed, so the queues can start over unheard.
+            for speaker in &mut self.speakers {
+                speaker.blocks.reset();
+            }
+            return ProcessStatus::ClearAllOutputs;
+        }
+
+        let frames = proc_info.frames;
+        let [w, x, y, z] = [&inputs[0], &inputs[1], &inputs[2], &inputs[3]];
+
+        let (sin, cos) = self.yaw.sin_cos();
+        let speaker_count = self.speakers.len() as f32;
+
+        outputs[0][..frames].fill(0.0);
+        outputs[1][..frames].fill(0.0);
+
+        for speaker in &mut self.speakers {
+            // Projection decoding, with FuMa weights converted to N3D.
+            let d = speaker.direction;
+            for frame in 0..frames {
+                let rotated_x = x[frame] * cos - y[frame] * sin;
+                let rotated_y = x[frame] * sin + y[frame] * cos;
+
+                self.feed[frame] = (core::f32::consts::SQRT_2 * w[frame]
+                    + 3.0 * (d.x * rotated_x + d.y * rotated_y + d.z * z[frame]))
+                    / speaker_count;
+            }
+
+            // Blocks needn't be whole partitions, so they're queued
+            // up. A partition the renderer rejects is left silent.
+            let renderer = &mut speaker.renderer;
+            speaker.blocks.process(
+                &mut self.feed[..frames],
+                &mut self.left[..frames],
+                &mut self.right[..frames],
+                |input, left, right| {
+                    if renderer
+                        .process_block(input, &mut *left, &mut *right)
+                        .is_err()
+                    {
+                        left.fill(0.0);
+                        right.fill(0.0);
+                    }
+                },
+            );
+
+            for (output, rendered) in outputs.iter_mut().zip([&self.left, &self.right]) {
+                for (sample, rendered) in output[..frames].iter_mut().zip(rendered) {
+                    *sample += rendered;
+                }
+            }
+        }
+
+        ProcessStatus::outputs_not_silent()
+    }
+}
@@ -1,9 +1,17 @@
 //! Head-related transfer function (HRTF) node.
 
-use std::sync::{Arc, OnceLock};
+use std::{
+    fmt,
+    ops::RangeInclusive,
+    sync::{
+        Arc, OnceLock,
+        atomic::{AtomicU32, AtomicUsize, Ordering},
+    },
+};
 
 use bevy::prelude::*;
 use bevy_seedling::{SeedlingSystems, prelude::*};
+use crossbeam_queue::ArrayQueue;
 use firewheel::{
     channel_config::{ChannelConfig, NonZeroChannelCount},
     diff::{Diff, Patch},
@@ -14,25 +22,168 @@ use sofar::{
     render::Renderer,
 };
 
-pub struct SofarPlugin;
+use crate::blocks::FixedBlocks;
+use crate::conditioning::{HrirConditioner, HrirConditioning, condition_dataset};
+use crate::crossover::{LowBandSplit, LowFrequencyMode};
+use crate::custom::{HrtfProcessorFactory, MaybeCustom};
+use crate::dataset::{DatasetInfo, DatasetStats, HrtfDataset, update_dataset_info};
+use crate::downmix::{ChannelLayout, Downmix};
+use crate::fallback::{FallibleProcessor, HrtfInitFailed, report_init_failures};
+use crate::latency::{HrtfLatencyInfo, tag_node_latency};
+use crate::limiter::{ClipCounter, OutputSafety, collect_clipped_samples, report_clipped_samples};
+use crate::morph::{FilterMorph, MorphTarget, use_morph_target};
+use crate::prewarm::{HrtfFilterCache, HrtfPrewarmPlugin, lookup_filter};
+use crate::rate::DatasetRate;
+use crate::sofar_ambisonic::SofarAmbisonicHrtfNode;
+use crate::spatial::{
+    AppliedDirection, CustomDirectionSystem, FRONT, HrtfNode, HrtfNodeConfig, InitFailure,
+    SpatialAudioDebugSystems, apply_spatial_audio_commands, draw_direction_gizmos,
+    rotate_90_degrees, update_hrtf_effects, usable_direction, warn_unhandled_commands,
+};
+use crate::stale::{report_stale_directions, track_direction_age};
+use crate::state::AppState;
+use crate::tail::SilenceTail;
+use crate::telemetry::{Telemetry, TelemetryMessage, drain_telemetry, silence_non_finite};
+use crate::template::{ChainTemplate, register_chain_template};
+use crate::trail::{draw_direction_trails, record_direction_trail};
+use crate::validate::Spatializer;
+use crate::vr::update_vr_controller_hrtf;
+
+#[derive(Default)]
+pub struct SofarPlugin {
+    /// The chain built for [`Spatial`](crate::template::Spatial) emitters.
+    pub template: ChainTemplate,
+    /// How the dataset's filters are cleaned up when it loads.
+    pub conditioning: HrirConditioning,
+    /// A dataset for every node without one to morph toward.
+    pub morph_target: Option<MorphTarget>,
+}
 
 impl Plugin for SofarPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Last, update_hrtf_effects.before(SeedlingSystems::Acquire))
-            .register_node::<SofarHrtfNode>();
+        app.add_systems(
+            Last,
+            (
+                apply_spatial_audio_commands::<SofarHrtfNode>.before(warn_unhandled_commands),
+                (
+                    update_hrtf_effects::<SofarHrtfNode>,
+                    update_vr_controller_hrtf::<SofarHrtfNode>,
+                )
+                    .run_if(
+                        not(in_state(AppState::Menu))
+                            .and(not(resource_exists::<CustomDirectionSystem>)),
+                    ),
+            )
+                .chain()
+                .before(SeedlingSystems::Acquire),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    draw_direction_gizmos::<SofarHrtfNode>,
+                    record_direction_trail::<SofarHrtfNode>.before(draw_direction_trails),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                tag_node_latency::<SofarHrtfNode>,
+                update_dataset_info::<SofarHrtfNode>,
+                apply_config_changes,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                (
+                    collect_clipped_samples::<SofarHrtfNode>.before(report_clipped_samples),
+                    track_direction_age::<SofarHrtfNode>.before(report_stale_directions),
+                )
+                    .in_set(SpatialAudioDebugSystems),
+                report_init_failures::<SofarHrtfNode>,
+                drain_telemetry::<SofarHrtfNode>,
+            ),
+        )
+        .add_event::<HrtfInitFailed>()
+        .add_observer(rebuild_renderer)
+        .init_resource::<DatasetInfo>()
+        .register_node::<SofarHrtfNode>()
+        .register_required_components::<SofarHrtfNode, Spatializer>()
+        .register_type::<SofarHrtfNode>()
+        .register_type::<HrtfConfig>()
+        .register_node::<SofarAmbisonicHrtfNode>()
+        .register_required_components::<SofarAmbisonicHrtfNode, Spatializer>()
+        .register_type::<SofarAmbisonicHrtfNode>();
+
+        // The partition length, and so the latency, depends on the
+        // stream, so it's reported per node by `tag_node_latency`.
+        app.world_mut().get_resource_or_init::<HrtfLatencyInfo>();
+
+        register_chain_template::<SofarHrtfNode>(app, self.template.clone());
+
+        if let Some(target) = &self.morph_target {
+            use_morph_target(app, target.clone());
+        }
+
+        // The dataset may load as soon as `Startup`, so the
+        // conditioning has to be in place before then.
+        self.conditioning.install();
+
+        app.add_plugins(HrtfPrewarmPlugin);
     }
 }
 
+/// The range of partition lengths a renderer is built with.
+pub const PARTITION_LEN_RANGE: RangeInclusive<usize> = 16..=1024;
+
+/// The partition length assumed before a processor is built.
+const DEFAULT_PARTITION_LEN: usize = 64;
+
+/// Choose a renderer partition length for a stream.
+///
+/// This is the stream's maximum block size rounded up to a power of
+/// two, clamped to [`PARTITION_LEN_RANGE`] and to the filter length.
+pub fn partition_len(max_block_frames: usize, filt_len: usize) -> usize {
+    let len = max_block_frames
+        .next_power_of_two()
+        .clamp(*PARTITION_LEN_RANGE.start(), *PARTITION_LEN_RANGE.end());
+
+    // The renderer can't use partitions longer than its filters.
+    len.min(1 << filt_len.max(1).ilog2())
+}
+
+/// Check a requested partition length, clamping it to
+/// [`PARTITION_LEN_RANGE`].
+///
+/// Lengths that aren't a power of two, including zero, are rejected.
+pub fn checked_partition_len(len: usize) -> Option<usize> {
+    len.is_power_of_two()
+        .then(|| len.clamp(*PARTITION_LEN_RANGE.start(), *PARTITION_LEN_RANGE.end()))
+}
+
 /// Head-related transfer function (HRTF) node.
-#[derive(Debug, Default, Clone, Component, Diff, Patch)]
+///
+/// Spawning the node alone inserts a default [`HrtfConfig`].
+#[derive(Debug, Default, Clone, Component, Diff, Patch, Reflect)]
+#[reflect(Component, Default)]
+#[require(Transform, HrtfConfig)]
 pub struct SofarHrtfNode {
     /// The direction vector pointing from the listener to the
     /// emitter.
     pub direction: Vec3,
+    /// Pass the downmixed input to both ears unprocessed.
+    pub bypass: bool,
+    /// How far to morph toward the configuration's
+    /// [`morph_target`](HrtfConfig::morph_target), from 0 to 1.
+    ///
+    /// Has no effect without a target.
+    pub morph: f32,
 }
 
 /// Configuration for [`SofarHrtfNode`].
-#[derive(Debug, Clone, Component)]
+///
+/// Only the tuning fields are reflected. The shared cells are
+/// recreated on load, and the channel count falls back to stereo.
+#[derive(Debug, Clone, Component, Reflect)]
+#[reflect(Component, Default)]
 pub struct HrtfConfig {
     /// The number of input channels.
     ///
@@ -40,21 +191,184 @@ pub struct HrtfConfig {
     /// before spatialization is applied.
     ///
     /// Defaults to [`NonZeroChannelCount::STEREO`].
+    #[reflect(ignore)]
     pub input_channels: NonZeroChannelCount,
+
+    /// How the input channels are weighted in the downmix.
+    ///
+    /// An invalid layout is logged and the inputs are averaged instead.
+    ///
+    /// Defaults to [`ChannelLayout::Auto`].
+    #[reflect(ignore)]
+    pub channel_layout: ChannelLayout,
+
+    /// How frequencies below `crossover_frequency` are rendered.
+    ///
+    /// Defaults to [`LowFrequencyMode::Full`].
+    pub low_freq_mode: LowFrequencyMode,
+
+    /// The crossover frequency in Hz when `low_freq_mode` is
+    /// [`LowFrequencyMode::SimplePan`].
+    ///
+    /// Clamped to [`CROSSOVER_RANGE`](crate::crossover::CROSSOVER_RANGE). Defaults to 400 Hz.
+    pub crossover_frequency: f32,
+
+    /// A cell the processor publishes its applied direction to.
+    ///
+    /// Defaults to `Some` in debug builds and `None` in release builds.
+    #[reflect(ignore)]
+    pub applied_direction: Option<Arc<AppliedDirection>>,
+
+    /// The safety stage applied to the output.
+    ///
+    /// Defaults to [`OutputSafety::Off`] on native targets and a
+    /// soft clip on the web, where there is no system limiter.
+    pub output_safety: OutputSafety,
+
+    /// The renderer's partition length in frames.
+    ///
+    /// Longer partitions need fewer FFTs per block, so they're cheaper,
+    /// but they add latency once they outgrow the stream's blocks.
+    /// Queueing whole partitions adds one frame short of another
+    /// partition of latency, see [`blocks`](crate::blocks).
+    /// `None` matches the stream's block size with [`partition_len`].
+    /// Other lengths are clamped to [`PARTITION_LEN_RANGE`], and
+    /// ones that aren't a power of two are ignored, see
+    /// [`checked_partition_len`]. Defaults to `None`.
+    pub partition_len: Option<usize>,
+
+    /// The partition length the processor was built with, or zero.
+    #[reflect(ignore)]
+    pub applied_partition_len: Arc<AtomicUsize>,
+
+    /// Renderers built for a new partition length, on their way to
+    /// the processor.
+    ///
+    /// Filled by [`rebuild_renderer`] when `partition_len` changes
+    /// on a live node.
+    #[reflect(ignore)]
+    pub partition_handoff: Arc<PartitionHandoff>,
+
+    /// Counts output samples that crossed the safety ceiling.
+    #[reflect(ignore)]
+    pub clip_counter: Arc<ClipCounter>,
+
+    /// Flagged when the processor falls back to passthrough.
+    #[reflect(ignore)]
+    pub init_failure: Arc<InitFailure>,
+
+    /// Carries the processor's reports to the ECS.
+    #[reflect(ignore)]
+    pub telemetry: Arc<Telemetry>,
+
+    /// A second dataset the node's `morph` blends toward.
+    ///
+    /// Defaults to `None`.
+    #[reflect(ignore)]
+    pub morph_target: Option<MorphTarget>,
+
+    /// Builds the node's processor in place of the built-in renderer.
+    ///
+    /// Filled in from [`SpatialAudioPlugin::with_custom_hrtf_processor`]
+    /// when `None`. Defaults to `None`.
+    ///
+    /// [`SpatialAudioPlugin::with_custom_hrtf_processor`]: crate::spatial::SpatialAudioPlugin::with_custom_hrtf_processor
+    #[reflect(ignore)]
+    pub custom_processor: Option<HrtfProcessorFactory>,
 }
 
 impl Default for HrtfConfig {
     fn default() -> Self {
         Self {
             input_channels: NonZeroChannelCount::STEREO,
+            channel_layout: ChannelLayout::Auto,
+            low_freq_mode: LowFrequencyMode::Full,
+            crossover_frequency: 400.0,
+            applied_direction: cfg!(debug_assertions).then(Default::default),
+            output_safety: OutputSafety::default(),
+            partition_len: None,
+            applied_partition_len: Default::default(),
+            partition_handoff: Default::default(),
+            clip_counter: Default::default(),
+            init_failure: Default::default(),
+            telemetry: Default::default(),
+            morph_target: None,
+            custom_processor: None,
         }
     }
 }
 
+impl HrtfNodeConfig for HrtfConfig {
+    fn applied_direction(&self) -> Option<&Arc<AppliedDirection>> {
+        self.applied_direction.as_ref()
+    }
+
+    fn clip_counter(&self) -> &Arc<ClipCounter> {
+        &self.clip_counter
+    }
+
+    fn latency_frames(&self) -> u32 {
+        let len = match self.applied_partition_len.load(Ordering::Relaxed) {
+            0 => self
+                .partition_len
+                .and_then(checked_partition_len)
+                .unwrap_or(DEFAULT_PARTITION_LEN),
+            len => len,
+        };
+
+        // The renderer's partition plus the queueing in `FixedBlocks`.
+        (len + FixedBlocks::latency(len)) as u32
+    }
+
+    fn init_failure(&self) -> Option<&Arc<InitFailure>> {
+        Some(&self.init_failure)
+    }
+
+    fn telemetry(&self) -> Option<&Arc<Telemetry>> {
+        Some(&self.telemetry)
+    }
+}
+
 struct HrtfProcessor {
     sofa: Arc<Sofar>,
+    cache: Option<Arc<HrtfFilterCache>>,
     renderer: Renderer,
     filter: Filter,
+    conditioner: HrirConditioner,
+    /// The conditioned `filter` the renderer uses.
+    rendered: Filter,
+    /// The last usable direction, rotated into the dataset's frame.
+    direction: Vec3,
+    morph: f32,
+    morph_state: Option<FilterMorph>,
+    applied_partition_len: Arc<AtomicUsize>,
+    partition_handoff: Arc<PartitionHandoff>,
+    split: Option<LowBandSplit>,
+    applied_direction: Option<Arc<AppliedDirection>>,
+    output_safety: OutputSafety,
+    clip_counter: Arc<ClipCounter>,
+    telemetry: Arc<Telemetry>,
+    downmix: Downmix,
+    bypass: bool,
+    tail: SilenceTail,
+    /// Whether the tail has passed since the input last went silent.
+    flushed: bool,
+    /// Hands the renderer whole partitions.
+    blocks: FixedBlocks,
+}
+
+impl HrtfNode for SofarHrtfNode {
+    fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    fn set_direction(&mut self, direction: Vec3) {
+        self.direction = direction;
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        self.bypass = bypass;
+    }
 }
 
 impl AudioNode for SofarHrtfNode {
@@ -68,49 +382,500 @@ impl AudioNode for SofarHrtfNode {
 
     fn construct_processor(
         &self,
-        _config: &Self::Configuration,
+        config: &Self::Configuration,
         cx: firewheel::node::ConstructProcessorContext,
     ) -> impl firewheel::node::AudioNodeProcessor {
-        let sample_rate = cx.stream_info.sample_rate.get() as f32;
-
-        static SOFAR: OnceLock<Arc<Sofar>> = OnceLock::new();
-        const SOFAR_BYES: &[u8] = include_bytes!("../assets/sadie_h12.sofa");
+        if let Some(factory) = &config.custom_processor {
+            return MaybeCustom::Custom(factory.construct(config, cx.stream_info));
+        }
 
-        let sofa = SOFAR
-            .get_or_init(|| {
-                Arc::new(
-                    OpenOptions::new()
-                        .sample_rate(sample_rate)
-                        .open_data(SOFAR_BYES)
-                        .unwrap(),
-                )
-            })
-            .clone();
+        let sample_rate = cx.stream_info.sample_rate.get() as f32;
+        let sofa = load_sofa(sample_rate);
+        let cache = HrtfFilterCache::get();
+        let conditioner = sofa_conditioner();
 
         let filt_len = sofa.filter_len();
         let mut filter = Filter::new(filt_len);
-        sofa.filter(0.0, 1.0, 0.0, &mut filter);
+        // Start from the node's direction, so a rebuilt
+        // processor doesn't snap back to the front.
+        let direction = rotate_90_degrees(
+            usable_direction(self.direction).unwrap_or(FRONT),
+            Vec3::NEG_Z,
+        );
+
+        let mut morph_state = config
+            .morph_target
+            .as_ref()
+            .and_then(|target| FilterMorph::new(target, &sofa, sample_rate));
+        match &mut morph_state {
+            Some(morph) => {
+                lookup_filter(&sofa, cache.as_deref(), direction, morph.source_mut());
+                morph.blend(direction, self.morph, &mut filter);
+            }
+            None => lookup_filter(&sofa, cache.as_deref(), direction, &mut filter),
+        }
 
-        let renderer = Renderer::builder(filt_len)
+        let render_len = conditioner.filter_len();
+        let mut rendered = Filter::new(render_len);
+        conditioner.apply(&filter, &mut rendered);
+
+        let max_block_frames = cx.stream_info.max_block_frames.get() as usize;
+        let partition_len = config
+            .partition_len
+            .and_then(checked_partition_len)
+            .unwrap_or_else(|| partition_len(max_block_frames, render_len));
+        config
+            .applied_partition_len
+            .store(partition_len, Ordering::Relaxed);
+        config
+            .partition_handoff
+            .set_stream(sample_rate, max_block_frames);
+
+        // Only a sample rate sofar can't render at fails
+        // here, and the node passes audio through instead.
+        let renderer = Renderer::builder(render_len)
             .with_sample_rate(sample_rate)
-            .with_partition_len(64)
+            .with_partition_len(partition_len)
             .build()
-            .unwrap();
+            .and_then(|mut renderer| renderer.set_filter(&rendered).map(|()| renderer));
+
+        let mut split = LowBandSplit::new(
+            config.low_freq_mode,
+            config.crossover_frequency,
+            sample_rate,
+            filt_len,
+            // Room for any partition length the node may switch to.
+            max_block_frames.max(*PARTITION_LEN_RANGE.end()),
+        );
+        if let Some(split) = &mut split {
+            split.pan.set_from_hrir(&rendered.left, &rendered.right);
+        }
 
-        HrtfProcessor {
+        let processor = renderer.map(|renderer| HrtfProcessor {
             sofa,
+            cache,
             renderer,
             filter,
+            conditioner,
+            rendered,
+            direction,
+            morph: self.morph,
+            morph_state,
+            applied_partition_len: config.applied_partition_len.clone(),
+            partition_handoff: config.partition_handoff.clone(),
+            split,
+            applied_direction: config.applied_direction.clone(),
+            output_safety: config.output_safety,
+            clip_counter: config.clip_counter.clone(),
+            telemetry: config.telemetry.clone(),
+            downmix: Downmix::new_or_average(&config.channel_layout, config.input_channels),
+            bypass: self.bypass,
+            tail: tail(render_len, partition_len),
+            flushed: true,
+            blocks: FixedBlocks::new(partition_len, max_block_frames),
+        });
+
+        MaybeCustom::Builtin(FallibleProcessor::new(processor, &config.init_failure))
+    }
+}
+
+/// The filter, the partition latency, and the
+/// latency [`FixedBlocks`] adds.
+fn tail(filt_len: usize, partition_len: usize) -> SilenceTail {
+    SilenceTail::new(filt_len + 2 * partition_len)
+}
+
+/// The embedded SOFA dataset.
+pub const SOFA_DATA: &[u8] = include_bytes!("../assets/sadie_h12.sofa");
+
+/// The sample rate [`SOFA_DATA`] was measured at.
+pub const SOFA_SAMPLE_RATE: f32 = 48_000.0;
+
+/// Load the embedded SOFA dataset, resampled to `sample_rate`.
+///
+/// If `sample_rate` is too far from [`SOFA_SAMPLE_RATE`], or resampling
+/// fails, the dataset is loaded at its native rate instead.
+///
+/// The dataset is only parsed once and shared between all nodes.
+pub fn load_sofa(sample_rate: f32) -> Arc<Sofar> {
+    static SOFAR: OnceLock<Arc<Sofar>> = OnceLock::new();
+
+    SOFAR
+        .get_or_init(|| {
+            let rate = DatasetRate::new(SOFA_SAMPLE_RATE, sample_rate);
+            rate.log("sadie_h12.sofa");
+
+            let resampled = rate
+                .resample()
+                .then(|| {
+                    OpenOptions::new()
+                        .sample_rate(sample_rate)
+                        .open_data(SOFA_DATA)
+                        .inspect_err(|e| {
+                            warn!(
+                                "failed to resample sadie_h12.sofa to {sample_rate} Hz, \
+                                 loading at the native rate: {e:?}"
+                            )
+                        })
+                        .ok()
+                })
+                .flatten();
+
+            let sofa = resampled.unwrap_or_else(|| {
+                OpenOptions::new()
+                    .sample_rate(SOFA_SAMPLE_RATE)
+                    .open_data(SOFA_DATA)
+                    .unwrap()
+            });
+
+            let conditioning =
+                condition_dataset(&sofa, "sadie_h12.sofa", HrirConditioning::installed());
+            SOFA_CONDITIONER.get_or_init(|| conditioning.conditioner);
+
+            let filter_len = sofa.filter_len();
+            SOFA_STATS.get_or_init(|| DatasetStats {
+                dataset: "sadie_h12.sofa",
+                filter_len,
+                // sofar doesn't expose the measurement count.
+                measurements: None,
+                // Each node's filter, plus its renderer's filter spectra
+                // and delay lines for both ears, with a second set of
+                // spectra to crossfade to.
+                bytes_per_node: 2 * filter_len * size_of::<f32>()
+                    + 2 * 3 * 2 * filter_len * 2 * size_of::<f32>(),
+                // The decoded dataset is about the size of the file.
+                shared_bytes: SOFA_DATA.len(),
+                nodes: 0,
+                hrir: Some(conditioning.analysis),
+                conditioned_hrir: conditioning.conditioned,
+            });
+
+            Arc::new(sofa)
+        })
+        .clone()
+}
+
+/// The embedded dataset's cost, once it's loaded.
+static SOFA_STATS: OnceLock<DatasetStats> = OnceLock::new();
+
+/// The embedded dataset's conditioning, once it's loaded.
+static SOFA_CONDITIONER: OnceLock<HrirConditioner> = OnceLock::new();
+
+/// How the embedded dataset's filters are conditioned.
+///
+/// The dataset must be loaded with [`load_sofa`] first.
+fn sofa_conditioner() -> HrirConditioner {
+    *SOFA_CONDITIONER
+        .get()
+        .expect("the conditioner is set when the dataset loads")
+}
+
+impl HrtfDataset for SofarHrtfNode {
+    fn dataset_stats() -> Option<DatasetStats> {
+        SOFA_STATS.get().cloned()
+    }
+}
+
+/// Render the response of the HRTF for `direction` to a unit impulse.
+///
+/// This builds its own renderer, so it never disturbs live nodes.
+pub fn impulse_response(direction: Vec3, sample_rate: f32, left: &mut [f32], right: &mut [f32]) {
+    let sofa = load_sofa(sample_rate);
+
+    let filt_len = sofa.filter_len();
+    let mut filter = Filter::new(filt_len);
+
+    // rotate the vector by 90 degrees about the head
+    let direction = usable_direction(direction).unwrap_or(FRONT);
+    let direction = rotate_90_degrees(direction, Vec3::NEG_Z);
+    sofa.filter(direction.x, direction.y, direction.z, &mut filter);
+
+    let conditioner = sofa_conditioner();
+    let mut rendered = Filter::new(conditioner.filter_len());
+    conditioner.apply(&filter, &mut rendered);
+
+    let mut renderer = Renderer::builder(rendered.left.len())
+        .with_sample_rate(sample_rate)
+        .with_partition_len(64)
+        .build()
+        .unwrap();
+    renderer.set_filter(&rendered).unwrap();
+
+    let mut impulse = vec![0.0; left.len()];
+    if let Some(first) = impulse.first_mut() {
+        *first = 1.0;
+    }
+
+    renderer.process_block(&impulse, left, right).unwrap();
+}
+
+impl HrtfProcessor {
+    /// Apply the filter for the current direction and morph,
+    /// looking the node's own dataset up again if `lookup` is set.
+    fn update_filter(&mut self, lookup: bool) {
+        let direction = self.direction;
+
+        match &mut self.morph_state {
+            Some(morph) => {
+                if lookup {
+                    lookup_filter(
+                        &self.sofa,
+                        self.cache.as_deref(),
+                        direction,
+                        morph.source_mut(),
+                    );
+                }
+                morph.blend(direction, self.morph, &mut self.filter);
+            }
+            // Without a target, only a new direction changes the filter.
+            None if lookup => {
+                lookup_filter(
+                    &self.sofa,
+                    self.cache.as_deref(),
+                    direction,
+                    &mut self.filter,
+                );
+            }
+            None => return,
+        }
+
+        self.conditioner.apply(&self.filter, &mut self.rendered);
+        // The renderer keeps its previous filter if it rejects this one.
+        if self.renderer.set_filter(&self.rendered).is_err() {
+            self.telemetry.send(TelemetryMessage::FilterLookupFailed);
+            return;
+        }
+
+        if let Some(split) = &mut self.split {
+            split
+                .pan
+                .set_from_hrir(&self.rendered.left, &self.rendered.right);
+        }
+    }
+
+    /// Swap in a renderer built for a new partition length, if one
+    /// has been handed over.
+    ///
+    /// The dataset and current filter are kept, so only the
+    /// convolution state starts over. The old renderer is handed
+    /// back to be dropped off the audio thread.
+    fn apply_partition_swap(&mut self) {
+        // Wait until the last renderer we retired has been collected.
+        if self.partition_handoff.retired.is_full() {
+            return;
+        }
+        let Some(mut swap) = self.partition_handoff.incoming.pop() else {
+            return;
+        };
+
+        if swap.renderer.set_filter(&self.rendered).is_err() {
+            self.telemetry.send(TelemetryMessage::FilterLookupFailed);
+            // Dropped on the main thread with the rest of the swap.
+            let _ = self.partition_handoff.retired.push(swap);
+            return;
+        }
+
+        std::mem::swap(&mut self.renderer, &mut swap.renderer);
+        std::mem::swap(&mut self.blocks, &mut swap.blocks);
+        self.applied_partition_len
+            .store(swap.partition_len, Ordering::Relaxed);
+        self.tail = tail(self.rendered.left.len(), swap.partition_len);
+
+        let _ = self.partition_handoff.retired.push(swap);
+    }
+}
+
+/// A renderer built for a new partition length.
+struct PartitionSwap {
+    renderer: Renderer,
+    blocks: FixedBlocks,
+    partition_len: usize,
+}
+
+/// Hands renderers between the main thread and a [`SofarHrtfNode`]'s
+/// processor without locking or allocating on the audio thread.
+///
+/// The main thread builds each renderer and drops the ones the
+/// processor retires, so the processor only ever swaps.
+pub struct PartitionHandoff {
+    incoming: ArrayQueue<PartitionSwap>,
+    retired: ArrayQueue<PartitionSwap>,
+    /// The stream the processor was built for, or zero.
+    sample_rate: AtomicU32,
+    max_block_frames: AtomicUsize,
+}
+
+impl Default for PartitionHandoff {
+    fn default() -> Self {
+        Self {
+            incoming: ArrayQueue::new(1),
+            retired: ArrayQueue::new(1),
+            sample_rate: AtomicU32::new(0),
+            max_block_frames: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl fmt::Debug for PartitionHandoff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartitionHandoff")
+            .field("pending", &self.incoming.len())
+            .field("retired", &self.retired.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartitionHandoff {
+    fn set_stream(&self, sample_rate: f32, max_block_frames: usize) {
+        self.sample_rate
+            .store(sample_rate.to_bits(), Ordering::Relaxed);
+        self.max_block_frames
+            .store(max_block_frames, Ordering::Relaxed);
+    }
+
+    /// Build a renderer for `partition_len` and hand it to the processor,
+    /// replacing any the processor hasn't picked up yet.
+    ///
+    /// Returns `false` if the processor hasn't been built, or the
+    /// renderer couldn't be.
+    fn request(&self, partition_len: usize) -> bool {
+        self.collect_retired();
+
+        let sample_rate = f32::from_bits(self.sample_rate.load(Ordering::Relaxed));
+        let max_block_frames = self.max_block_frames.load(Ordering::Relaxed);
+        if max_block_frames == 0 {
+            return false;
+        }
+
+        let renderer = Renderer::builder(sofa_conditioner().filter_len())
+            .with_sample_rate(sample_rate)
+            .with_partition_len(partition_len)
+            .build();
+        let Ok(renderer) = renderer else {
+            return false;
+        };
+
+        self.incoming.force_push(PartitionSwap {
+            renderer,
+            blocks: FixedBlocks::new(partition_len, max_block_frames),
+            partition_len,
+        });
+        true
+    }
+
+    /// Drop the renderers the processor has retired.
+    fn collect_retired(&self) {
+        while self.retired.pop().is_some() {}
+    }
+}
+
+/// Triggered when a live [`SofarHrtfNode`]'s configuration changes.
+#[derive(Debug, Clone, Copy, Event)]
+pub struct HrtfConfigChanged {
+    pub entity: Entity,
+    /// Whether the renderer needs rebuilding for a new partition length.
+    pub partition_len_changed: bool,
+}
+
+/// Trigger [`HrtfConfigChanged`] for configuration changes on live nodes.
+///
+/// Observers don't fire on in-place mutation, so changes are
+/// found here and handed to [`rebuild_renderer`].
+pub fn apply_config_changes(
+    configs: Query<(Entity, Ref<HrtfConfig>), With<SofarHrtfNode>>,
+    mut commands: Commands,
+) {
+    for (entity, config) in configs.iter() {
+        if !config.is_changed() || config.is_added() {
+            continue;
         }
+
+        let requested = config.partition_len.and_then(checked_partition_len);
+        if let (Some(partition_len), None) = (config.partition_len, requested) {
+            warn!(
+                "ignoring partition length {partition_len} for HRTF node {entity}, \
+                 it must be a power of two"
+            );
+        }
+
+        let applied = config.applied_partition_len.load(Ordering::Relaxed);
+        let partition_len_changed =
+            applied != 0 && requested.is_some_and(|partition_len| partition_len != applied);
+
+        commands.trigger(HrtfConfigChanged {
+            entity,
+            partition_len_changed,
+        });
     }
 }
 
-fn rotate_90_degrees(vector: Vec3, axis: Vec3) -> Vec3 {
-    let cross_product = axis.cross(vector);
-    let dot_product = axis.dot(vector);
+/// Send a node's processor a renderer for its new partition length.
+///
+/// Only `partition_len` can change without rebuilding the node;
+/// the renderer is swapped in place, keeping the dataset and filter.
+fn rebuild_renderer(trigger: Trigger<HrtfConfigChanged>, configs: Query<&HrtfConfig>) {
+    let HrtfConfigChanged {
+        entity,
+        partition_len_changed,
+    } = *trigger.event();
+
+    if !partition_len_changed {
+        return;
+    }
+    let Ok(config) = configs.get(entity) else {
+        return;
+    };
+    let Some(partition_len) = config.partition_len.and_then(checked_partition_len) else {
+        return;
+    };
+
+    if !config.partition_handoff.request(partition_len) {
+        warn!(
+            "failed to build a renderer with partition length {partition_len} \
+             for HRTF node {entity}"
+        );
+    }
+}
+
+/// Render one partition, splitting off the low band if there's a split.
+///
+/// If the renderer rejects the partition, it's reported
+/// through `telemetry` and rendered as silence.
+fn render_block(
+    renderer: &mut Renderer,
+    split: &mut Option<LowBandSplit>,
+    telemetry: &Telemetry,
+    input: &mut [f32],
+    left: &mut [f32],
+    right: &mut [f32],
+) {
+    // Only the high band is convolved when splitting.
+    if let Some(split) = split {
+        split.low.clear();
+        for sample in input.iter_mut() {
+            let (low, high) = split.crossover.split(*sample);
+            split.low.push(low);
+            *sample = high;
+        }
+    }
 
-    // Rodrigues formula for 90 degrees
-    cross_product + axis * dot_product
+    if renderer
+        .process_block(input, &mut *left, &mut *right)
+        .is_err()
+    {
+        telemetry.send(TelemetryMessage::FilterLookupFailed);
+        left.fill(0.0);
+        right.fill(0.0);
+        return;
+    }
+
+    if let Some(split) = split {
+        for ((low, left), right) in split.low.iter().zip(left.iter_mut()).zip(right.iter_mut()) {
+            let (l, r) = split.pan.process(*low);
+            *left += l;
+            *right += r;
+        }
+    }
 }
 
 impl AudioNodeProcessor for HrtfProcessor {
@@ -124,84 +889,112 @@ impl AudioNodeProcessor for HrtfProcessor {
         proc_info: &firewheel::node::ProcInfo,
         mut events: firewheel::event::NodeEventList,
     ) -> ProcessStatus {
-        events.for_each_patch::<SofarHrtfNode>(|SofarHrtfNodePatch::Direction(direction)| {
-            let direction = direction.normalize_or_zero();
+        // Only the latest direction matters, so superseded
+        // patches are dropped rather than each triggering a lookup.
+        let mut latest_direction = None;
+        let mut latest_morph = None;
+        events.for_each_patch::<SofarHrtfNode>(|patch| match patch {
+            SofarHrtfNodePatch::Direction(direction) => latest_direction = Some(direction),
+            SofarHrtfNodePatch::Bypass(bypass) => self.bypass = bypass,
+            SofarHrtfNodePatch::Morph(morph) => latest_morph = Some(morph),
+        });
 
-            // rotate the vector by 90 degrees about the head
-            let direction = rotate_90_degrees(direction, Vec3::NEG_Z);
+        self.apply_partition_swap();
 
-            self.sofa
-                .filter(direction.x, direction.y, direction.z, &mut self.filter);
-            self.renderer.set_filter(&self.filter).unwrap();
-        });
+        // An unusable direction keeps the current filter.
+        let direction = latest_direction.and_then(usable_direction);
+        if let Some(direction) = direction {
+            if let Some(applied) = &self.applied_direction {
+                applied.store(direction);
+            }
 
-        if proc_info.in_silence_mask.all_channels_silent(inputs.len()) {
-            return ProcessStatus::ClearAllOutputs;
+            // rotate the vector by 90 degrees about the head
+            self.direction = rotate_90_degrees(direction, Vec3::NEG_Z);
+        }
+        if let Some(morph) = latest_morph {
+            self.morph = morph;
         }
 
-        let input = &mut scratch_buffers[0][..proc_info.frames];
+        if direction.is_some() || latest_morph.is_some() {
+            self.update_filter(direction.is_some());
+        }
 
-        for frame in 0..proc_info.frames {
-            let mut downmixed = 0.0;
-            for channel in inputs {
-                downmixed += channel[frame];
+        let silent = proc_info.in_silence_mask.all_channels_silent(inputs.len());
+        if !self.tail.process_block(silent, proc_info.frames) {
+            if !self.flushed {
+                self.flushed = true;
+                self.telemetry.send(TelemetryMessage::TailFinished);
             }
-            downmixed /= inputs.len() as f32;
 
-            input[frame] = downmixed;
+            // The output has flushed, so the queues can start over unheard.
+            self.blocks.reset();
+            return ProcessStatus::ClearAllOutputs;
         }
+        self.flushed = false;
 
-        let (left, right) = outputs.split_at_mut(1);
+        if self.bypass {
+            let (left, right) = outputs.split_at_mut(1);
+            let left = &mut left[0][..proc_info.frames];
+            for (frame, sample) in left.iter_mut().enumerate() {
+                *sample = self.downmix.sample(inputs, frame);
+            }
+            right[0][..proc_info.frames].copy_from_slice(left);
 
-        self.renderer
-            .process_block(&input, &mut left[0], &mut right[0])
-            .unwrap();
+            return ProcessStatus::outputs_not_silent();
+        }
 
-        ProcessStatus::outputs_not_silent()
-    }
-}
+        let input = &mut scratch_buffers[0][..proc_info.frames];
 
-fn update_hrtf_effects(
-    listeners: Query<&GlobalTransform, Or<(With<SpatialListener2D>, With<SpatialListener3D>)>>,
-    mut emitters: Query<(&mut SofarHrtfNode, &EffectOf)>,
-    effect_parents: Query<&GlobalTransform>,
-) {
-    for (mut spatial, effect_of) in emitters.iter_mut() {
-        let Ok(transform) = effect_parents.get(effect_of.0) else {
-            continue;
-        };
+        for (frame, sample) in input.iter_mut().enumerate() {
+            *sample = self.downmix.sample(inputs, frame);
+        }
+
+        let (left, right) = outputs.split_at_mut(1);
+        let (renderer, split, telemetry) = (&mut self.renderer, &mut self.split, &self.telemetry);
 
-        let emitter_pos = transform.translation();
-        let closest_listener = find_closest_listener(
-            emitter_pos,
-            listeners.iter().map(GlobalTransform::translation),
+        self.blocks.process(
+            input,
+            &mut left[0][..proc_info.frames],
+            &mut right[0][..proc_info.frames],
+            |input, left, right| render_block(renderer, split, telemetry, input, left, right),
         );
 
-        let Some(listener_pos) = closest_listener else {
-            continue;
-        };
+        if silence_non_finite(outputs, proc_info.frames) {
+            self.telemetry.send(TelemetryMessage::NanDetected);
+        }
 
-        // TODO: factor in listener rotation
-        spatial.direction = emitter_pos - listener_pos;
+        let clipped = outputs
+            .iter_mut()
+            .map(|output| self.output_safety.process(&mut output[..proc_info.frames]))
+            .sum();
+        self.clip_counter.add(clipped);
+        self.telemetry.send_clipped(clipped);
+
+        ProcessStatus::outputs_not_silent()
     }
 }
 
-fn find_closest_listener(emitter_pos: Vec3, listeners: impl Iterator<Item = Vec3>) -> Option<Vec3> {
-    let mut closest_listener: Option<(f32, Vec3)> = None;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for listener_pos in listeners {
-        let distance = emitter_pos.distance_squared(listener_pos);
+    #[test]
+    fn requested_partition_lengths_are_checked() {
+        assert_eq!(checked_partition_len(0), None);
+        assert_eq!(checked_partition_len(100), None);
+        assert_eq!(checked_partition_len(usize::MAX), None);
 
-        match &mut closest_listener {
-            None => closest_listener = Some((distance, listener_pos)),
-            Some((old_distance, old_pos)) => {
-                if distance < *old_distance {
-                    *old_distance = distance;
-                    *old_pos = listener_pos;
-                }
-            }
-        }
+        assert_eq!(checked_partition_len(1), Some(16));
+        assert_eq!(checked_partition_len(128), Some(128));
+        assert_eq!(checked_partition_len(1 << 20), Some(1024));
     }
 
-    closest_listener.map(|l| l.1)
+    #[test]
+    fn automatic_partition_lengths_fit_the_stream_and_filter() {
+        assert_eq!(partition_len(0, 256), 16);
+        assert_eq!(partition_len(100, 256), 128);
+        assert_eq!(partition_len(4096, 256), 256);
+        assert_eq!(partition_len(4096, 300), 256);
+        assert_eq!(partition_len(4096, 8192), 1024);
+    }
 }
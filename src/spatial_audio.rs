@@ -0,0 +1,125 @@
+//! Backend-independent access to HRTF nodes.
+//!
+//! [`SpatialAudioNodes`] looks up the HRTF node on an effect entity
+//! whatever its backend and hands it back as a [`SpatialAudio`] trait
+//! object, so game systems can read and steer spatialization without
+//! naming `SofarHrtfNode` or `FyroxHrtfNode`. Backends that aren't
+//! compiled in are stood in for by [`Disabled`], which is never spawned.
+
+use bevy::{
+    ecs::{component::Mutable, system::SystemParam},
+    prelude::*,
+};
+
+use crate::spatial::HrtfNode;
+
+/// The object-safe part of [`HrtfNode`].
+pub trait SpatialAudio: Send + Sync {
+    /// The direction vector pointing from the listener to the emitter.
+    fn direction(&self) -> Vec3;
+
+    /// Set the direction vector pointing from the listener to the emitter.
+    fn set_direction(&mut self, direction: Vec3);
+
+    /// Pass the input through without spatialization.
+    fn set_bypass(&mut self, bypass: bool);
+}
+
+impl<N: HrtfNode> SpatialAudio for N {
+    fn direction(&self) -> Vec3 {
+        HrtfNode::direction(self)
+    }
+
+    fn set_direction(&mut self, direction: Vec3) {
+        HrtfNode::set_direction(self, direction);
+    }
+
+    fn set_bypass(&mut self, bypass: bool) {
+        HrtfNode::set_bypass(self, bypass);
+    }
+}
+
+/// Stands in for a backend that isn't compiled in.
+///
+/// Each backend gets its own `ID` so their queries don't conflict.
+#[derive(Component)]
+pub struct Disabled<const ID: u8>;
+
+impl<const ID: u8> SpatialAudio for Disabled<ID> {
+    fn direction(&self) -> Vec3 {
+        Vec3::ZERO
+    }
+
+    fn set_direction(&mut self, _: Vec3) {}
+
+    fn set_bypass(&mut self, _: bool) {}
+}
+
+#[cfg(feature = "sofar")]
+type SofarNode = crate::sofar_hrtf::SofarHrtfNode;
+#[cfg(not(feature = "sofar"))]
+type SofarNode = Disabled<0>;
+
+#[cfg(feature = "fyrox")]
+type FyroxNode = crate::fyrox_hrtf::FyroxHrtfNode;
+#[cfg(not(feature = "fyrox"))]
+type FyroxNode = Disabled<1>;
+
+#[cfg(feature = "realfft-backend")]
+type RealFftNode = crate::realfft_hrtf::RealFftHrtfNode;
+#[cfg(not(feature = "realfft-backend"))]
+type RealFftNode = Disabled<2>;
+
+#[cfg(feature = "lite")]
+type SimplePanNode = crate::simple_pan::SimplePanNode;
+#[cfg(not(feature = "lite"))]
+type SimplePanNode = Disabled<3>;
+
+/// Every HRTF node, whatever its backend.
+#[derive(SystemParam)]
+pub struct SpatialAudioNodes<'w, 's> {
+    sofar: Query<'w, 's, &'static mut SofarNode>,
+    fyrox: Query<'w, 's, &'static mut FyroxNode>,
+    realfft: Query<'w, 's, &'static mut RealFftNode>,
+    simple_pan: Query<'w, 's, &'static mut SimplePanNode>,
+}
+
+impl SpatialAudioNodes<'_, '_> {
+    /// The HRTF node on this effect entity.
+    pub fn get(&self, effect: Entity) -> Option<&dyn SpatialAudio> {
+        fn get<'a, N: SpatialAudio + Component<Mutability = Mutable>>(
+            query: &'a Query<&mut N>,
+            effect: Entity,
+        ) -> Option<&'a dyn SpatialAudio> {
+            query.get(effect).ok().map(|node| node as &dyn SpatialAudio)
+        }
+
+        get(&self.sofar, effect)
+            .or_else(|| get(&self.fyrox, effect))
+            .or_else(|| get(&self.realfft, effect))
+            .or_else(|| get(&self.simple_pan, effect))
+    }
+
+    /// The HRTF node on this effect entity, with change detection.
+    pub fn get_mut(&mut self, effect: Entity) -> Option<Mut<'_, dyn SpatialAudio>> {
+        fn get_mut<'a, N: SpatialAudio + Component<Mutability = Mutable>>(
+            query: &'a mut Query<&mut N>,
+            effect: Entity,
+        ) -> Option<Mut<'a, dyn SpatialAudio>> {
+            query
+                .get_mut(effect)
+                .ok()
+                .map(|node| node.map_unchanged(|node| node as &mut dyn SpatialAudio))
+        }
+
+        if self.sofar.contains(effect) {
+            get_mut(&mut self.sofar, effect)
+        } else if self.fyrox.contains(effect) {
+            get_mut(&mut self.fyrox, effect)
+        } else if self.realfft.contains(effect) {
+            get_mut(&mut self.realfft, effect)
+        } else {
+            get_mut(&mut self.simple_pan, effect)
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Detecting direction updates that never reached the audio thread.
+//!
+//! Neither bevy_seedling nor firewheel reports a patch that was dropped,
+//! for example because the event queue was full or the node was removed
+//! while the patch was in flight. Instead, each node's requested
+//! direction is compared with the one its processor last published to
+//! [`AppliedDirection`](crate::spatial::AppliedDirection). A node whose
+//! processor lags behind for more than [`STALE_FRAMES`] frames is counted
+//! in [`STALE_DIRECTIONS`].
+//!
+//! Only nodes with an applied-direction cell are tracked, which by
+//! default means debug builds.
+
+use bevy::{
+    diagnostic::{DiagnosticPath, Diagnostics},
+    prelude::*,
+};
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig, usable_direction};
+
+/// The number of HRTF nodes whose processor has been rendering
+/// a stale direction for more than [`STALE_FRAMES`] frames.
+pub const STALE_DIRECTIONS: DiagnosticPath = DiagnosticPath::const_new("hrtf/stale_directions");
+
+/// How many frames a processor may trail its node before
+/// the direction counts as stale.
+///
+/// A patch normally lands within a frame or two.
+pub const STALE_FRAMES: u32 = 2;
+
+/// How many frames a node's processor has trailed its requested direction.
+#[derive(Debug, Default, Clone, Copy, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct DirectionAge(pub u32);
+
+/// Stale nodes counted this frame.
+#[derive(Debug, Default, Resource)]
+pub struct StaleDirections(u32);
+
+/// Update the [`DirectionAge`] of every `N` node.
+pub fn track_direction_age<N: HrtfNode>(
+    mut nodes: Query<(Entity, &N, &N::Configuration, Option<&mut DirectionAge>)>,
+    mut stale: ResMut<StaleDirections>,
+    mut commands: Commands,
+) {
+    for (entity, node, config, age) in nodes.iter_mut() {
+        let Some(applied) = config.applied_direction() else {
+            continue;
+        };
+
+        // Processors keep their last direction in place of an unusable one.
+        let current = usable_direction(node.direction())
+            .is_none_or(|requested| requested.abs_diff_eq(applied.load(), 1e-4));
+
+        match age {
+            Some(mut age) => {
+                if current {
+                    if age.0 != 0 {
+                        age.0 = 0;
+                    }
+                } else {
+                    age.0 += 1;
+                    if age.0 > STALE_FRAMES {
+                        stale.0 += 1;
+                    }
+                }
+            }
+            None => {
+                commands.entity(entity).insert(DirectionAge::default());
+            }
+        }
+    }
+}
+
+/// Publish this frame's stale node count to [`STALE_DIRECTIONS`].
+pub fn report_stale_directions(mut stale: ResMut<StaleDirections>, mut diagnostics: Diagnostics) {
+    let count = core::mem::take(&mut stale.0);
+    diagnostics.add_measurement(&STALE_DIRECTIONS, || count as f64);
+}
@@ -0,0 +1,229 @@
+//! Gating audio on the app state.
+//!
+//! The demo starts in a menu. Pressing start (which also counts as
+//! the user gesture browsers require before audio can play) enters
+//! [`AppState::Playing`], and `Escape` toggles [`AppState::Paused`].
+//!
+//! - Entering `Paused` fades the main bus out and freezes motion.
+//! - Entering `Menu` pauses all sample players. The effect chains
+//!   keep running, so reverb tails ring out naturally.
+//! - Entering `Playing` fades back in and resumes playback.
+//!
+//! Independently of the state, pausing [`Time<Virtual>`] pauses every
+//! spatialized player until time is unpaused. The effect chains stay
+//! in the graph, so each HRTF processor keeps its filter state and
+//! picks up where it left off.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::validate::Spatializer;
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<AppState>()
+            .enable_state_scoped_entities::<AppState>()
+            .init_resource::<MainBusFade>()
+            .add_systems(OnEnter(AppState::Menu), (spawn_menu, pause_players))
+            .add_systems(OnEnter(AppState::Paused), fade_to(0.0))
+            .add_systems(OnEnter(AppState::Playing), (fade_to(1.0), resume_players))
+            .add_systems(
+                Update,
+                (
+                    start_button.run_if(in_state(AppState::Menu)),
+                    pause_new_players.run_if(in_state(AppState::Menu)),
+                    toggle_pause.run_if(not(in_state(AppState::Menu))),
+                    fade_main_bus,
+                    pause_with_time.run_if(not(in_state(AppState::Menu))),
+                ),
+            );
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, States)]
+pub enum AppState {
+    #[default]
+    Menu,
+    Playing,
+    Paused,
+}
+
+/// A gain applied on top of the main bus volume.
+///
+/// The pause fade and the scene transition fade are ramped
+/// separately and multiplied together.
+#[derive(Debug, Resource)]
+pub struct MainBusFade {
+    level: f32,
+    target: f32,
+    transition: f32,
+    transition_target: f32,
+    /// The main bus volume before any fading, captured on the first fade.
+    base: Option<Volume>,
+}
+
+impl Default for MainBusFade {
+    fn default() -> Self {
+        Self {
+            level: 1.0,
+            target: 1.0,
+            transition: 1.0,
+            transition_target: 1.0,
+            base: None,
+        }
+    }
+}
+
+impl MainBusFade {
+    /// Ramp the scene transition gain toward `target`.
+    pub fn set_transition_target(&mut self, target: f32) {
+        self.transition_target = target;
+    }
+
+    /// The current scene transition gain.
+    pub fn transition(&self) -> f32 {
+        self.transition
+    }
+}
+
+fn step_toward(level: f32, target: f32, step: f32) -> f32 {
+    if level < target {
+        (level + step).min(target)
+    } else {
+        (level - step).max(target)
+    }
+}
+
+fn fade_to(target: f32) -> impl Fn(ResMut<MainBusFade>) {
+    move |mut fade| fade.target = target
+}
+
+pub(crate) fn fade_main_bus(
+    mut fade: ResMut<MainBusFade>,
+    mut main_bus: Single<&mut VolumeNode, With<MainBus>>,
+    time: Res<Time>,
+    real_time: Res<Time<Real>>,
+) {
+    const FADE_SECONDS: f32 = 0.2;
+    const TRANSITION_SECONDS: f32 = 0.15;
+
+    if fade.level == fade.target && fade.transition == fade.transition_target {
+        return;
+    }
+
+    let base = *fade.base.get_or_insert(main_bus.volume);
+
+    fade.level = step_toward(fade.level, fade.target, time.delta_secs() / FADE_SECONDS);
+    // Transitions run even while virtual time is paused.
+    fade.transition = step_toward(
+        fade.transition,
+        fade.transition_target,
+        real_time.delta_secs() / TRANSITION_SECONDS,
+    );
+
+    main_bus.volume = Volume::Linear(base.linear() * fade.level * fade.transition);
+}
+
+fn spawn_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            StateScoped(AppState::Menu),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+        ))
+        .with_child((
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(24.0), Val::Px(12.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.15, 0.15, 0.2)),
+            children![Text::new("Start")],
+        ));
+}
+
+fn start_button(
+    buttons: Query<&Interaction, (Changed<Interaction>, With<Button>)>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if buttons.iter().any(|i| *i == Interaction::Pressed) {
+        next.set(AppState::Playing);
+    }
+}
+
+fn toggle_pause(
+    input: Res<ButtonInput<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if input.just_pressed(KeyCode::Escape) {
+        next.set(match state.get() {
+            AppState::Paused => AppState::Playing,
+            _ => AppState::Paused,
+        });
+    }
+}
+
+fn pause_players(mut players: Query<&mut PlaybackSettings>) {
+    for mut settings in players.iter_mut() {
+        settings.pause();
+    }
+}
+
+/// Players spawned while in the menu start paused.
+fn pause_new_players(mut players: Query<&mut PlaybackSettings, Added<PlaybackSettings>>) {
+    for mut settings in players.iter_mut() {
+        settings.pause();
+    }
+}
+
+fn resume_players(mut players: Query<&mut PlaybackSettings>) {
+    for mut settings in players.iter_mut() {
+        settings.play();
+    }
+}
+
+/// Marks a player that was paused because [`Time<Virtual>`] was.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct SpatialAudioPause;
+
+/// Pause spatialized players while virtual time is paused,
+/// and resume the ones paused here when it isn't.
+fn pause_with_time(
+    time: Res<Time<Virtual>>,
+    mut players: Query<(
+        Entity,
+        &mut PlaybackSettings,
+        &SampleEffects,
+        Has<SpatialAudioPause>,
+    )>,
+    spatializers: Query<(), With<Spatializer>>,
+    mut commands: Commands,
+) {
+    let paused = time.is_paused();
+
+    for (entity, mut settings, effects, was_paused) in players.iter_mut() {
+        if paused == was_paused {
+            continue;
+        }
+
+        if !effects.iter().any(|effect| spatializers.contains(effect)) {
+            continue;
+        }
+
+        if paused {
+            settings.pause();
+            commands.entity(entity).insert(SpatialAudioPause);
+        } else {
+            settings.play();
+            commands.entity(entity).remove::<SpatialAudioPause>();
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Declaring effect chains with named nodes.
+//!
+//! [`channel_strip!`] spawns a list of named nodes as an emitter's
+//! effects, in order, and records each node's entity under its name in
+//! a [`ChannelStrip`] on the emitter, so a node can be found again
+//! without walking [`SampleEffects`] and matching on types:
+//!
+//! ```ignore
+//! channel_strip!(&mut emitter, {
+//!     hrtf: FyroxHrtfNode::default() => HrtfStandby,
+//!     volume: VolumeNode::default(),
+//! });
+//! ```
+//!
+//! Components after `=>` are inserted alongside the node. A strip with
+//! a [`VolumeNode`] ahead of an HRTF node is logged as an error, since
+//! the HRTF stage would then see a signal the strip's gain has already
+//! shaped.
+
+use core::any::TypeId;
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+/// The nodes an emitter's [`channel_strip!`] spawned, by name.
+#[derive(Debug, Default, Clone, Component)]
+pub struct ChannelStrip(HashMap<&'static str, Entity>);
+
+impl ChannelStrip {
+    pub fn insert(&mut self, name: &'static str, node: Entity) {
+        self.0.insert(name, node);
+    }
+
+    /// The node spawned under `name`.
+    #[cfg_attr(
+        not(feature = "demo-ui"),
+        expect(dead_code, reason = "only the demo panel looks strip nodes up")
+    )]
+    pub fn get(&self, name: &str) -> Option<Entity> {
+        self.0.get(name).copied()
+    }
+}
+
+/// Whether `node` is one of the HRTF nodes compiled into this build.
+fn is_hrtf(node: TypeId) -> bool {
+    let hrtf_nodes: &[TypeId] = &[
+        #[cfg(feature = "fyrox")]
+        TypeId::of::<crate::fyrox_hrtf::FyroxHrtfNode>(),
+        #[cfg(feature = "sofar")]
+        TypeId::of::<crate::sofar_hrtf::SofarHrtfNode>(),
+        #[cfg(feature = "sofar")]
+        TypeId::of::<crate::sofar_ambisonic::SofarAmbisonicHrtfNode>(),
+        #[cfg(feature = "realfft-backend")]
+        TypeId::of::<crate::realfft_hrtf::RealFftHrtfNode>(),
+        #[cfg(feature = "lite")]
+        TypeId::of::<crate::simple_pan::SimplePanNode>(),
+    ];
+
+    hrtf_nodes.contains(&node)
+}
+
+/// A [`channel_strip!`] with a [`VolumeNode`] ahead of an HRTF node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StripOrderError {
+    pub volume: &'static str,
+    pub hrtf: &'static str,
+}
+
+impl core::fmt::Display for StripOrderError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "channel strip volume `{}` comes before HRTF node `{}`",
+            self.volume, self.hrtf
+        )
+    }
+}
+
+impl std::error::Error for StripOrderError {}
+
+/// Check that no [`VolumeNode`] comes before an HRTF node in `nodes`.
+///
+/// Called by [`channel_strip!`] before anything is spawned.
+pub fn validate_strip(nodes: &[(&'static str, TypeId)]) -> Result<(), StripOrderError> {
+    let Some(volume) = nodes
+        .iter()
+        .position(|(_, node)| *node == TypeId::of::<VolumeNode>())
+    else {
+        return Ok(());
+    };
+
+    match nodes[volume..].iter().find(|(_, node)| is_hrtf(*node)) {
+        Some((hrtf, _)) => Err(StripOrderError {
+            volume: nodes[volume].0,
+            hrtf,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Spawn named nodes as an emitter's effects, recording
+/// them in a [`ChannelStrip`] on the emitter.
+///
+/// See the [module docs](self) for the syntax. A strip in the
+/// wrong order is logged and spawned as declared.
+macro_rules! channel_strip {
+    // A node on its own, or with the components inserted alongside it.
+    (@bundle $node:ident) => {
+        $node
+    };
+    (@bundle $node:ident, $extra:expr) => {
+        ($node, $extra)
+    };
+    ($emitter:expr, { $($name:ident : $node:expr $(=> $extra:expr)?),+ $(,)? }) => {{
+        let emitter: &mut ::bevy::ecs::system::EntityCommands = $emitter;
+
+        $(let $name = $node;)+
+        if let Err(error) = $crate::strip::validate_strip(&[
+            $((stringify!($name), ::core::any::Any::type_id(&$name))),+
+        ]) {
+            ::bevy::log::error!("{error}");
+        }
+
+        let mut strip = $crate::strip::ChannelStrip::default();
+        emitter.with_related_entities::<::bevy_seedling::prelude::EffectOf>(|chain| {
+            $(strip.insert(
+                stringify!($name),
+                chain.spawn($crate::strip::channel_strip!(@bundle $name $(, $extra)?)).id(),
+            );)+
+        });
+        emitter.insert(strip);
+    }};
+}
+
+pub(crate) use channel_strip;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "fyrox")]
+    #[test]
+    fn volume_must_follow_hrtf() {
+        let hrtf = TypeId::of::<crate::fyrox_hrtf::FyroxHrtfNode>();
+        let volume = TypeId::of::<VolumeNode>();
+
+        assert_eq!(
+            validate_strip(&[("hrtf", hrtf), ("volume", volume)]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_strip(&[("volume", volume), ("hrtf", hrtf)]),
+            Err(StripOrderError {
+                volume: "volume",
+                hrtf: "hrtf",
+            })
+        );
+    }
+
+    #[test]
+    fn strips_without_hrtf_are_valid() {
+        let volume = TypeId::of::<VolumeNode>();
+        let spatial = TypeId::of::<SpatialBasicNode>();
+
+        assert_eq!(
+            validate_strip(&[("volume", volume), ("spatial", spatial)]),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Letting processors ring out after their input goes silent.
+//!
+//! Convolution and delay lines keep producing output for a while after
+//! their input stops. A processor that reports silence as soon as its
+//! input does cuts that tail off, and leaves it buffered, so it plays
+//! when the input resumes instead. [`SilenceTail`] keeps a processor
+//! running on silent input until its tail has passed, after which its
+//! state has flushed to zeros and it can report silence without doing
+//! any work.
+
+/// Counts down a processor's tail once its input goes silent.
+#[derive(Debug, Clone)]
+pub struct SilenceTail {
+    len: usize,
+    remaining: usize,
+}
+
+impl SilenceTail {
+    /// Track a tail of `len` frames.
+    pub fn new(len: usize) -> Self {
+        Self { len, remaining: 0 }
+    }
+
+    /// Whether a block of `frames` needs processing.
+    ///
+    /// Blocks with input rearm the tail. Silent blocks are processed
+    /// until the tail has passed, and skipped after that.
+    pub fn process_block(&mut self, input_silent: bool, frames: usize) -> bool {
+        if !input_silent {
+            self.remaining = self.len;
+            return true;
+        }
+
+        let ringing = self.remaining > 0;
+        self.remaining = self.remaining.saturating_sub(frames);
+        ringing
+    }
+}
+
+#[cfg(all(test, feature = "sofar"))]
+mod tests {
+    use super::*;
+    use crate::blocks::FixedBlocks;
+    use crate::crossover::Crossover;
+    use crate::limiter::OutputSafety;
+    use crate::rng::DemoRng;
+    use crate::telemetry::{Telemetry, TelemetryMessage, silence_non_finite};
+
+    const MAX_FRAMES: usize = 512;
+    const PARTITION_LEN: usize = 128;
+    /// A short filter standing in for an HRIR, with an L1 norm of 1,
+    /// so it never raises the level of its input.
+    const FILTER: [f32; 4] = [0.5, 0.25, -0.15, 0.1];
+
+    #[test]
+    fn silence_is_reported_once_the_tail_has_passed() {
+        let mut rng = DemoRng { seed: 11 }.stream("tail");
+
+        for len in [0, 1, 100, 1000] {
+            let mut tail = SilenceTail::new(len);
+            // A fresh tail has nothing to ring out.
+            let mut silent_frames = len;
+
+            for _ in 0..2000 {
+                let frames = 1 + (rng.next_u64() % MAX_FRAMES as u64) as usize;
+                let silent = rng.next_f32() < 0.8;
+
+                let processed = tail.process_block(silent, frames);
+                if silent {
+                    assert_eq!(processed, silent_frames < len);
+                    silent_frames += frames;
+                } else {
+                    assert!(processed);
+                    silent_frames = 0;
+                }
+            }
+        }
+    }
+
+    /// Drive the stages an HRTF processor chains together with random
+    /// block sizes, silent stretches and bad output, and check the
+    /// output of every block.
+    #[test]
+    fn processor_stages_keep_their_invariants() {
+        for seed in 0..8 {
+            let mut rng = DemoRng { seed }.stream("processor stages");
+
+            let mut tail = SilenceTail::new(FILTER.len() + 2 * PARTITION_LEN);
+            let mut blocks = FixedBlocks::new(PARTITION_LEN, MAX_FRAMES);
+            let mut crossover = Crossover::new(400.0, 48_000.0);
+            let mut history = [0.0; FILTER.len()];
+            let safety = OutputSafety::Clamp { ceiling: 0.5 };
+            let telemetry = Telemetry::default();
+
+            let (mut input_energy, mut output_energy) = (0.0f64, 0.0f64);
+            let (mut clipped, mut nan_blocks) = (0, 0);
+
+            for _ in 0..400 {
+                let frames = 1 + (rng.next_u64() % MAX_FRAMES as u64) as usize;
+                let silent = rng.next_f32() < 0.4;
+                let mut input: Vec<f32> = (0..frames)
+                    .map(|_| if silent { 0.0 } else { rng.range(-1.0, 1.0) })
+                    .collect();
+                input_energy += input.iter().map(|x| f64::from(x * x)).sum::<f64>();
+
+                // Stale output from a previous block must never leak through.
+                let mut left = vec![f32::NAN; frames];
+                let mut right = vec![f32::NAN; frames];
+
+                if !tail.process_block(silent, frames) {
+                    blocks.reset();
+                    left.fill(0.0);
+                    right.fill(0.0);
+                } else {
+                    let mut low = Vec::with_capacity(frames);
+                    for sample in &mut input {
+                        let (l, h) = crossover.split(*sample);
+                        low.push(l);
+                        *sample = h;
+                    }
+
+                    blocks.process(&mut input, &mut left, &mut right, |input, left, right| {
+                        for ((input, left), right) in input.iter().zip(left).zip(right) {
+                            history.rotate_right(1);
+                            history[0] = *input;
+                            let out = history.iter().zip(FILTER).map(|(x, h)| x * h).sum();
+                            *left = out;
+                            *right = -out;
+                        }
+                    });
+
+                    for ((low, left), right) in low.iter().zip(&mut left).zip(&mut right) {
+                        *left += low;
+                        *right += low;
+                    }
+
+                    // A misbehaving renderer now and then.
+                    if rng.next_f32() < 0.05 {
+                        left[frames / 2] = f32::INFINITY;
+                        nan_blocks += 1;
+                    }
+                    let mut outputs = [&mut left[..], &mut right[..]];
+                    if silence_non_finite(&mut outputs, frames) {
+                        telemetry.send(TelemetryMessage::NanDetected);
+                    }
+
+                    let count = outputs
+                        .iter_mut()
+                        .map(|output| safety.process(output))
+                        .sum();
+                    clipped += count;
+                    telemetry.send_clipped(count);
+                }
+
+                assert_eq!(left.len(), frames);
+                assert!(left.iter().chain(&right).all(|sample| sample.is_finite()));
+                assert!(left.iter().chain(&right).all(|sample| sample.abs() <= 0.5));
+                output_energy += left
+                    .iter()
+                    .chain(&right)
+                    .map(|x| f64::from(x * x))
+                    .sum::<f64>();
+
+                let mut reported = (0, 0);
+                let dropped = telemetry.drain(|message| match message {
+                    TelemetryMessage::NanDetected => reported.0 += 1,
+                    TelemetryMessage::Clipped(count) => reported.1 += u64::from(count),
+                    _ => unreachable!(),
+                });
+                assert_eq!(dropped, 0);
+                nan_blocks -= reported.0;
+                clipped -= reported.1;
+            }
+
+            // Every problem was reported, and nothing else.
+            assert_eq!((nan_blocks, clipped), (0, 0), "seed {seed}");
+
+            // Each ear's gain is at most the filter's plus the low band's,
+            // so the output is bounded by the input scaled by both.
+            assert!(
+                output_energy <= 2.0 * 4.0 * input_energy,
+                "seed {seed}: {output_energy} > {input_energy}"
+            );
+        }
+    }
+}
@@ -0,0 +1,354 @@
+//! Reporting problems from the audio thread.
+//!
+//! Processors can't log, lock or allocate, so the HRTF processors
+//! report what went wrong as compact [`TelemetryMessage`]s through a
+//! [`Telemetry`] channel instead. Each node's configuration holds its
+//! channel, which the node's processor sends to and [`drain_telemetry`]
+//! drains every frame. Each message is logged and counted in the node's
+//! [`ProcessorTelemetry`], which shows up in the inspector, and lost
+//! messages are reported through [`TELEMETRY_DROPPED`].
+//!
+//! The channel is a ring of [`CAPACITY`] slots with a single sender,
+//! the processor, and a single receiver, the ECS. Sending never waits:
+//! when the ring is full, the oldest messages are overwritten, and
+//! counted as dropped once the receiver gets to them.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use bevy::{
+    diagnostic::{DiagnosticPath, Diagnostics},
+    prelude::*,
+};
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig};
+
+/// The number of telemetry messages lost across all HRTF
+/// nodes during the last frame.
+pub const TELEMETRY_DROPPED: DiagnosticPath = DiagnosticPath::const_new("hrtf/telemetry_dropped");
+
+/// How many messages a [`Telemetry`] channel holds before
+/// it starts overwriting the oldest.
+///
+/// A processor sends at most a few messages per block, so this
+/// covers several frames' worth.
+pub const CAPACITY: usize = 64;
+
+/// Something a processor reports from the audio thread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetryMessage {
+    /// The output had NaN or infinite samples, and was silenced.
+    NanDetected,
+    /// This many output samples crossed the safety ceiling.
+    Clipped(u32),
+    /// A filter couldn't be applied, so the previous one was
+    /// kept, or a block couldn't be rendered and was silenced.
+    FilterLookupFailed,
+    /// The input went silent and the tail has passed.
+    TailFinished,
+}
+
+impl TelemetryMessage {
+    /// The largest count a [`Clipped`](Self::Clipped) message carries.
+    pub const MAX_COUNT: u32 = (1 << 24) - 1;
+
+    /// The message's tag in the top 8 bits, above its payload.
+    fn encode(self) -> u32 {
+        match self {
+            Self::NanDetected => 1 << 24,
+            Self::Clipped(count) => 2 << 24 | count.min(Self::MAX_COUNT),
+            Self::FilterLookupFailed => 3 << 24,
+            Self::TailFinished => 4 << 24,
+        }
+    }
+
+    fn decode(bits: u32) -> Option<Self> {
+        match bits >> 24 {
+            1 => Some(Self::NanDetected),
+            2 => Some(Self::Clipped(bits & Self::MAX_COUNT)),
+            3 => Some(Self::FilterLookupFailed),
+            4 => Some(Self::TailFinished),
+            _ => None,
+        }
+    }
+}
+
+/// A bounded channel from one processor to the ECS.
+///
+/// Only one processor may send at a time. Firewheel builds a node's
+/// processor again when the stream restarts, but drops the old one
+/// first, so the node's channel carries on.
+#[derive(Debug)]
+pub struct Telemetry {
+    /// Each slot holds the sequence number it was written
+    /// at in the top 32 bits, above the encoded message.
+    slots: [AtomicU64; CAPACITY],
+    /// The sequence number of the next message sent.
+    written: AtomicU64,
+    /// The sequence number of the next message drained.
+    read: AtomicU64,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            slots: core::array::from_fn(|_| AtomicU64::new(0)),
+            written: AtomicU64::new(0),
+            read: AtomicU64::new(0),
+        }
+    }
+}
+
+impl Telemetry {
+    /// Send `message`, overwriting the oldest if the ring is full.
+    ///
+    /// Never waits, locks or allocates.
+    pub fn send(&self, message: TelemetryMessage) {
+        // The sender is the only one moving `written`.
+        let sequence = self.written.load(Ordering::Relaxed);
+        let slot = sequence << 32 | u64::from(message.encode());
+
+        self.slots[sequence as usize % CAPACITY].store(slot, Ordering::Release);
+        self.written.store(sequence + 1, Ordering::Release);
+    }
+
+    /// Send a [`Clipped`](TelemetryMessage::Clipped) message,
+    /// unless nothing was clipped.
+    pub fn send_clipped(&self, count: u64) {
+        if count > 0 {
+            let count = count.min(u64::from(TelemetryMessage::MAX_COUNT)) as u32;
+            self.send(TelemetryMessage::Clipped(count));
+        }
+    }
+
+    /// Pass every message sent since the last drain to
+    /// `receive`, oldest first, returning how many were lost.
+    pub fn drain(&self, mut receive: impl FnMut(TelemetryMessage)) -> u64 {
+        let written = self.written.load(Ordering::Acquire);
+        let mut next = self.read.load(Ordering::Relaxed);
+
+        // Anything more than a ring behind has been overwritten.
+        let mut dropped = written.saturating_sub(next).saturating_sub(CAPACITY as u64);
+        next += dropped;
+
+        while next < written {
+            let slot = self.slots[next as usize % CAPACITY].load(Ordering::Acquire);
+
+            // The sender may have lapped the ring while this drained.
+            if (slot >> 32) as u32 == next as u32 {
+                if let Some(message) = TelemetryMessage::decode(slot as u32) {
+                    receive(message);
+                }
+            } else {
+                dropped += 1;
+            }
+
+            next += 1;
+        }
+
+        self.read.store(next, Ordering::Relaxed);
+        dropped
+    }
+}
+
+/// Silence the first `frames` samples of `outputs` if any of
+/// them are NaN or infinite, returning whether they were.
+///
+/// A NaN would otherwise pass through the output safety stage and
+/// poison everything mixed after the node.
+pub fn silence_non_finite(outputs: &mut [&mut [f32]], frames: usize) -> bool {
+    let non_finite = outputs
+        .iter()
+        .any(|output| output[..frames].iter().any(|sample| !sample.is_finite()));
+
+    if non_finite {
+        for output in outputs.iter_mut() {
+            output[..frames].fill(0.0);
+        }
+    }
+
+    non_finite
+}
+
+/// What a node's processor has reported since the node was spawned.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct ProcessorTelemetry {
+    /// Blocks silenced for NaN or infinite output.
+    pub nan_blocks: u64,
+    pub clipped_samples: u64,
+    /// Filters that couldn't be applied.
+    pub failed_lookups: u64,
+    /// How often the processor went quiet once its input did.
+    pub tails_finished: u64,
+    /// Messages lost to a full ring.
+    pub dropped: u64,
+}
+
+impl ProcessorTelemetry {
+    fn receive(&mut self, message: TelemetryMessage) {
+        match message {
+            TelemetryMessage::NanDetected => self.nan_blocks += 1,
+            TelemetryMessage::Clipped(count) => self.clipped_samples += u64::from(count),
+            TelemetryMessage::FilterLookupFailed => self.failed_lookups += 1,
+            TelemetryMessage::TailFinished => self.tails_finished += 1,
+        }
+    }
+
+    fn add(&mut self, other: Self) {
+        self.nan_blocks += other.nan_blocks;
+        self.clipped_samples += other.clipped_samples;
+        self.failed_lookups += other.failed_lookups;
+        self.tails_finished += other.tails_finished;
+        self.dropped += other.dropped;
+    }
+
+    fn log(&self, entity: Entity) {
+        if self.nan_blocks > 0 {
+            error!(
+                "HRTF node {entity} silenced {} blocks of NaN or infinite output",
+                self.nan_blocks
+            );
+        }
+        if self.failed_lookups > 0 {
+            warn!(
+                "HRTF node {entity} kept its previous filter {} times",
+                self.failed_lookups
+            );
+        }
+        if self.dropped > 0 {
+            warn!(
+                "HRTF node {entity} dropped {} telemetry messages",
+                self.dropped
+            );
+        }
+        if self.clipped_samples > 0 {
+            debug!(
+                "HRTF node {entity} clipped {} samples",
+                self.clipped_samples
+            );
+        }
+        if self.tails_finished > 0 {
+            trace!("HRTF node {entity} finished its tail");
+        }
+    }
+}
+
+/// Telemetry messages lost this frame.
+#[derive(Debug, Default, Resource)]
+pub struct DroppedTelemetry(u64);
+
+/// Drain the telemetry of every `N` node into its [`ProcessorTelemetry`].
+pub fn drain_telemetry<N: HrtfNode>(
+    mut nodes: Query<(Entity, &N::Configuration, Option<&mut ProcessorTelemetry>), With<N>>,
+    mut dropped: ResMut<DroppedTelemetry>,
+    mut commands: Commands,
+) {
+    for (entity, config, totals) in nodes.iter_mut() {
+        let Some(telemetry) = config.telemetry() else {
+            continue;
+        };
+
+        let mut received = ProcessorTelemetry::default();
+        let lost = telemetry.drain(|message| received.receive(message));
+        received.dropped = lost;
+        received.log(entity);
+        dropped.0 += received.dropped;
+
+        match totals {
+            // Leave quiet nodes unchanged for the inspector.
+            Some(mut totals) => {
+                if received != ProcessorTelemetry::default() {
+                    totals.add(received);
+                }
+            }
+            None => {
+                commands.entity(entity).insert(received);
+            }
+        }
+    }
+}
+
+/// Publish this frame's dropped message count to [`TELEMETRY_DROPPED`].
+pub fn report_dropped_telemetry(
+    mut dropped: ResMut<DroppedTelemetry>,
+    mut diagnostics: Diagnostics,
+) {
+    let count = core::mem::take(&mut dropped.0);
+    diagnostics.add_measurement(&TELEMETRY_DROPPED, || count as f64);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drain(telemetry: &Telemetry) -> (Vec<TelemetryMessage>, u64) {
+        let mut received = Vec::new();
+        let dropped = telemetry.drain(|message| received.push(message));
+        (received, dropped)
+    }
+
+    #[test]
+    fn messages_arrive_in_order() {
+        let telemetry = Telemetry::default();
+        let sent = [
+            TelemetryMessage::NanDetected,
+            TelemetryMessage::Clipped(12),
+            TelemetryMessage::FilterLookupFailed,
+            TelemetryMessage::TailFinished,
+            TelemetryMessage::Clipped(TelemetryMessage::MAX_COUNT),
+        ];
+        for message in sent {
+            telemetry.send(message);
+        }
+
+        assert_eq!(drain(&telemetry), (sent.to_vec(), 0));
+        assert_eq!(drain(&telemetry), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn a_lapped_ring_drops_the_oldest() {
+        let telemetry = Telemetry::default();
+        let extra = 10;
+        for count in 0..(CAPACITY + extra) as u32 {
+            telemetry.send(TelemetryMessage::Clipped(count));
+        }
+
+        let (received, dropped) = drain(&telemetry);
+        assert_eq!(dropped, extra as u64);
+        let expected: Vec<_> = (extra as u32..(CAPACITY + extra) as u32)
+            .map(TelemetryMessage::Clipped)
+            .collect();
+        assert_eq!(received, expected);
+
+        // The count starts over once the receiver has caught up.
+        telemetry.send(TelemetryMessage::NanDetected);
+        assert_eq!(drain(&telemetry), (vec![TelemetryMessage::NanDetected], 0));
+    }
+
+    #[test]
+    fn dropped_messages_are_counted_across_laps() {
+        let telemetry = Telemetry::default();
+        for _ in 0..3 * CAPACITY + 1 {
+            telemetry.send(TelemetryMessage::TailFinished);
+        }
+
+        let (received, dropped) = drain(&telemetry);
+        assert_eq!(received.len(), CAPACITY);
+        assert_eq!(dropped, 2 * CAPACITY as u64 + 1);
+    }
+
+    #[test]
+    fn clipped_counts_saturate_and_skip_zero() {
+        let telemetry = Telemetry::default();
+        telemetry.send_clipped(0);
+        telemetry.send_clipped(u64::MAX);
+
+        assert_eq!(
+            drain(&telemetry),
+            (
+                vec![TelemetryMessage::Clipped(TelemetryMessage::MAX_COUNT)],
+                0
+            )
+        );
+    }
+}
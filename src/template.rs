@@ -0,0 +1,250 @@
+//! Building a default effect chain for bare emitters.
+//!
+//! The smallest integration is a [`SamplePlayer`] with a [`Transform`]
+//! and a [`Spatial`] marker. When both the marker and the player are
+//! present, the first HRTF backend plugin added builds its
+//! [`ChainTemplate`] as the emitter's effects: an optional reverb send,
+//! the backend's node, and an optional output volume.
+//!
+//! The chain is built once, in whichever order the marker and the
+//! player are inserted, and never for an emitter that already declares
+//! its own effects with `sample_effects!`.
+
+use bevy::ecs::relationship::RelatedSpawner;
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::bus::{ReverbBus, ReverbBuses};
+
+/// Builds the backend's default effect chain on this emitter.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Spatial;
+
+/// The chain built for emitters marked [`Spatial`].
+#[derive(Debug, Clone)]
+pub struct ChainTemplate {
+    /// The reverb bus to send to ahead of the HRTF node, if it exists.
+    ///
+    /// Defaults to [`ReverbBus::MAIN`].
+    pub send_bus: Option<&'static str>,
+    /// The level of the reverb send.
+    ///
+    /// Defaults to -6 dB.
+    pub send_level: Volume,
+    /// A volume node after the HRTF node.
+    ///
+    /// Defaults to unity gain.
+    pub volume: Option<Volume>,
+}
+
+impl Default for ChainTemplate {
+    fn default() -> Self {
+        Self {
+            send_bus: Some(ReverbBus::MAIN.0),
+            send_level: Volume::Linear(0.5),
+            volume: Some(Volume::UNITY_GAIN),
+        }
+    }
+}
+
+/// The template in use, with the node spawner of the backend that set it.
+#[derive(Resource)]
+struct ActiveTemplate {
+    template: ChainTemplate,
+    spawn_node: fn(&mut RelatedSpawner<EffectOf>),
+}
+
+/// Spawn a default `N` into a chain.
+fn spawn_node<N: Bundle + Default>(chain: &mut RelatedSpawner<EffectOf>) {
+    chain.spawn(N::default());
+}
+
+/// Build `template` with `N` for [`Spatial`] emitters, unless
+/// another backend already registered its template.
+pub fn register_chain_template<N: Bundle + Default>(app: &mut App, template: ChainTemplate) {
+    if app.world().contains_resource::<ActiveTemplate>() {
+        return;
+    }
+
+    app.insert_resource(ActiveTemplate {
+        template,
+        spawn_node: spawn_node::<N>,
+    })
+    .add_observer(on_add_spatial)
+    .add_observer(on_add_player);
+}
+
+/// Marks an emitter whose template chain has been built.
+#[derive(Component)]
+struct ChainTemplated;
+
+fn on_add_spatial(trigger: Trigger<OnAdd, Spatial>, mut commands: Commands) {
+    let entity = trigger.target();
+    commands.queue(move |world: &mut World| apply_template(world, entity));
+}
+
+fn on_add_player(trigger: Trigger<OnAdd, SamplePlayer>, mut commands: Commands) {
+    let entity = trigger.target();
+    commands.queue(move |world: &mut World| apply_template(world, entity));
+}
+
+/// Build the template on `entity` if it's a [`Spatial`] player with no chain.
+///
+/// This runs as a command, after any `sample_effects!` spawned alongside
+/// the marker have been attached.
+fn apply_template(world: &mut World, entity: Entity) {
+    let Ok(emitter) = world.get_entity(entity) else {
+        return;
+    };
+    if !emitter.contains::<Spatial>()
+        || !emitter.contains::<SamplePlayer>()
+        || emitter.contains::<SampleEffects>()
+        || emitter.contains::<ChainTemplated>()
+    {
+        return;
+    }
+
+    build_template(world, entity, |_| {});
+}
+
+/// Build the template on `entity`, after the nodes `leading` spawns.
+fn build_template(
+    world: &mut World,
+    entity: Entity,
+    leading: impl FnOnce(&mut RelatedSpawner<EffectOf>),
+) {
+    let ActiveTemplate {
+        template,
+        spawn_node,
+    } = world.resource::<ActiveTemplate>();
+    let (template, spawn_node) = (template.clone(), *spawn_node);
+    let send = template.send_bus.and_then(|bus| {
+        world
+            .resource::<ReverbBuses>()
+            .send(bus, template.send_level)
+    });
+
+    world
+        .entity_mut(entity)
+        .insert(ChainTemplated)
+        .with_related_entities::<EffectOf>(|chain| {
+            leading(chain);
+            if let Some(send) = send {
+                chain.spawn(send);
+            }
+            spawn_node(chain);
+            if let Some(volume) = template.volume {
+                chain.spawn(VolumeNode { volume });
+            }
+        });
+}
+
+/// Build the active template on `emitter`, after the nodes `leading` spawns.
+///
+/// Unlike a [`Spatial`] marker, this works for emitters that need a few
+/// nodes of their own ahead of the template.
+pub fn spawn_template_chain(
+    emitter: &mut EntityCommands,
+    leading: impl FnOnce(&mut RelatedSpawner<EffectOf>) + Send + 'static,
+) {
+    emitter.queue(move |emitter: EntityWorldMut| {
+        let entity = emitter.id();
+        let world = emitter.into_world_mut();
+
+        if !world.contains_resource::<ActiveTemplate>() {
+            warn!("no HRTF backend registered a chain template for {entity}");
+            return;
+        }
+        build_template(world, entity, leading);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stands in for a backend's HRTF node.
+    #[derive(Debug, Default, Component)]
+    struct TestNode;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ReverbBuses>();
+        register_chain_template::<TestNode>(&mut app, ChainTemplate::default());
+
+        app
+    }
+
+    /// The template nodes built on `emitter`.
+    fn template_nodes(app: &mut App, emitter: Entity) -> usize {
+        app.world_mut()
+            .query_filtered::<&EffectOf, With<TestNode>>()
+            .iter(app.world())
+            .filter(|effect_of| effect_of.0 == emitter)
+            .count()
+    }
+
+    #[test]
+    fn chain_is_built_once_in_either_order() {
+        let mut app = app();
+
+        // The marker after the player, a frame later.
+        let late_marker = app
+            .world_mut()
+            .spawn(SamplePlayer::new(Handle::default()))
+            .id();
+        app.update();
+        app.world_mut().entity_mut(late_marker).insert(Spatial);
+
+        // The player after the marker, and both at once.
+        let late_player = app.world_mut().spawn(Spatial).id();
+        app.update();
+        app.world_mut()
+            .entity_mut(late_player)
+            .insert(SamplePlayer::new(Handle::default()));
+        let together = app
+            .world_mut()
+            .spawn((SamplePlayer::new(Handle::default()), Spatial))
+            .id();
+
+        app.update();
+        // Reinserting either doesn't build a second chain.
+        app.world_mut()
+            .entity_mut(late_marker)
+            .insert((Spatial, SamplePlayer::new(Handle::default())));
+        app.update();
+
+        for emitter in [late_marker, late_player, together] {
+            assert_eq!(template_nodes(&mut app, emitter), 1);
+        }
+    }
+
+    #[test]
+    fn explicit_chains_are_left_alone() {
+        let mut app = app();
+
+        let together = app
+            .world_mut()
+            .spawn((
+                SamplePlayer::new(Handle::default()),
+                Spatial,
+                sample_effects![VolumeNode::default()],
+            ))
+            .id();
+        let late_marker = app
+            .world_mut()
+            .spawn((
+                SamplePlayer::new(Handle::default()),
+                sample_effects![VolumeNode::default()],
+            ))
+            .id();
+        app.update();
+        app.world_mut().entity_mut(late_marker).insert(Spatial);
+        app.update();
+
+        for emitter in [together, late_marker] {
+            assert_eq!(template_nodes(&mut app, emitter), 0);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Spreading large emitter spawns over several frames.
+//!
+//! Every HRTF node builds its processor when its chain is spawned, and
+//! each one loads or resamples a dataset. Spawning a hundred emitters in
+//! one frame stalls the main thread long enough to hitch the display
+//! and glitch the stream. Emitters queued in [`PendingEmitters`] are
+//! instead spawned a few at a time, within the limits in
+//! [`SpawnThrottle`], while a counter shows the progress.
+//!
+//! A queued emitter doesn't exist until it's spawned, and then it's
+//! spawned whole, so nothing half-constructed is ever seen or heard.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+use crate::timeline::TimelineTrack;
+use crate::{DemoAssets, DemoChain, spawn_one};
+
+pub struct SpawnThrottlePlugin;
+
+impl Plugin for SpawnThrottlePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpawnThrottle>()
+            .init_resource::<PendingEmitters>()
+            .add_systems(Update, (spawn_pending_emitters, update_progress).chain());
+    }
+}
+
+/// Limits on how many queued emitters are spawned per frame.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct SpawnThrottle {
+    /// The most emitters spawned in one frame.
+    pub per_frame: usize,
+    /// When the last frame took longer than this, only one emitter
+    /// is spawned, so a slow machine isn't pushed further behind.
+    pub frame_budget: Duration,
+}
+
+impl Default for SpawnThrottle {
+    fn default() -> Self {
+        Self {
+            per_frame: 8,
+            frame_budget: Duration::from_millis(20),
+        }
+    }
+}
+
+/// An emitter waiting to be spawned.
+#[derive(Debug, Clone, Copy)]
+pub struct EmitterDescriptor {
+    pub chain: DemoChain,
+    pub angle: f32,
+    pub volume: Volume,
+    pub track: Option<TimelineTrack>,
+}
+
+/// Emitters waiting to be spawned, oldest first.
+#[derive(Debug, Default, Resource)]
+pub struct PendingEmitters {
+    queue: VecDeque<EmitterDescriptor>,
+    /// Emitters spawned since the queue was last empty.
+    spawned: usize,
+}
+
+impl PendingEmitters {
+    pub fn push(&mut self, emitter: EmitterDescriptor) {
+        self.queue.push_back(emitter);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Drop every emitter that hasn't been spawned yet.
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.spawned = 0;
+    }
+}
+
+fn spawn_pending_emitters(
+    mut pending: ResMut<PendingEmitters>,
+    throttle: Res<SpawnThrottle>,
+    assets: Option<Res<DemoAssets>>,
+    server: Res<AssetServer>,
+    buses: Res<ReverbBuses>,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    let Some(assets) = assets else {
+        return;
+    };
+    if pending.queue.is_empty() {
+        return;
+    }
+
+    let limit = if time.delta() > throttle.frame_budget {
+        1
+    } else {
+        throttle.per_frame.max(1)
+    };
+
+    for _ in 0..limit {
+        let Some(emitter) = pending.queue.pop_front() else {
+            break;
+        };
+
+        let entity = spawn_one(
+            &mut commands,
+            &assets,
+            &server,
+            &buses,
+            emitter.chain,
+            emitter.angle,
+            emitter.volume,
+        );
+        if let Some(track) = emitter.track {
+            commands.entity(entity).insert(track);
+        }
+
+        pending.spawned += 1;
+    }
+
+    if pending.queue.is_empty() {
+        info!("spawned {} queued emitters", pending.spawned);
+        pending.spawned = 0;
+    }
+}
+
+#[derive(Component)]
+struct SpawnProgress;
+
+fn update_progress(
+    pending: Res<PendingEmitters>,
+    mut progress: Query<(Entity, &mut Text), With<SpawnProgress>>,
+    mut commands: Commands,
+) {
+    if !pending.is_changed() {
+        return;
+    }
+
+    let waiting = pending.queue.len();
+    if waiting == 0 {
+        for (entity, _) in progress.iter() {
+            commands.entity(entity).despawn();
+        }
+        return;
+    }
+
+    let text = format!(
+        "spawning emitters: {}/{}",
+        pending.spawned,
+        pending.spawned + waiting
+    );
+    match progress.single_mut() {
+        Ok((_, mut progress)) => progress.0 = text,
+        Err(_) => {
+            commands.spawn((
+                SpawnProgress,
+                Text::new(text),
+                TextFont::from_font_size(14.0),
+                Node {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(8.0),
+                    left: Val::Px(8.0),
+                    ..default()
+                },
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU32;
+    use std::time::Instant;
+
+    use firewheel::{
+        StreamInfo,
+        node::{AudioNode, ConstructProcessorContext, NodeID},
+    };
+
+    use super::*;
+
+    /// Build one emitter's processor, the main thread's share of a spawn.
+    fn construct<N: AudioNode>(node: N, config: &N::Configuration) {
+        let stream = StreamInfo {
+            sample_rate: NonZeroU32::new(48_000).unwrap(),
+            max_block_frames: NonZeroU32::new(256).unwrap(),
+            ..Default::default()
+        };
+
+        let processor = node.construct_processor(
+            config,
+            ConstructProcessorContext::new(NodeID::DANGLING, &stream, &mut None),
+        );
+        std::hint::black_box(processor);
+    }
+
+    fn construct_emitter(index: usize) {
+        let backends: &[fn()] = &[
+            #[cfg(feature = "sofar")]
+            || {
+                use crate::sofar_hrtf::{HrtfConfig, SofarHrtfNode};
+                construct(SofarHrtfNode::default(), &HrtfConfig::default())
+            },
+            #[cfg(feature = "fyrox")]
+            || {
+                use crate::fyrox_hrtf::{FyroxHrtfNode, HrtfConfig};
+                construct(FyroxHrtfNode::default(), &HrtfConfig::default())
+            },
+        ];
+
+        if !backends.is_empty() {
+            backends[index % backends.len()]();
+        }
+    }
+
+    /// The worst frame of processor construction for 128 emitters,
+    /// spawned at once and through the default [`SpawnThrottle`].
+    ///
+    /// Run with `cargo test --features sofar,fyrox frame_cost
+    /// -- --ignored --nocapture`. Rendering, asset loading and the
+    /// rest of the scene aren't included.
+    ///
+    /// On one core of a test machine, with half the emitters on each
+    /// backend, loading the datasets took about 170 ms. The worst frame
+    /// took about 3.1 s unthrottled and 200 to 290 ms throttled, most of
+    /// it spent decoding a sphere for every fyrox node.
+    #[test]
+    #[ignore = "a measurement, not a check"]
+    fn frame_cost_with_and_without_the_throttle() {
+        const EMITTERS: usize = 128;
+
+        // The datasets are loaded once, by whichever frame comes first.
+        let start = Instant::now();
+        for index in 0..2 {
+            construct_emitter(index);
+        }
+        let loading = start.elapsed();
+
+        let start = Instant::now();
+        for index in 0..EMITTERS {
+            construct_emitter(index);
+        }
+        let unthrottled = start.elapsed();
+
+        let per_frame = SpawnThrottle::default().per_frame;
+        let mut throttled = Duration::ZERO;
+        for frame in 0..EMITTERS.div_ceil(per_frame) {
+            let start = Instant::now();
+            for index in frame * per_frame..((frame + 1) * per_frame).min(EMITTERS) {
+                construct_emitter(index);
+            }
+            throttled = throttled.max(start.elapsed());
+        }
+
+        println!(
+            "dataset loading {:.1} ms, worst frame unthrottled {:.1} ms, \
+             throttled to {per_frame} per frame {:.1} ms",
+            loading.as_secs_f64() * 1000.0,
+            unthrottled.as_secs_f64() * 1000.0,
+            throttled.as_secs_f64() * 1000.0,
+        );
+    }
+}
@@ -0,0 +1,388 @@
+//! Recording and replay of emitter and listener trajectories.
+//!
+//! While recording, the transforms of every [`TimelineTrack`] are
+//! captured each frame into a bounded [`Timeline`]. While replaying,
+//! those transforms are written back at the scrub position, after
+//! all gameplay movement, so the spatial systems see exactly what
+//! they saw during the recording.
+//!
+//! | Key          | Action                       |
+//! | ------------ | ---------------------------- |
+//! | `R`          | Start or stop recording      |
+//! | `P`          | Start or stop replaying      |
+//! | `Space`      | Pause or resume the replay   |
+//! | `Left/Right` | Scrub backward or forward    |
+//! | `F5` / `F9`  | Save or load `timeline.bin`  |
+//!
+//! Saved timelines record the [`DemoRng`] seed, which is logged on load.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use bevy::{prelude::*, transform::TransformSystem};
+use bevy_seedling::prelude::SampleEffects;
+
+use crate::rng::DemoRng;
+use crate::spatial::{SpatialAudioCommand, SpatialAudioCommandKind};
+
+pub struct TimelinePlugin;
+
+impl Plugin for TimelinePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Timeline>()
+            .init_resource::<TimelineMode>()
+            .add_systems(Update, timeline_controls)
+            .add_systems(
+                PostUpdate,
+                (record_timeline, replay_timeline)
+                    .chain()
+                    .before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// Marks an entity whose transform is captured in the [`Timeline`].
+///
+/// The ID must be stable across runs so saved timelines can
+/// be matched back to the entities they were recorded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Component)]
+pub struct TimelineTrack(pub u32);
+
+/// The transforms of all tracks at a point in time.
+#[derive(Debug, Clone, Default)]
+pub struct TimelineFrame {
+    /// Seconds since the recording started.
+    pub time: f64,
+    pub transforms: Vec<(TimelineTrack, Transform)>,
+}
+
+/// A bounded recording of track transforms.
+#[derive(Debug, Resource)]
+pub struct Timeline {
+    /// Frames older than this many seconds are discarded.
+    ///
+    /// Defaults to five minutes.
+    pub max_duration: f64,
+    /// The seed of the run this was recorded in, if known.
+    pub seed: Option<u64>,
+    frames: VecDeque<TimelineFrame>,
+    start: f64,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self {
+            max_duration: 5.0 * 60.0,
+            seed: None,
+            frames: VecDeque::new(),
+            start: 0.0,
+        }
+    }
+}
+
+/// The current timeline mode.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Resource)]
+pub enum TimelineMode {
+    #[default]
+    Off,
+    Recording,
+    Replaying {
+        /// The replay position in seconds.
+        cursor: f64,
+        playing: bool,
+    },
+}
+
+impl Timeline {
+    /// The current timeline format version.
+    ///
+    /// Version 2 added the seed. Version 1 files still load, without one.
+    pub const VERSION: u32 = 2;
+    const MAGIC: &[u8; 4] = b"HRTL";
+
+    /// The time of the first and last frame.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        Some((self.frames.front()?.time, self.frames.back()?.time))
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn push(&mut self, frame: TimelineFrame) {
+        self.frames.push_back(frame);
+
+        while let (Some(first), Some(last)) = (self.frames.front(), self.frames.back()) {
+            if last.time - first.time <= self.max_duration {
+                break;
+            }
+
+            self.frames.pop_front();
+        }
+    }
+
+    /// Sample a track at `time`, interpolating between frames.
+    pub fn sample(&self, track: TimelineTrack, time: f64) -> Option<Transform> {
+        let next = self.frames.partition_point(|frame| frame.time < time);
+        let find = |frame: &TimelineFrame| {
+            frame
+                .transforms
+                .iter()
+                .find(|(t, _)| *t == track)
+                .map(|(_, transform)| *transform)
+        };
+
+        let after = self.frames.get(next);
+        let before = next.checked_sub(1).and_then(|i| self.frames.get(i));
+
+        match (before, after) {
+            (Some(before), Some(after)) => {
+                let (a, b) = (find(before)?, find(after)?);
+                let span = after.time - before.time;
+                let t = if span > 0.0 {
+                    ((time - before.time) / span) as f32
+                } else {
+                    0.0
+                };
+
+                Some(Transform {
+                    translation: a.translation.lerp(b.translation, t),
+                    rotation: a.rotation.slerp(b.rotation, t),
+                    scale: a.scale.lerp(b.scale, t),
+                })
+            }
+            (Some(frame), None) | (None, Some(frame)) => find(frame),
+            (None, None) => None,
+        }
+    }
+
+    /// Write the timeline in its versioned binary format.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+
+        file.write_all(Self::MAGIC)?;
+        file.write_all(&Self::VERSION.to_le_bytes())?;
+        file.write_all(&self.seed.unwrap_or_default().to_le_bytes())?;
+        file.write_all(&(self.frames.len() as u64).to_le_bytes())?;
+
+        for frame in &self.frames {
+            file.write_all(&frame.time.to_le_bytes())?;
+            file.write_all(&(frame.transforms.len() as u32).to_le_bytes())?;
+
+            for (track, transform) in &frame.transforms {
+                file.write_all(&track.0.to_le_bytes())?;
+                let values = transform
+                    .translation
+                    .to_array()
+                    .into_iter()
+                    .chain(transform.rotation.to_array())
+                    .chain(transform.scale.to_array());
+                for value in values {
+                    file.write_all(&value.to_le_bytes())?;
+                }
+            }
+        }
+
+        file.flush()
+    }
+
+    /// Read a timeline written by [`Timeline::save`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = io::BufReader::new(std::fs::File::open(path)?);
+
+        let mut magic = [0; 4];
+        file.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a timeline file",
+            ));
+        }
+
+        let version = read_u32(&mut file)?;
+        if !(1..=Self::VERSION).contains(&version) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported timeline version {version}"),
+            ));
+        }
+
+        let mut timeline = Self::default();
+        if version >= 2 {
+            timeline.seed = Some(read_u64(&mut file)?);
+        }
+
+        let frame_count = read_u64(&mut file)?;
+
+        for _ in 0..frame_count {
+            let time = f64::from_bits(read_u64(&mut file)?);
+            let transform_count = read_u32(&mut file)?;
+
+            let mut transforms = Vec::with_capacity(transform_count as usize);
+            for _ in 0..transform_count {
+                let track = TimelineTrack(read_u32(&mut file)?);
+
+                let mut values = [0f32; 10];
+                for value in &mut values {
+                    *value = f32::from_bits(read_u32(&mut file)?);
+                }
+
+                transforms.push((
+                    track,
+                    Transform {
+                        translation: Vec3::from_slice(&values[0..3]),
+                        rotation: Quat::from_slice(&values[3..7]),
+                        scale: Vec3::from_slice(&values[7..10]),
+                    },
+                ));
+            }
+
+            timeline
+                .frames
+                .push_back(TimelineFrame { time, transforms });
+        }
+
+        Ok(timeline)
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn timeline_controls(
+    input: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<TimelineMode>,
+    mut timeline: ResMut<Timeline>,
+    rng: Res<DemoRng>,
+    time: Res<Time>,
+    emitters: Query<Entity, (With<TimelineTrack>, With<SampleEffects>)>,
+    mut spatial_commands: EventWriter<SpatialAudioCommand>,
+) {
+    const TIMELINE_PATH: &str = "timeline.bin";
+    const SCRUB_SPEED: f64 = 2.0;
+
+    if input.just_pressed(KeyCode::KeyR) {
+        *mode = match *mode {
+            TimelineMode::Recording => TimelineMode::Off,
+            _ => {
+                timeline.clear();
+                timeline.start = time.elapsed_secs_f64();
+                timeline.seed = Some(rng.seed);
+                TimelineMode::Recording
+            }
+        };
+    }
+
+    if input.just_pressed(KeyCode::KeyP) {
+        *mode = match *mode {
+            TimelineMode::Replaying { .. } => TimelineMode::Off,
+            _ => {
+                // Emitters jump to the start of the recording.
+                for target in emitters.iter() {
+                    spatial_commands.write(SpatialAudioCommand {
+                        target,
+                        kind: SpatialAudioCommandKind::SnapDirection,
+                    });
+                }
+
+                TimelineMode::Replaying {
+                    cursor: timeline.range().map(|r| r.0).unwrap_or_default(),
+                    playing: true,
+                }
+            }
+        };
+    }
+
+    if input.just_pressed(KeyCode::F5) {
+        match timeline.save(TIMELINE_PATH) {
+            Ok(()) => info!("saved timeline to {TIMELINE_PATH}"),
+            Err(e) => error!("failed to save timeline: {e}"),
+        }
+    }
+
+    if input.just_pressed(KeyCode::F9) {
+        match Timeline::load(TIMELINE_PATH) {
+            Ok(loaded) => {
+                match loaded.seed {
+                    Some(seed) => {
+                        info!("loaded timeline from {TIMELINE_PATH}, recorded with --seed={seed}")
+                    }
+                    None => info!("loaded timeline from {TIMELINE_PATH}"),
+                }
+                *timeline = loaded;
+            }
+            Err(e) => error!("failed to load timeline: {e}"),
+        }
+    }
+
+    if let TimelineMode::Replaying { cursor, playing } = &mut *mode {
+        let delta = time.delta_secs_f64();
+
+        if input.just_pressed(KeyCode::Space) {
+            *playing = !*playing;
+        }
+
+        if *playing {
+            *cursor += delta;
+        }
+        if input.pressed(KeyCode::ArrowLeft) {
+            *cursor -= delta * SCRUB_SPEED;
+        }
+        if input.pressed(KeyCode::ArrowRight) {
+            *cursor += delta * SCRUB_SPEED;
+        }
+
+        if let Some((start, end)) = timeline.range() {
+            *cursor = cursor.clamp(start, end);
+        }
+    }
+}
+
+fn record_timeline(
+    tracks: Query<(&TimelineTrack, &Transform)>,
+    mode: Res<TimelineMode>,
+    mut timeline: ResMut<Timeline>,
+    time: Res<Time>,
+) {
+    if *mode != TimelineMode::Recording {
+        return;
+    }
+
+    let frame = TimelineFrame {
+        time: time.elapsed_secs_f64() - timeline.start,
+        transforms: tracks
+            .iter()
+            .map(|(t, transform)| (*t, *transform))
+            .collect(),
+    };
+
+    timeline.push(frame);
+}
+
+fn replay_timeline(
+    mut tracks: Query<(&TimelineTrack, &mut Transform)>,
+    mode: Res<TimelineMode>,
+    timeline: Res<Timeline>,
+) {
+    let TimelineMode::Replaying { cursor, .. } = *mode else {
+        return;
+    };
+
+    for (track, mut transform) in tracks.iter_mut() {
+        if let Some(recorded) = timeline.sample(*track, cursor) {
+            *transform = recorded;
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! A gizmo trail of each emitter's recent path.
+//!
+//! Press [`DirectionTrailSettings::key`] to toggle it. While enabled,
+//! each HRTF effect records its emitter's position every
+//! [`interval`](DirectionTrailSettings::interval) seconds, along with
+//! how far the direction its processor applied lagged behind the one
+//! it was sent. Points are drawn from green, in sync, to red, lagging by
+//! [`FULL_LAG`] or more, which makes smoothing and extrapolation
+//! visible over time rather than for a single frame.
+//!
+//! A trail is cleared when its emitter snaps to a new direction, so
+//! teleports don't draw a line across the scene. Only nodes with an
+//! applied-direction cell are tracked, which by default means debug
+//! builds.
+
+use std::collections::VecDeque;
+
+use bevy::{
+    color::palettes::css::{GREEN, RED},
+    prelude::*,
+};
+use bevy_seedling::prelude::*;
+
+use crate::spatial::{HrtfNode, HrtfNodeConfig, SpatialAudioCommand, SpatialAudioCommandKind};
+
+/// The lag, in radians, drawn fully red.
+pub const FULL_LAG: f32 = 30.0 * core::f32::consts::PI / 180.0;
+
+/// Configures the direction trail.
+#[derive(Debug, Clone, Copy, Resource)]
+pub struct DirectionTrailSettings {
+    pub enabled: bool,
+    /// The most points kept per emitter.
+    pub len: usize,
+    /// Seconds between recorded points.
+    pub interval: f32,
+    /// The key that toggles the trail.
+    pub key: KeyCode,
+}
+
+impl Default for DirectionTrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            len: 120,
+            interval: 1.0 / 30.0,
+            key: KeyCode::F3,
+        }
+    }
+}
+
+/// An HRTF effect's recorded trail.
+#[derive(Debug, Default, Component)]
+pub struct DirectionTrail {
+    /// Emitter positions and the lag at each, oldest first.
+    points: VecDeque<(Vec3, f32)>,
+    since_last: f32,
+}
+
+/// Record the trail of every `N` node.
+pub fn record_direction_trail<N: HrtfNode>(
+    mut nodes: Query<(
+        Entity,
+        &N,
+        &N::Configuration,
+        &EffectOf,
+        Option<&mut DirectionTrail>,
+    )>,
+    emitters: Query<&GlobalTransform>,
+    mut snaps: EventReader<SpatialAudioCommand>,
+    settings: Res<DirectionTrailSettings>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    if !settings.enabled {
+        snaps.clear();
+        return;
+    }
+
+    let snapped: Vec<Entity> = snaps
+        .read()
+        .filter(|command| command.kind == SpatialAudioCommandKind::SnapDirection)
+        .map(|command| command.target)
+        .collect();
+
+    for (entity, node, config, effect_of, trail) in nodes.iter_mut() {
+        let Some(applied) = config.applied_direction() else {
+            continue;
+        };
+
+        let Some(mut trail) = trail else {
+            commands.entity(entity).insert(DirectionTrail::default());
+            continue;
+        };
+
+        if snapped.contains(&entity) || snapped.contains(&effect_of.0) {
+            trail.points.clear();
+        }
+
+        trail.since_last += time.delta_secs();
+        if trail.since_last < settings.interval {
+            continue;
+        }
+        trail.since_last = 0.0;
+
+        let Ok(transform) = emitters.get(effect_of.0) else {
+            continue;
+        };
+
+        let commanded = node.direction();
+        let applied = applied.load();
+        let lag = if commanded == Vec3::ZERO || applied == Vec3::ZERO {
+            0.0
+        } else {
+            commanded.angle_between(applied)
+        };
+
+        trail.points.push_back((transform.translation(), lag));
+        while trail.points.len() > settings.len {
+            trail.points.pop_front();
+        }
+    }
+}
+
+/// Draw every recorded trail.
+pub fn draw_direction_trails(
+    trails: Query<&DirectionTrail>,
+    settings: Res<DirectionTrailSettings>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.enabled {
+        return;
+    }
+
+    for trail in trails.iter() {
+        gizmos.linestrip_gradient(trail.points.iter().map(|(position, lag)| {
+            let lag = (lag / FULL_LAG).min(1.0);
+            (*position, Color::from(GREEN).mix(&Color::from(RED), lag))
+        }));
+    }
+}
+
+/// Toggle the trail, forgetting what was recorded when it's hidden.
+pub fn toggle_direction_trail(
+    input: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<DirectionTrailSettings>,
+    mut trails: Query<&mut DirectionTrail>,
+) {
+    if !input.just_pressed(settings.key) {
+        return;
+    }
+
+    settings.enabled = !settings.enabled;
+    if !settings.enabled {
+        for mut trail in trails.iter_mut() {
+            trail.points.clear();
+        }
+    }
+}
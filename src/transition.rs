@@ -0,0 +1,243 @@
+//! Click-free scene changes.
+//!
+//! Despawning and respawning many emitters at once cuts their audio
+//! mid-waveform and brings the new chains in abruptly, which adds up
+//! to an audible dip and pop. Changes queued with
+//! [`TransitionSceneExt::transition_scene`] instead go through one
+//! path:
+//!
+//! 1. The main bus fades out over 150 ms.
+//! 2. Every queued change runs while it's silent.
+//! 3. The coordinator waits until queued emitters are spawned, their
+//!    nodes are in the audio graph and every playing sample has
+//!    rendered its first block, or a second at most.
+//! 4. The main bus fades back in.
+
+use std::collections::VecDeque;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_seedling::{node::FirewheelNode, pool::Sampler, prelude::*, sample::Sample};
+
+use crate::state::MainBusFade;
+use crate::throttle::PendingEmitters;
+
+pub struct SceneTransitionPlugin;
+
+impl Plugin for SceneTransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SceneTransition>()
+            .add_systems(Update, drive_transition);
+    }
+}
+
+/// The longest a transition stays silent waiting for new emitters.
+const READY_TIMEOUT_SECONDS: f32 = 1.0;
+
+type SceneChange = Box<dyn FnOnce(&mut Commands) + Send + Sync>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum TransitionPhase {
+    #[default]
+    Idle,
+    FadingOut,
+    /// The changes ran this long ago, in seconds.
+    Waiting(f32),
+}
+
+/// Scene changes waiting for the main bus to fade out.
+#[derive(Default, Resource)]
+pub struct SceneTransition {
+    phase: TransitionPhase,
+    changes: VecDeque<SceneChange>,
+}
+
+impl SceneTransition {
+    /// Whether a transition is underway.
+    pub fn is_active(&self) -> bool {
+        self.phase != TransitionPhase::Idle || !self.changes.is_empty()
+    }
+}
+
+pub trait TransitionSceneExt {
+    /// Run `change` while the main bus is faded out.
+    fn transition_scene(&mut self, change: impl FnOnce(&mut Commands) + Send + Sync + 'static);
+}
+
+impl TransitionSceneExt for Commands<'_, '_> {
+    fn transition_scene(&mut self, change: impl FnOnce(&mut Commands) + Send + Sync + 'static) {
+        self.queue(move |world: &mut World| {
+            world
+                .resource_mut::<SceneTransition>()
+                .changes
+                .push_back(Box::new(change));
+        });
+    }
+}
+
+/// Whether the audio for every emitter is ready to be heard.
+#[derive(SystemParam)]
+struct EmittersReady<'w, 's> {
+    players: Query<
+        'w,
+        's,
+        (
+            &'static SamplePlayer,
+            &'static PlaybackSettings,
+            Option<&'static Sampler>,
+        ),
+    >,
+    unacquired: Query<'w, 's, (), (With<EffectOf>, Without<FirewheelNode>)>,
+    samples: Res<'w, Assets<Sample>>,
+    pending: Res<'w, PendingEmitters>,
+}
+
+impl EmittersReady<'_, '_> {
+    /// Playing samples are ready once their sampler's playhead has
+    /// moved, that is once they've rendered a block, and paused
+    /// ones once they're loaded.
+    fn ready(&self) -> bool {
+        self.pending.is_empty()
+            && self.unacquired.is_empty()
+            && self
+                .players
+                .iter()
+                .all(|(player, settings, sampler)| match *settings.playback {
+                    PlaybackState::Play { .. } => sampler
+                        .and_then(Sampler::try_playhead_frames)
+                        .is_some_and(|frames| frames > 0),
+                    _ => self.samples.contains(&player.sample),
+                })
+    }
+}
+
+fn drive_transition(
+    mut transition: ResMut<SceneTransition>,
+    mut fade: ResMut<MainBusFade>,
+    emitters: EmittersReady,
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+) {
+    match transition.phase {
+        TransitionPhase::Idle => {
+            if !transition.changes.is_empty() {
+                fade.set_transition_target(0.0);
+                transition.phase = TransitionPhase::FadingOut;
+            }
+        }
+        TransitionPhase::FadingOut => {
+            if fade.transition() == 0.0 {
+                for change in transition.changes.drain(..) {
+                    change(&mut commands);
+                }
+                transition.phase = TransitionPhase::Waiting(0.0);
+            }
+        }
+        TransitionPhase::Waiting(elapsed) => {
+            // Changes queued while silent can run right away.
+            if !transition.changes.is_empty() {
+                for change in transition.changes.drain(..) {
+                    change(&mut commands);
+                }
+                transition.phase = TransitionPhase::Waiting(0.0);
+                return;
+            }
+
+            // The changes' commands are applied after the first frame.
+            let elapsed = elapsed + time.delta_secs();
+            if elapsed > time.delta_secs() && emitters.ready() || elapsed >= READY_TIMEOUT_SECONDS {
+                fade.set_transition_target(1.0);
+                transition.phase = TransitionPhase::Idle;
+            } else {
+                transition.phase = TransitionPhase::Waiting(elapsed);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "null-backend"))]
+mod tests {
+    use core::f32::consts::TAU;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::null_backend::{NullAudioBackend, NullBackendConfig};
+    use crate::state::fade_main_bus;
+
+    /// A second of a 220 Hz tone, which loops seamlessly, starting at `phase`.
+    fn tone(app: &mut App, phase: f32) -> Handle<Sample> {
+        let tone: Vec<f32> = (0..48_000)
+            .map(|frame| 0.5 * (TAU * 220.0 * frame as f32 / 48_000.0 + phase).sin())
+            .collect();
+
+        app.world_mut()
+            .resource_mut::<Assets<Sample>>()
+            .add(Sample::new(vec![tone]))
+    }
+
+    /// Update `app` for `seconds` of wall-clock time, which
+    /// the null backend renders at.
+    fn run_for(app: &mut App, seconds: f32) {
+        let start = std::time::Instant::now();
+        while start.elapsed().as_secs_f32() < seconds {
+            app.update();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn transition_has_no_discontinuities() {
+        let config = NullBackendConfig::default();
+        let output = config.output.clone();
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin::default(),
+            SeedlingPlugin::<NullAudioBackend> {
+                stream_config: config,
+                ..SeedlingPlugin::new()
+            },
+            SceneTransitionPlugin,
+        ))
+        .init_resource::<MainBusFade>()
+        .init_resource::<PendingEmitters>()
+        .add_systems(Update, fade_main_bus.after(drive_transition));
+        app.finish();
+        app.cleanup();
+
+        // Both tones are at their loudest where they'd be cut or start.
+        let before = tone(&mut app, 0.0);
+        let after = tone(&mut app, TAU / 4.0);
+        let old = app
+            .world_mut()
+            .spawn(SamplePlayer::new(before).looping())
+            .id();
+        run_for(&mut app, 0.3);
+
+        app.world_mut()
+            .commands()
+            .transition_scene(move |commands| {
+                commands.entity(old).despawn();
+                commands.spawn(SamplePlayer::new(after).looping());
+            });
+        app.update();
+        while app.world().resource::<SceneTransition>().is_active() {
+            run_for(&mut app, 0.01);
+        }
+        run_for(&mut app, 0.3);
+
+        let output = output.take();
+        let left: Vec<_> = output.iter().step_by(2).copied().collect();
+        assert!(
+            left.iter().any(|sample| sample.abs() > 0.4),
+            "no audio was rendered"
+        );
+
+        // The tone itself moves at most 0.5 * TAU * 220 / 48000 per frame.
+        let worst = left
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(worst < 0.05, "a {worst} jump between frames");
+    }
+}
@@ -0,0 +1,178 @@
+//! Catching emitters that were spawned without a spatializer.
+//!
+//! A positioned [`SamplePlayer`] whose effect chain has no
+//! [`Spatializer`] plays dry, which is easy to miss when a
+//! `sample_effects!` block is forgotten or stripped by a cfg. Each such
+//! emitter is checked once while a listener exists. In debug builds a
+//! warning names it, and emitters marked [`AutoSpatialize`] get the
+//! demo's default chain inserted.
+
+use bevy::{ecs::name::NameOrEntity, prelude::*};
+use bevy_seedling::prelude::*;
+
+use crate::bus::ReverbBuses;
+
+pub struct SpatialValidationPlugin;
+
+impl Plugin for SpatialValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, validate_emitters)
+            // bevy_seedling's own panner spatializes too.
+            .register_required_components::<SpatialBasicNode, Spatializer>();
+    }
+}
+
+/// Marks a node that spatializes its input.
+///
+/// Each backend registers this as a required component of its node,
+/// so any effect chain containing one is considered spatialized.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct Spatializer;
+
+/// Insert the default effect chain on this emitter
+/// if it's spawned without a spatializer.
+#[derive(Debug, Default, Clone, Copy, Component)]
+pub struct AutoSpatialize;
+
+/// Set once an emitter's chain has been checked.
+#[derive(Component)]
+struct ChainChecked;
+
+fn validate_emitters(
+    emitters: Query<
+        (NameOrEntity, Option<&SampleEffects>, Has<AutoSpatialize>),
+        (With<SamplePlayer>, With<Transform>, Without<ChainChecked>),
+    >,
+    listeners: Query<(), Or<(With<SpatialListener2D>, With<SpatialListener3D>)>>,
+    spatializers: Query<(), With<Spatializer>>,
+    buses: Res<ReverbBuses>,
+    chain: Res<crate::DemoChain>,
+    mut commands: Commands,
+) {
+    if listeners.is_empty() {
+        return;
+    }
+
+    for (emitter, effects, auto) in emitters.iter() {
+        commands.entity(emitter.entity).insert(ChainChecked);
+
+        let spatialized = effects
+            .is_some_and(|effects| effects.iter().any(|effect| spatializers.contains(effect)));
+        if spatialized {
+            continue;
+        }
+
+        if auto {
+            info!("inserting the default spatial chain on {emitter}");
+            crate::spawn_default_chain(&mut commands.entity(emitter.entity), &buses, *chain);
+        } else if cfg!(debug_assertions) {
+            warn!("{emitter} has a transform but no spatializer in its effect chain");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DemoChain;
+    use crate::bus::{ReverbBus, ReverbSend, register_bus};
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, SpatialValidationPlugin))
+            .init_resource::<ReverbBuses>()
+            .insert_resource(DemoChain::Panner)
+            .add_observer(register_bus);
+        app.world_mut().spawn(ReverbBus::MAIN);
+
+        app
+    }
+
+    fn listen(app: &mut App) {
+        app.world_mut()
+            .spawn((SpatialListener3D, Transform::default()));
+    }
+
+    fn emitter(app: &mut App, extra: impl Bundle) -> Entity {
+        app.world_mut()
+            .spawn((
+                SamplePlayer::new(Handle::default()),
+                Transform::default(),
+                extra,
+            ))
+            .id()
+    }
+
+    /// The entities in `emitter`'s effect chain.
+    fn chain(app: &App, emitter: Entity) -> Vec<Entity> {
+        app.world()
+            .get::<SampleEffects>(emitter)
+            .map(|effects| effects.iter().collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn unspatialized_emitters_are_flagged_once() {
+        let mut app = app();
+        listen(&mut app);
+        let dry = emitter(&mut app, ());
+        app.update();
+
+        assert!(app.world().get::<ChainChecked>(dry).is_some());
+        assert!(
+            chain(&app, dry).is_empty(),
+            "only auto-spatialized emitters are fixed"
+        );
+
+        // A spatialized emitter is checked and left as it is.
+        let spatialized = app
+            .world_mut()
+            .spawn((
+                SamplePlayer::new(Handle::default()),
+                Transform::default(),
+                sample_effects![SpatialBasicNode::default()],
+            ))
+            .id();
+        app.update();
+
+        assert!(app.world().get::<ChainChecked>(spatialized).is_some());
+        assert_eq!(chain(&app, spatialized).len(), 1);
+    }
+
+    #[test]
+    fn emitters_wait_for_a_listener() {
+        let mut app = app();
+        let dry = emitter(&mut app, AutoSpatialize);
+        app.update();
+        assert!(app.world().get::<ChainChecked>(dry).is_none());
+        assert!(chain(&app, dry).is_empty());
+
+        listen(&mut app);
+        app.update();
+        assert!(app.world().get::<ChainChecked>(dry).is_some());
+        assert!(!chain(&app, dry).is_empty());
+    }
+
+    #[test]
+    fn auto_spatialize_inserts_the_default_chain() {
+        let mut app = app();
+        listen(&mut app);
+        let dry = emitter(&mut app, AutoSpatialize);
+        app.update();
+
+        let chain = chain(&app, dry);
+        let world = app.world();
+        assert!(
+            chain
+                .iter()
+                .any(|effect| world.get::<Spatializer>(*effect).is_some())
+        );
+
+        // The reverb send goes to the registered main bus.
+        let sends: Vec<_> = chain
+            .iter()
+            .filter_map(|effect| world.get::<ReverbSend>(*effect))
+            .collect();
+        assert_eq!(sends, [&ReverbSend(ReverbBus::MAIN.0)]);
+    }
+}
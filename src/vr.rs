@@ -0,0 +1,57 @@
+//! Emitters carried by VR controllers.
+//!
+//! A controller's pose comes from the VR runtime, usually every frame
+//! and often ahead of Bevy's transform propagation, so emitters marked
+//! [`VrControllerEmitter`] are spatialized from that pose directly
+//! instead of their [`GlobalTransform`]. The listener is whichever
+//! listener is closest, normally the one on the headset.
+
+use bevy::prelude::*;
+use bevy_seedling::prelude::*;
+
+use crate::spatial::{HrtfNode, Listeners, closest_listener};
+
+/// Which hand holds a controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[expect(dead_code, reason = "the demo has no VR input")]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// An emitter whose position is a VR controller's pose.
+///
+/// The pose is in the same space as the listener's [`GlobalTransform`].
+/// Write it whenever the VR runtime reports a new one.
+#[derive(Debug, Clone, Copy, Component)]
+#[expect(dead_code, reason = "the demo has no VR input")]
+pub struct VrControllerEmitter {
+    pub hand: Hand,
+    pub pose: Transform,
+}
+
+/// Point `N` nodes on controller emitters at their controller.
+///
+/// [`update_hrtf_effects`](crate::spatial::update_hrtf_effects)
+/// leaves these emitters alone.
+pub fn update_vr_controller_hrtf<N: HrtfNode>(
+    listeners: Listeners,
+    mut nodes: Query<(&mut N, &EffectOf)>,
+    controllers: Query<&VrControllerEmitter>,
+) {
+    for (mut node, effect_of) in nodes.iter_mut() {
+        let Ok(controller) = controllers.get(effect_of.0) else {
+            continue;
+        };
+
+        let position = controller.pose.translation;
+        let Some(listener) = closest_listener(&listeners, position) else {
+            continue;
+        };
+
+        let direction = listener.relative_direction(position - listener.translation());
+        if !node.direction().abs_diff_eq(direction, 1e-4) {
+            node.set_direction(direction);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+//! Minimal WAV file writing.
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+/// Write interleaved `f32` samples to a 32-bit float WAV file.
+pub fn write_wav(
+    path: impl AsRef<Path>,
+    channels: u16,
+    sample_rate: u32,
+    samples: &[f32],
+) -> io::Result<()> {
+    let mut file = io::BufWriter::new(std::fs::File::create(path)?);
+
+    const FORMAT_IEEE_FLOAT: u16 = 3;
+    const BYTES_PER_SAMPLE: u16 = 4;
+
+    let data_len = (samples.len() * BYTES_PER_SAMPLE as usize) as u32;
+    let block_align = channels * BYTES_PER_SAMPLE;
+
+    file.write_all(b"RIFF")?;
+    file.write_all(&(36 + data_len).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    file.write_all(&FORMAT_IEEE_FLOAT.to_le_bytes())?;
+    file.write_all(&channels.to_le_bytes())?;
+    file.write_all(&sample_rate.to_le_bytes())?;
+    file.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    file.write_all(&block_align.to_le_bytes())?;
+    file.write_all(&(BYTES_PER_SAMPLE * 8).to_le_bytes())?;
+
+    file.write_all(b"data")?;
+    file.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        file.write_all(&sample.to_le_bytes())?;
+    }
+
+    file.flush()
+}